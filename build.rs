@@ -13,6 +13,7 @@ fn main() {
         || target_os == "freebsd"
         || target_os == "openbsd"
         || target_os == "netbsd"
+        || target_os == "dragonfly"
     {
         #[cfg(feature = "bindgen")]
         build_wrapper();
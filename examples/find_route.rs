@@ -6,7 +6,7 @@ use std::time::Duration;
 pub fn main() {
     let net: IpAddr = "192.168.4.0".parse().unwrap();
     let ip: IpAddr = "192.168.4.10".parse().unwrap();
-    let mut manager = RouteManager::new().unwrap();
+    let manager = RouteManager::new().unwrap();
     let find_route = manager.find_route(&ip).unwrap();
     println!("find route: {ip} -> {find_route:?}");
     // Need to set up the correct gateway
@@ -0,0 +1,6 @@
+pub fn main() {
+    let vec = route_manager::list_interfaces().unwrap();
+    for x in vec {
+        println!("{x}");
+    }
+}
@@ -22,7 +22,7 @@ pub fn main() {
     });
     // Need to set up the correct gateway
     let route = Route::new("192.168.2.0".parse().unwrap(), 24).with_if_index(1);
-    let mut manager = RouteManager::new().unwrap();
+    let manager = RouteManager::new().unwrap();
 
     let result = manager.add(&route);
     println!("route add {route} {result:?}");
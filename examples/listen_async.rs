@@ -15,7 +15,7 @@ pub async fn main() {
     });
     // Need to set up the correct gateway
     let route = Route::new("192.168.2.0".parse().unwrap(), 24).with_if_index(1);
-    let mut manager = AsyncRouteManager::new().unwrap();
+    let manager = AsyncRouteManager::new().unwrap();
     let result = manager.add(&route).await;
     println!("route add {route} {result:?}");
     tokio::time::sleep(Duration::from_secs(1)).await;
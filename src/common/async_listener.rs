@@ -0,0 +1,50 @@
+use crate::{AsyncRouteListener, RouteChange};
+use std::future::{poll_fn, Future};
+use std::io;
+use std::pin::pin;
+use std::task::Poll;
+use std::time::Duration;
+
+impl AsyncRouteListener {
+    /// Asynchronously listens for a route change event, waiting at most `timeout`.
+    ///
+    /// Fails with `TimedOut` if no event arrives in time. Like [`listen`](Self::listen), this
+    /// is cancel safe: events received before the timeout fires stay buffered for the next call.
+    pub async fn listen_with_timeout(&mut self, timeout: Duration) -> io::Result<RouteChange> {
+        with_timeout(Some(timeout), self.listen()).await
+    }
+}
+
+/// Runs `fut`, failing with `TimedOut` if it does not complete within `timeout`.
+pub(crate) async fn with_timeout<T>(
+    timeout: Option<Duration>,
+    fut: impl Future<Output = io::Result<T>>,
+) -> io::Result<T> {
+    #[cfg(feature = "async")]
+    let timer = timeout.map(tokio::time::sleep);
+    #[cfg(all(feature = "async_io", not(feature = "async")))]
+    let timer = timeout.map(async_io::Timer::after);
+    timeout_with(timer, fut).await
+}
+
+/// Runs `fut`, failing with `TimedOut` if `timer` completes first.
+pub(crate) async fn timeout_with<T>(
+    timer: Option<impl Future>,
+    fut: impl Future<Output = io::Result<T>>,
+) -> io::Result<T> {
+    let Some(timer) = timer else {
+        return fut.await;
+    };
+    let mut timer = pin!(timer);
+    let mut fut = pin!(fut);
+    poll_fn(|cx| {
+        if let Poll::Ready(rs) = fut.as_mut().poll(cx) {
+            return Poll::Ready(rs);
+        }
+        if timer.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(Err(io::Error::from(io::ErrorKind::TimedOut)));
+        }
+        Poll::Pending
+    })
+    .await
+}
@@ -0,0 +1,59 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+/// Number of idle buffers a pool keeps.
+const POOL_LEN: usize = 4;
+
+/// Receive buffers shared by the clones of a manager, so that calls running at the same time
+/// each get their own and later calls reuse them.
+pub(crate) struct BufPool {
+    bufs: Mutex<Vec<Vec<u8>>>,
+    len: usize,
+}
+
+/// A buffer taken from a [`BufPool`], returned to it on drop.
+pub(crate) struct PooledBuf<'a> {
+    pool: &'a BufPool,
+    buf: Vec<u8>,
+}
+
+impl BufPool {
+    /// Creates a pool whose new buffers are `len` zeroed bytes long.
+    pub(crate) fn new(len: usize) -> Self {
+        Self {
+            bufs: Mutex::new(Vec::new()),
+            len,
+        }
+    }
+    /// Takes an idle buffer, or allocates one if all are in use.
+    pub(crate) fn take(&self) -> PooledBuf<'_> {
+        let buf = self.bufs.lock().unwrap().pop();
+        PooledBuf {
+            pool: self,
+            buf: buf.unwrap_or_else(|| vec![0; self.len]),
+        }
+    }
+}
+
+impl Deref for PooledBuf<'_> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.buf
+    }
+}
+
+impl DerefMut for PooledBuf<'_> {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buf
+    }
+}
+
+impl Drop for PooledBuf<'_> {
+    fn drop(&mut self) {
+        let mut bufs = self.pool.bufs.lock().unwrap();
+        if bufs.len() < POOL_LEN {
+            bufs.push(std::mem::take(&mut self.buf));
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use crate::{Route, RouteManager};
+use std::borrow::Cow;
+use std::io;
+#[cfg(unix)]
+use std::time::Duration;
+
+/// Builder for a [`RouteManager`] with defaults for the routes it operates on, returned by
+/// [`RouteManager::builder`].
+///
+/// The defaults apply to every route passed to [`add`](RouteManager::add),
+/// [`delete`](RouteManager::delete) and [`update`](RouteManager::update) that does not set
+/// the attribute itself, so that an application working on one table does not have to
+/// repeat it at every call site.
+#[derive(Clone, Debug, Default)]
+pub struct RouteManagerBuilder {
+    #[cfg(target_os = "linux")]
+    pub(crate) table: Option<u8>,
+    #[cfg(target_os = "linux")]
+    protocol: Option<u8>,
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    metric: Option<u32>,
+    pub(crate) owner: Option<u32>,
+    #[cfg(unix)]
+    pub(crate) timeout: Option<Duration>,
+    normalize_mapped: bool,
+    pub(crate) retry: Option<crate::RetryPolicy>,
+}
+
+impl RouteManager {
+    /// Returns a builder for a manager with route defaults.
+    pub fn builder() -> RouteManagerBuilder {
+        RouteManagerBuilder::default()
+    }
+}
+
+impl RouteManagerBuilder {
+    /// (Linux only) Sets the routing table of routes that leave it unset (0).
+    #[cfg(target_os = "linux")]
+    pub fn with_table(mut self, table: u8) -> Self {
+        self.table = Some(table);
+        self
+    }
+    /// (Linux only) Sets the routing protocol (`RTPROT_*`) of routes without one.
+    #[cfg(target_os = "linux")]
+    pub fn with_protocol(mut self, protocol: u8) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+    /// (Windows/Linux) Sets the metric of routes without one.
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    pub fn with_metric(mut self, metric: u32) -> Self {
+        self.metric = Some(metric);
+        self
+    }
+    /// Tags the routes the manager adds as owned by the application, so that
+    /// [`flush_owned`](RouteManager::flush_owned) can remove them later, e.g. after a crash.
+    ///
+    /// The tag is the routing protocol (`RTPROT_*`, above `RTPROT_STATIC` and up to 255) on
+    /// Linux, set on routes without an explicit protocol; the route protocol
+    /// (`NL_ROUTE_PROTOCOL`) on Windows; and one or more of the `RTF_PROTO1`..`RTF_PROTO3`
+    /// flags on BSD (`RTF_PROTO3` is missing on NetBSD). Pick a value that no other software
+    /// on the host uses; [`build`](Self::build) rejects 0 and the values above.
+    pub fn with_owner(mut self, tag: u32) -> Self {
+        self.owner = Some(tag);
+        self
+    }
+    /// (Unix only) Bounds each wait for a reply of the kernel, so that a request whose reply
+    /// never arrives fails with `TimedOut` instead of blocking forever. The timeout is rounded
+    /// up to a microsecond.
+    ///
+    /// The socket of a request that timed out is closed, so that its late reply is never taken
+    /// for that of another request.
+    #[cfg(unix)]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout.max(Duration::from_micros(1)));
+        self
+    }
+    /// Sets whether routes are converted with [`Route::to_canonical`] before they are
+    /// installed or deleted, so that IPv4-mapped IPv6 destinations (`::ffff:0:0/96`) become
+    /// IPv4 routes (default `false`, as such routes are valid IPv6 routes on some platforms).
+    ///
+    /// Nexthops in the mapped form are converted to IPv4 for IPv4 routes either way.
+    pub fn with_normalize_mapped(mut self, normalize: bool) -> Self {
+        self.normalize_mapped = normalize;
+        self
+    }
+    /// Retries `add`, `delete`, `update` and `list` when they fail with a transient error,
+    /// under `policy`; see [`RetryPolicy`](crate::RetryPolicy).
+    pub fn with_retry_policy(mut self, policy: crate::RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+    /// Creates the manager.
+    ///
+    /// Fails with `InvalidInput` if the owner tag could also match routes the manager did not
+    /// add; see [`with_owner`](Self::with_owner).
+    pub fn build(self) -> io::Result<RouteManager> {
+        if let Some(owner) = self.owner {
+            check_owner(owner)?;
+        }
+        RouteManager::with_defaults(self)
+    }
+    /// Returns `route` with the defaults filled in.
+    pub(crate) fn apply<'a>(&self, route: &'a Route) -> Cow<'a, Route> {
+        #[allow(unused_mut)]
+        let mut route = route.normalized(self.normalize_mapped);
+        #[cfg(target_os = "linux")]
+        {
+            if let (Some(table), 0) = (self.table, route.table) {
+                route.to_mut().table = table;
+            }
+            let protocol = self.owner.map(|v| v as u8).or(self.protocol);
+            if let (Some(protocol), None) = (protocol, route.protocol) {
+                route.to_mut().protocol = Some(protocol);
+            }
+        }
+        #[cfg(any(target_os = "windows", target_os = "linux"))]
+        if let (Some(metric), None) = (self.metric, route.metric) {
+            route.to_mut().metric = Some(metric);
+        }
+        #[cfg(any(
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "macos",
+            target_os = "netbsd",
+            target_os = "dragonfly"
+        ))]
+        if let Some(owner) = self.owner {
+            route.to_mut().raw_flags |= owner;
+        }
+        route
+    }
+    /// Whether `route`, as read from the system, carries the owner tag.
+    fn is_owned(&self, route: &Route) -> bool {
+        let Some(owner) = self.owner else {
+            return false;
+        };
+        #[cfg(target_os = "linux")]
+        return route.protocol == Some(owner as u8);
+        #[cfg(windows)]
+        return route
+            .reported
+            .row
+            .as_ref()
+            .is_some_and(|v| v.protocol == owner as i32);
+        #[cfg(any(
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "macos",
+            target_os = "netbsd",
+            target_os = "dragonfly"
+        ))]
+        return route.reported.flags.is_some_and(|v| v & owner == owner);
+        #[cfg(not(any(
+            windows,
+            target_os = "linux",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "macos",
+            target_os = "netbsd",
+            target_os = "dragonfly"
+        )))]
+        {
+            let _ = (route, owner);
+            false
+        }
+    }
+}
+
+/// Checks that routes tagged with `owner` can only have been added by an owning manager.
+fn check_owner(owner: u32) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    let (valid, expected) = (
+        owner > libc::RTPROT_STATIC as u32 && owner <= u8::MAX as u32,
+        "a routing protocol number above RTPROT_STATIC (5-255)",
+    );
+    #[cfg(any(
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "macos",
+        target_os = "netbsd",
+        target_os = "dragonfly"
+    ))]
+    let (valid, expected) = (
+        owner != 0 && owner & !crate::OWNER_FLAGS == 0,
+        "a combination of the RTF_PROTO* flags",
+    );
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "macos",
+        target_os = "netbsd",
+        target_os = "dragonfly"
+    )))]
+    let (valid, expected) = (owner != 0, "non-zero");
+    if valid {
+        return Ok(());
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("owner tag {owner} is not {expected}"),
+    ))
+}
+
+impl RouteManager {
+    /// Deletes every route tagged with the owner tag of the manager (see
+    /// [`RouteManagerBuilder::with_owner`]) and returns them, e.g. to clean up after a
+    /// previous run that crashed. Routes of other software are left alone.
+    ///
+    /// Fails with `InvalidInput` if the manager has no owner tag.
+    pub fn flush_owned(&self) -> io::Result<Vec<Route>> {
+        let defaults = self.defaults().clone();
+        if defaults.owner.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the route manager has no owner tag",
+            ));
+        }
+        let mut flushed = Vec::new();
+        for route in self.list()? {
+            if defaults.is_owned(&route) && self.delete_if_present(&route)? {
+                flushed.push(route);
+            }
+        }
+        Ok(flushed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_error(owner: u32) -> io::ErrorKind {
+        match RouteManager::builder().with_owner(owner).build() {
+            Ok(_) => panic!("owner tag {owner} was accepted"),
+            Err(e) => e.kind(),
+        }
+    }
+
+    #[test]
+    fn reject_zero_owner() {
+        assert_eq!(build_error(0), io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn reject_reserved_protocol_owner() {
+        for owner in [libc::RTPROT_BOOT, libc::RTPROT_STATIC] {
+            assert_eq!(build_error(owner as u32), io::ErrorKind::InvalidInput);
+        }
+        assert_eq!(build_error(256), io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(any(
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "macos",
+        target_os = "netbsd",
+        target_os = "dragonfly"
+    ))]
+    #[test]
+    fn reject_non_protocol_flag_owner() {
+        assert_eq!(
+            build_error(crate::OWNER_FLAGS | 1),
+            io::ErrorKind::InvalidInput
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use crate::Route;
+use std::time::Duration;
+
+/// Usage data the kernel reports for a route, returned by [`Route::cache_info`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteCacheInfo {
+    pub(crate) users: u32,
+    pub(crate) last_use: Duration,
+    pub(crate) expires: Option<Duration>,
+    pub(crate) error: u32,
+    pub(crate) used: u32,
+    pub(crate) id: u32,
+    pub(crate) ts: u32,
+    pub(crate) ts_age: u32,
+    pub(crate) packets: Option<u64>,
+    pub(crate) bytes: Option<u64>,
+    pub(crate) wrong_if: Option<u64>,
+}
+
+impl RouteCacheInfo {
+    /// Returns the number of references held on the route.
+    pub fn users(&self) -> u32 {
+        self.users
+    }
+    /// Returns the time since the route was last used.
+    pub fn last_use(&self) -> Duration {
+        self.last_use
+    }
+    /// Returns the time left until the route expires, `None` if it does not expire.
+    pub fn expires(&self) -> Option<Duration> {
+        self.expires
+    }
+    /// Returns the error of the route, e.g. for unreachable routes.
+    pub fn error(&self) -> u32 {
+        self.error
+    }
+    /// Returns how often the route was used.
+    pub fn used(&self) -> u32 {
+        self.used
+    }
+    /// Returns the IP identifier of the route.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+    /// Returns the last TCP timestamp seen on the route.
+    pub fn ts(&self) -> u32 {
+        self.ts
+    }
+    /// Returns the age of [`ts`](Self::ts) in seconds.
+    pub fn ts_age(&self) -> u32 {
+        self.ts_age
+    }
+    /// Returns the number of packets forwarded by a multicast route.
+    pub fn packets(&self) -> Option<u64> {
+        self.packets
+    }
+    /// Returns the number of bytes forwarded by a multicast route.
+    pub fn bytes(&self) -> Option<u64> {
+        self.bytes
+    }
+    /// Returns the number of packets a multicast route received on the wrong interface.
+    pub fn wrong_if(&self) -> Option<u64> {
+        self.wrong_if
+    }
+}
+
+impl Route {
+    /// (Linux only) Returns the usage data reported with the route (`RTA_CACHEINFO` and, for
+    /// multicast routes, `RTA_MFC_STATS`), `None` if the kernel reported none.
+    pub fn cache_info(&self) -> Option<&RouteCacheInfo> {
+        #[cfg(target_os = "linux")]
+        return self.reported.cache_info.as_deref();
+        #[cfg(not(target_os = "linux"))]
+        None
+    }
+}
@@ -0,0 +1,249 @@
+use crate::{Route, RouteChange, RouteListener};
+use std::collections::VecDeque;
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Route listener that collapses bursts of events, created by [`RouteListener::coalesce`].
+///
+/// After the first event of a burst it keeps reading for the configured window, then hands
+/// out what is left, one event per route: successive events for the same route collapse into
+/// their net effect, e.g. an added and then modified route is reported as added in its final
+/// state, and a route that is added and deleted again within the window is not reported at
+/// all. Events are handed out one at a time by [`listen`](Self::listen) or as a whole burst
+/// by [`listen_burst`](Self::listen_burst).
+pub struct CoalescingListener {
+    listener: RouteListener,
+    coalescer: Coalescer,
+}
+
+impl RouteListener {
+    /// Wraps the listener so that bursts of events within `window` are coalesced.
+    pub fn coalesce(self, window: Duration) -> CoalescingListener {
+        CoalescingListener {
+            listener: self,
+            coalescer: Coalescer::new(window),
+        }
+    }
+}
+
+impl CoalescingListener {
+    /// Listens for the next route change event that survives coalescing.
+    pub fn listen(&mut self) -> io::Result<RouteChange> {
+        loop {
+            if let Some(change) = self.coalescer.pop()? {
+                return Ok(change);
+            }
+            self.collect()?;
+        }
+    }
+    /// Listens for the next burst of route change events and returns what survives
+    /// coalescing at once, e.g. to reconcile once per burst instead of once per event.
+    ///
+    /// Never returns an empty batch; a burst whose events cancel each other out is skipped.
+    pub fn listen_burst(&mut self) -> io::Result<Vec<RouteChange>> {
+        loop {
+            if let Some(batch) = self.coalescer.pop_all()? {
+                return Ok(batch);
+            }
+            self.collect()?;
+        }
+    }
+    fn collect(&mut self) -> io::Result<()> {
+        let first = self.listener.listen()?;
+        self.coalescer.push(first);
+        let deadline = Instant::now() + self.coalescer.window;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(());
+            }
+            match self.listener.listen_timeout(remaining) {
+                Ok(change) => self.coalescer.push(change),
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => return Ok(()),
+                Err(e) => {
+                    self.coalescer.error = Some(e);
+                    return Ok(());
+                }
+            }
+        }
+    }
+    /// Returns the underlying listener. Events still held for coalescing are discarded.
+    pub fn into_inner(self) -> RouteListener {
+        self.listener
+    }
+}
+
+/// Asynchronous counterpart of [`CoalescingListener`], created by
+/// [`AsyncRouteListener::coalesce`](crate::AsyncRouteListener::coalesce).
+#[cfg(any(feature = "async", feature = "async_io"))]
+pub struct AsyncCoalescingListener {
+    listener: crate::AsyncRouteListener,
+    coalescer: Coalescer,
+}
+
+#[cfg(any(feature = "async", feature = "async_io"))]
+impl crate::AsyncRouteListener {
+    /// Wraps the listener so that bursts of events within `window` are coalesced.
+    pub fn coalesce(self, window: Duration) -> AsyncCoalescingListener {
+        AsyncCoalescingListener {
+            listener: self,
+            coalescer: Coalescer::new(window),
+        }
+    }
+}
+
+#[cfg(any(feature = "async", feature = "async_io"))]
+impl AsyncCoalescingListener {
+    /// Asynchronously listens for the next route change event that survives coalescing.
+    ///
+    /// This method is cancel safe; events read before cancellation are handed out by the
+    /// next call without waiting for the rest of the window.
+    pub async fn listen(&mut self) -> io::Result<RouteChange> {
+        loop {
+            if let Some(change) = self.coalescer.pop()? {
+                return Ok(change);
+            }
+            self.collect().await?;
+        }
+    }
+    /// Asynchronously listens for the next burst of route change events and returns what
+    /// survives coalescing at once.
+    ///
+    /// Never returns an empty batch. This method is cancel safe in the same way as
+    /// [`listen`](Self::listen).
+    pub async fn listen_burst(&mut self) -> io::Result<Vec<RouteChange>> {
+        loop {
+            if let Some(batch) = self.coalescer.pop_all()? {
+                return Ok(batch);
+            }
+            self.collect().await?;
+        }
+    }
+    async fn collect(&mut self) -> io::Result<()> {
+        let first = self.listener.listen().await?;
+        self.coalescer.push(first);
+        let deadline = Instant::now() + self.coalescer.window;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(());
+            }
+            match self.listener.listen_with_timeout(remaining).await {
+                Ok(change) => self.coalescer.push(change),
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => return Ok(()),
+                Err(e) => {
+                    self.coalescer.error = Some(e);
+                    return Ok(());
+                }
+            }
+        }
+    }
+    /// Returns the underlying listener. Events still held for coalescing are discarded.
+    pub fn into_inner(self) -> crate::AsyncRouteListener {
+        self.listener
+    }
+}
+
+struct Coalescer {
+    window: Duration,
+    pending: VecDeque<RouteChange>,
+    // Error hit while collecting a burst, reported once the burst is handed out
+    error: Option<io::Error>,
+}
+
+impl Coalescer {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: VecDeque::new(),
+            error: None,
+        }
+    }
+    fn push(&mut self, change: RouteChange) {
+        let previous = self
+            .pending
+            .iter()
+            .rposition(|v| refers_to(changed_route(v), &change));
+        match previous {
+            Some(index) => {
+                let previous = self.pending.remove(index).unwrap();
+                if let Some(merged) = merge(previous, change) {
+                    self.pending.push_back(merged);
+                }
+            }
+            None => self.pending.push_back(change),
+        }
+    }
+    fn pop(&mut self) -> io::Result<Option<RouteChange>> {
+        if let Some(change) = self.pending.pop_front() {
+            return Ok(Some(change));
+        }
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+    fn pop_all(&mut self) -> io::Result<Option<Vec<RouteChange>>> {
+        if !self.pending.is_empty() {
+            return Ok(Some(self.pending.drain(..).collect()));
+        }
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The route as it is after the change.
+fn changed_route(change: &RouteChange) -> &Route {
+    match change {
+        RouteChange::Add(route) | RouteChange::Delete(route) => route,
+        RouteChange::Change { new, .. } => new,
+    }
+}
+
+/// Whether `change` affects the route `route`.
+fn refers_to(route: &Route, change: &RouteChange) -> bool {
+    match change {
+        RouteChange::Add(v) | RouteChange::Delete(v) | RouteChange::Change { old: Some(v), .. } => {
+            same_entry(route, v)
+        }
+        // Like `RouteTable::apply`, a change without the previous route replaces the route to
+        // the same destination on the same interface
+        RouteChange::Change { old: None, new } => {
+            route.same_destination(new) && route.same_table(new) && route.if_index == new.if_index
+        }
+    }
+}
+
+/// Collapses two successive changes of the same route into their net effect, if any.
+fn merge(previous: RouteChange, change: RouteChange) -> Option<RouteChange> {
+    use RouteChange::*;
+    match (previous, change) {
+        (Add(_), Delete(_)) => None,
+        (Add(_), Add(new) | Change { new, .. }) => Some(Add(new)),
+        (Change { old, .. }, Delete(route)) => Some(Delete(old.unwrap_or(route))),
+        (Change { old, .. }, Add(new) | Change { new, .. }) => {
+            if old.as_ref() == Some(&new) {
+                None
+            } else {
+                Some(Change { old, new })
+            }
+        }
+        (Delete(_), Delete(route)) => Some(Delete(route)),
+        (Delete(old), Add(new) | Change { new, .. }) => {
+            if old == new {
+                None
+            } else {
+                Some(Change {
+                    old: Some(old),
+                    new,
+                })
+            }
+        }
+    }
+}
+
+fn same_entry(a: &Route, b: &Route) -> bool {
+    a.same_destination(b) && !a.conflicts_with(b)
+}
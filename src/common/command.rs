@@ -0,0 +1,242 @@
+use crate::Route;
+use std::io;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// (Feature `command`) Route manager that runs the system's command-line tools instead of
+/// talking to the kernel directly.
+///
+/// Uses `ip route` on Linux, `route` and `netstat` on BSD and `netsh` on Windows. It is a
+/// fallback for environments where the native APIs are unavailable, e.g. containers whose
+/// seccomp filter blocks netlink sockets; it is slower and, as it parses the tools' output,
+/// only models the attributes they print. Attributes a tool cannot express make `add`,
+/// `delete` and `update` fail with `Unsupported`.
+///
+/// The command lines of changes can be recorded, e.g. for audit logs and change tickets,
+/// either in addition to or instead of running them; see
+/// [`with_recording`](Self::with_recording) and [`with_execute`](Self::with_execute).
+#[derive(Clone, Debug, Default)]
+pub struct CommandRouteManager {
+    pub(crate) program_dir: Option<PathBuf>,
+    record: bool,
+    skip_execute: bool,
+    recorded: Vec<String>,
+    retry: Option<crate::RetryPolicy>,
+}
+
+impl CommandRouteManager {
+    /// Creates a manager that finds the tools through `PATH`.
+    pub fn new() -> io::Result<Self> {
+        Ok(Self::default())
+    }
+    /// Runs the tools from `dir` rather than finding them through `PATH`.
+    pub fn with_program_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.program_dir = Some(dir.into());
+        self
+    }
+    /// Runs a tool again under `policy` when it fails with a transient error, see
+    /// [`RetryPolicy`](crate::RetryPolicy).
+    pub fn with_retry_policy(mut self, policy: crate::RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+    /// Returns the directory the tools are run from, if set.
+    pub fn program_dir(&self) -> Option<&std::path::Path> {
+        self.program_dir.as_deref()
+    }
+    /// Records the command line of every `add`, `delete` and `update`, see
+    /// [`recorded`](Self::recorded).
+    pub fn with_recording(mut self, record: bool) -> Self {
+        self.record = record;
+        self
+    }
+    /// Whether `add`, `delete` and `update` run their commands (the default). When disabled
+    /// the commands are only recorded, as if [`with_recording`](Self::with_recording) were
+    /// set, and the calls succeed without changing the system.
+    pub fn with_execute(mut self, execute: bool) -> Self {
+        self.skip_execute = !execute;
+        self
+    }
+    /// Returns the recorded command lines, oldest first.
+    pub fn recorded(&self) -> &[String] {
+        &self.recorded
+    }
+    /// Returns the recorded command lines and clears the record.
+    pub fn take_recorded(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.recorded)
+    }
+    /// Adds a new route with `ip route add` on Linux, `route add` on BSD and
+    /// `netsh interface ipv4|ipv6 add route` on Windows.
+    pub fn add(&mut self, route: &Route) -> io::Result<()> {
+        Self::add_command(route)
+            .and_then(|(program, args)| self.execute(program, &args))
+            .map_err(crate::context("add", route))
+    }
+    /// Deletes an existing route with `ip route del` on Linux, `route delete` on BSD and
+    /// `netsh interface ipv4|ipv6 delete route` on Windows.
+    pub fn delete(&mut self, route: &Route) -> io::Result<()> {
+        Self::delete_command(route)
+            .and_then(|(program, args)| self.execute(program, &args))
+            .map_err(crate::context("delete", route))
+    }
+    /// Modifies an existing route in place with `ip route change` on Linux, `route change`
+    /// on BSD and `netsh interface ipv4|ipv6 set route` on Windows, see
+    /// [`RouteManager::update`](crate::RouteManager::update).
+    pub fn update(&mut self, route: &Route) -> io::Result<()> {
+        Self::update_command(route)
+            .and_then(|(program, args)| self.execute(program, &args))
+            .map_err(crate::context("update", route))
+    }
+    /// Returns the shell command line that [`add`](Self::add) would run, without running it.
+    pub fn render_add(&self, route: &Route) -> io::Result<String> {
+        let (program, args) = Self::add_command(route)?;
+        Ok(self.render(program, &args))
+    }
+    /// Returns the shell command line that [`delete`](Self::delete) would run, without
+    /// running it.
+    pub fn render_delete(&self, route: &Route) -> io::Result<String> {
+        let (program, args) = Self::delete_command(route)?;
+        Ok(self.render(program, &args))
+    }
+    /// Returns the shell command line that [`update`](Self::update) would run, without
+    /// running it.
+    pub fn render_update(&self, route: &Route) -> io::Result<String> {
+        let (program, args) = Self::update_command(route)?;
+        Ok(self.render(program, &args))
+    }
+    /// Route Lookup by Destination Address
+    ///
+    /// The route is selected from the listed routes like
+    /// [`RouteManager::find_route`](crate::RouteManager::find_route) does.
+    pub fn find_route(&mut self, dest: &IpAddr) -> io::Result<Option<Route>> {
+        let list = self.list()?;
+        Ok(super::select_route(&list, dest).cloned())
+    }
+    /// Returns the installed route to exactly `destination/prefix`, or `None` if there is
+    /// none. Among several metrics the preferred route is returned.
+    pub fn get_route(&mut self, destination: IpAddr, prefix: u8) -> io::Result<Option<Route>> {
+        let wanted = Route::new(crate::network_address(destination, prefix), prefix);
+        wanted.check()?;
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|v| v.same_destination(&wanted) && super::watch::is_effective(v))
+            .min_by_key(super::default_route_priority))
+    }
+    /// Records and, unless disabled, runs a command that changes the routes.
+    fn execute(&mut self, program: &str, args: &[String]) -> io::Result<()> {
+        if self.record || self.skip_execute {
+            self.recorded.push(self.render(program, args));
+        }
+        if self.skip_execute {
+            return Ok(());
+        }
+        self.run(program, args).map(|_| ())
+    }
+    fn program_path(&self, program: &str) -> PathBuf {
+        match &self.program_dir {
+            Some(dir) => dir.join(program),
+            None => PathBuf::from(program),
+        }
+    }
+    /// Formats the command line, quoting arguments for a POSIX shell where needed.
+    fn render(&self, program: &str, args: &[String]) -> String {
+        let path = self.program_path(program);
+        let mut line = quote(&path.to_string_lossy());
+        for arg in args {
+            line.push(' ');
+            line.push_str(&quote(arg));
+        }
+        line
+    }
+    /// Runs `program` with `args` and returns its standard output.
+    pub(crate) fn run(&self, program: &str, args: &[String]) -> io::Result<String> {
+        let path = self.program_path(program);
+        debug_event!(program, ?args, "running route command");
+        let output = crate::retry(self.retry, || {
+            let output = Command::new(&path).args(args).env("LC_ALL", "C").output()?;
+            if output.status.success() {
+                return Ok(output);
+            }
+            // netsh reports errors on standard output
+            let msg = if output.stderr.is_empty() {
+                &output.stdout
+            } else {
+                &output.stderr
+            };
+            Err(command_error(&String::from_utf8_lossy(msg)))
+        })?;
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// Quotes `arg` for a POSIX shell unless it consists of safe characters only.
+fn quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:%=,+@".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Maps the error message of a failed command to the error the native API would report.
+fn command_error(stderr: &str) -> io::Error {
+    #[cfg(unix)]
+    const CODES: &[(&str, i32)] = &[
+        ("File exists", libc::EEXIST),
+        ("No such process", libc::ESRCH),
+        ("not in table", libc::ESRCH),
+        ("Operation not permitted", libc::EPERM),
+        ("Permission denied", libc::EACCES),
+        ("No such device", libc::ENODEV),
+        ("Cannot find device", libc::ENODEV),
+        ("Network is unreachable", libc::ENETUNREACH),
+        ("Invalid argument", libc::EINVAL),
+        ("No buffer space available", libc::ENOBUFS),
+        ("Device or resource busy", libc::EBUSY),
+    ];
+    #[cfg(windows)]
+    const CODES: &[(&str, i32)] = &[
+        (
+            "already exists",
+            windows_sys::Win32::Foundation::ERROR_OBJECT_ALREADY_EXISTS as i32,
+        ),
+        (
+            "Element not found",
+            windows_sys::Win32::Foundation::ERROR_NOT_FOUND as i32,
+        ),
+        (
+            "requires elevation",
+            windows_sys::Win32::Foundation::ERROR_ACCESS_DENIED as i32,
+        ),
+        (
+            "Access is denied",
+            windows_sys::Win32::Foundation::ERROR_ACCESS_DENIED as i32,
+        ),
+        (
+            "parameter is incorrect",
+            windows_sys::Win32::Foundation::ERROR_INVALID_PARAMETER as i32,
+        ),
+    ];
+    #[cfg(not(any(unix, windows)))]
+    const CODES: &[(&str, i32)] = &[];
+    let stderr = stderr.trim();
+    match CODES.iter().find(|(msg, _)| stderr.contains(msg)) {
+        Some((_, code)) => io::Error::from_raw_os_error(*code),
+        None => io::Error::other(stderr.to_string()),
+    }
+}
+
+/// The error for a route attribute the command cannot express.
+#[cfg(unix)]
+pub(crate) fn unsupported(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{what} cannot be set through the route command"),
+    )
+}
@@ -0,0 +1,112 @@
+use crate::{Route, RouteChange, RouteFlags};
+use std::fmt;
+
+/// Formats a route on one line in the style of `ip route show`, returned by
+/// [`Route::display_compact`].
+///
+/// ```text
+/// 10.0.0.0/24 via 10.0.0.1 dev eth0 metric 100
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct CompactRoute<'a>(&'a Route);
+
+/// Formats a route change in the style of `ip monitor route`, returned by
+/// [`RouteChange::display_compact`]: added routes as is, deleted routes prefixed with
+/// `Deleted` and modified routes with `Changed`.
+#[derive(Copy, Clone, Debug)]
+pub struct CompactRouteChange<'a>(&'a RouteChange);
+
+impl Route {
+    /// Returns a formatter that renders the route like `ip route show`, e.g.
+    /// `10.0.0.0/24 via 10.0.0.1 dev eth0 metric 100`.
+    pub fn display_compact(&self) -> CompactRoute<'_> {
+        CompactRoute(self)
+    }
+}
+
+impl RouteChange {
+    /// Returns a formatter that renders the change like `ip monitor route`.
+    pub fn display_compact(&self) -> CompactRouteChange<'_> {
+        CompactRouteChange(self)
+    }
+}
+
+impl fmt::Display for CompactRoute<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let route = self.0;
+        let flags = route.flags();
+        if flags.contains(RouteFlags::BLACKHOLE) {
+            f.write_str("blackhole ")?;
+        } else if flags.contains(RouteFlags::REJECT) {
+            f.write_str("unreachable ")?;
+        }
+        let max_prefix = if route.destination.is_ipv4() { 32 } else { 128 };
+        if route.prefix == 0 {
+            f.write_str("default")?;
+        } else if route.prefix == max_prefix {
+            write!(f, "{}", route.destination)?;
+        } else {
+            write!(f, "{}/{}", route.destination, route.prefix)?;
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(source) = route.source {
+            write!(f, " from {source}/{}", route.source_prefix)?;
+        }
+        if let Some(gateway) = route.gateway {
+            write!(f, " via {gateway}")?;
+            // The interface implies the zone of a link-local gateway
+            if let (Some(scope_id), None) = (route.gateway_scope_id, route.get_index()) {
+                write!(f, "%{scope_id}")?;
+            }
+        }
+        match (&route.if_name, route.if_index) {
+            (Some(name), _) => write!(f, " dev {name}")?,
+            (None, Some(index)) => write!(f, " dev #{index}")?,
+            (None, None) => {}
+        }
+        #[cfg(target_os = "linux")]
+        {
+            // Like `ip`, the main table and the boot protocol are implied
+            match route.table {
+                0 | libc::RT_TABLE_MAIN => {}
+                libc::RT_TABLE_LOCAL => f.write_str(" table local")?,
+                libc::RT_TABLE_DEFAULT => f.write_str(" table default")?,
+                table => write!(f, " table {table}")?,
+            }
+            match route.protocol {
+                None | Some(libc::RTPROT_BOOT) => {}
+                Some(libc::RTPROT_KERNEL) => f.write_str(" proto kernel")?,
+                Some(libc::RTPROT_STATIC) => f.write_str(" proto static")?,
+                Some(protocol) => write!(f, " proto {protocol}")?,
+            }
+        }
+        #[cfg(unix)]
+        if let Some(pref_source) = route.pref_source {
+            write!(f, " src {pref_source}")?;
+        }
+        #[cfg(any(target_os = "windows", target_os = "linux"))]
+        if let Some(metric) = route.metric {
+            write!(f, " metric {metric}")?;
+        }
+        #[cfg(not(target_os = "linux"))]
+        #[cfg(unix)]
+        if let Some(mtu) = route.mtu {
+            write!(f, " mtu {mtu}")?;
+        }
+        #[cfg(target_os = "openbsd")]
+        if route.priority != 0 {
+            write!(f, " priority {}", route.priority)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for CompactRouteChange<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            RouteChange::Add(route) => write!(f, "{}", route.display_compact()),
+            RouteChange::Delete(route) => write!(f, "Deleted {}", route.display_compact()),
+            RouteChange::Change { new, .. } => write!(f, "Changed {}", new.display_compact()),
+        }
+    }
+}
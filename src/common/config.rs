@@ -0,0 +1,326 @@
+use crate::Route;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::io;
+use std::net::IpAddr;
+
+/// Serialization format of a [`RouteConfig`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// JSON, with the `json` feature.
+    #[cfg(feature = "json")]
+    Json,
+    /// TOML, with the `toml` feature.
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+/// A set of routes to install, e.g. read from a provisioning file.
+///
+/// Each route has the portable fields `destination`, `prefix`, `gateway`, `gateway_scope_id`,
+/// `if_name`, `if_index` and `metric` (ignored on BSD). Platform-specific fields go under a
+/// `linux`, `bsd` or `windows` key; keys for other platforms are ignored, so one file can serve
+/// several platforms:
+///
+/// ```toml
+/// [[routes]]
+/// destination = "10.0.0.0"
+/// prefix = 8
+/// gateway = "192.168.1.1"
+/// if_name = "eth0"
+/// linux = { table = 254 }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteConfig {
+    pub(crate) routes: Vec<Route>,
+}
+
+impl RouteConfig {
+    /// Creates a config from a list of routes.
+    pub fn new(routes: Vec<Route>) -> Self {
+        Self { routes }
+    }
+    /// Reads a config in the given format.
+    ///
+    /// Fails with `InvalidData` (or `UnexpectedEof` for truncated input) if the input cannot be
+    /// parsed. Routes are not validated here; see
+    /// [`RouteManager::apply_config`](crate::RouteManager::apply_config).
+    #[cfg(any(feature = "json", feature = "toml"))]
+    pub fn from_reader<R: io::Read>(reader: R, format: ConfigFormat) -> io::Result<Self> {
+        match format {
+            #[cfg(feature = "json")]
+            ConfigFormat::Json => serde_json::from_reader(reader).map_err(io::Error::from),
+            #[cfg(feature = "toml")]
+            ConfigFormat::Toml => {
+                let buf = io::read_to_string(reader)?;
+                toml::from_str(&buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+        }
+    }
+    /// Writes the config in the given format, in the same representation
+    /// [`from_reader`](Self::from_reader) reads.
+    #[cfg(any(feature = "json", feature = "toml"))]
+    pub fn to_writer<W: io::Write>(&self, mut writer: W, format: ConfigFormat) -> io::Result<()> {
+        match format {
+            #[cfg(feature = "json")]
+            ConfigFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, self)?;
+                writer.write_all(b"\n")
+            }
+            #[cfg(feature = "toml")]
+            ConfigFormat::Toml => {
+                let buf = toml::to_string(self).map_err(io::Error::other)?;
+                writer.write_all(buf.as_bytes())
+            }
+        }
+    }
+    /// Returns the routes of the config.
+    pub fn routes(&self) -> &[Route] {
+        &self.routes
+    }
+}
+
+impl crate::RouteManager {
+    /// Validates and adds every route of `config`.
+    ///
+    /// Entries are handled independently; the result for each is returned in config order.
+    pub fn apply_config(&self, config: &RouteConfig) -> Vec<io::Result<()>> {
+        self.add_all(&config.routes)
+    }
+    /// Serializes the current routing table, e.g. for auditing or support bundles.
+    ///
+    /// Platform-specific fields are written under a `linux`, `bsd` or `windows` key, so the
+    /// output can be read back with [`RouteConfig::from_reader`].
+    #[cfg(any(feature = "json", feature = "toml"))]
+    pub fn export(&self, format: ConfigFormat) -> io::Result<String> {
+        export(RouteConfig::new(self.list()?), format)
+    }
+}
+
+#[cfg(any(feature = "async", feature = "async_io"))]
+impl crate::AsyncRouteManager {
+    /// Asynchronously validates and adds every route of `config`.
+    ///
+    /// Entries are handled independently; the result for each is returned in config order.
+    pub async fn apply_config(&self, config: &RouteConfig) -> Vec<io::Result<()>> {
+        let mut results = Vec::with_capacity(config.routes.len());
+        for route in &config.routes {
+            let rs = match route.check() {
+                Ok(()) => self.add(route).await,
+                Err(e) => Err(e),
+            };
+            results.push(rs);
+        }
+        results
+    }
+    /// Asynchronously serializes the current routing table.
+    ///
+    /// See [`RouteManager::export`](crate::RouteManager::export).
+    #[cfg(any(feature = "json", feature = "toml"))]
+    pub async fn export(&self, format: ConfigFormat) -> io::Result<String> {
+        export(RouteConfig::new(self.list().await?), format)
+    }
+}
+
+#[cfg(any(feature = "json", feature = "toml"))]
+fn export(config: RouteConfig, format: ConfigFormat) -> io::Result<String> {
+    let mut buf = Vec::new();
+    config.to_writer(&mut buf, format)?;
+    String::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[derive(Serialize, Deserialize)]
+struct RouteRepr {
+    destination: IpAddr,
+    prefix: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gateway: Option<IpAddr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gateway_scope_id: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    if_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    if_index: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    metric: Option<u32>,
+    #[cfg(target_os = "linux")]
+    #[serde(default)]
+    linux: LinuxRepr,
+    #[cfg(not(target_os = "linux"))]
+    #[cfg(unix)]
+    #[serde(default)]
+    bsd: BsdRepr,
+    #[cfg(target_os = "windows")]
+    #[serde(default)]
+    windows: WindowsRepr,
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Default, Serialize, Deserialize)]
+struct LinuxRepr {
+    #[serde(default)]
+    table: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<IpAddr>,
+    #[serde(default)]
+    source_prefix: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    protocol: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pref_source: Option<IpAddr>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    raw_attrs: Vec<RawAttributeRepr>,
+}
+
+#[cfg(not(target_os = "linux"))]
+#[cfg(unix)]
+#[derive(Default, Serialize, Deserialize)]
+struct BsdRepr {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pref_source: Option<IpAddr>,
+    #[serde(default)]
+    raw_flags: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    raw_attrs: Vec<RawAttributeRepr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mtu: Option<u32>,
+    /// Seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expire: Option<u64>,
+    #[cfg(target_os = "openbsd")]
+    #[serde(default)]
+    priority: u8,
+}
+
+#[cfg(target_os = "windows")]
+#[derive(Default, Serialize, Deserialize)]
+struct WindowsRepr {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    luid: Option<u64>,
+}
+
+#[cfg(unix)]
+#[derive(Serialize, Deserialize)]
+struct RawAttributeRepr {
+    kind: u16,
+    value: Vec<u8>,
+}
+
+#[cfg(unix)]
+fn raw_attrs_repr(route: &Route) -> Vec<RawAttributeRepr> {
+    route
+        .raw_attrs
+        .iter()
+        .map(|v| RawAttributeRepr {
+            kind: v.kind(),
+            value: v.value().to_vec(),
+        })
+        .collect()
+}
+
+impl From<&Route> for RouteRepr {
+    fn from(route: &Route) -> Self {
+        Self {
+            destination: route.destination,
+            prefix: route.prefix,
+            gateway: route.gateway,
+            gateway_scope_id: route.gateway_scope_id,
+            if_name: route.if_name.as_deref().map(String::from),
+            if_index: route.if_index,
+            #[cfg(any(target_os = "windows", target_os = "linux"))]
+            metric: route.metric,
+            #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+            metric: None,
+            #[cfg(target_os = "linux")]
+            linux: LinuxRepr {
+                table: route.table,
+                source: route.source,
+                source_prefix: route.source_prefix,
+                protocol: route.protocol,
+                pref_source: route.pref_source,
+                raw_attrs: raw_attrs_repr(route),
+            },
+            #[cfg(not(target_os = "linux"))]
+            #[cfg(unix)]
+            bsd: BsdRepr {
+                pref_source: route.pref_source,
+                raw_flags: route.raw_flags,
+                raw_attrs: raw_attrs_repr(route),
+                mtu: route.mtu,
+                expire: route
+                    .expire
+                    .and_then(|v| v.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|v| v.as_secs()),
+                #[cfg(target_os = "openbsd")]
+                priority: route.priority,
+            },
+            #[cfg(target_os = "windows")]
+            windows: WindowsRepr { luid: route.luid },
+        }
+    }
+}
+
+impl From<RouteRepr> for Route {
+    fn from(repr: RouteRepr) -> Self {
+        let mut route = Route::new(repr.destination, repr.prefix);
+        route.gateway = repr.gateway;
+        route.gateway_scope_id = repr.gateway_scope_id;
+        route.if_name = repr.if_name.map(Into::into);
+        route.if_index = repr.if_index;
+        #[cfg(any(target_os = "windows", target_os = "linux"))]
+        {
+            route.metric = repr.metric;
+        }
+        #[cfg(target_os = "linux")]
+        {
+            route.table = repr.linux.table;
+            route.source = repr.linux.source;
+            route.source_prefix = repr.linux.source_prefix;
+            route.protocol = repr.linux.protocol;
+            route.pref_source = repr.linux.pref_source;
+            route.raw_attrs = repr
+                .linux
+                .raw_attrs
+                .into_iter()
+                .map(|v| crate::RawAttribute::new(v.kind, v.value))
+                .collect();
+        }
+        #[cfg(not(target_os = "linux"))]
+        #[cfg(unix)]
+        {
+            route.pref_source = repr.bsd.pref_source;
+            route.raw_flags = repr.bsd.raw_flags;
+            route.mtu = repr.bsd.mtu;
+            route.expire = repr
+                .bsd
+                .expire
+                .map(|v| std::time::UNIX_EPOCH + std::time::Duration::from_secs(v));
+            #[cfg(target_os = "openbsd")]
+            {
+                route.priority = repr.bsd.priority;
+            }
+            route.raw_attrs = repr
+                .bsd
+                .raw_attrs
+                .into_iter()
+                .map(|v| crate::RawAttribute::new(v.kind, v.value))
+                .collect();
+        }
+        #[cfg(target_os = "windows")]
+        {
+            route.luid = repr.windows.luid;
+        }
+        route
+    }
+}
+
+impl Serialize for Route {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RouteRepr::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Route {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        RouteRepr::deserialize(deserializer).map(Route::from)
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{Route, RouteManager};
+use std::fmt;
+use std::io;
+
+/// A request to the system built without sending it, returned by the `dry_run_*` methods of
+/// [`RouteManager`].
+///
+/// The bytes are those the manager would send: the netlink message on Linux, the routing
+/// socket message (`rt_msghdr` and its sockaddrs) on BSD and the `MIB_IPFORWARD_ROW2` on
+/// Windows. `Debug` prints the decoded request.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RouteRequest {
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) decoded: String,
+}
+
+/// The operation a request is built for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RequestKind {
+    Add,
+    Delete,
+    Update,
+}
+
+impl RouteRequest {
+    /// Returns the raw request.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+    /// Returns the raw request.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl fmt::Debug for RouteRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.decoded)
+    }
+}
+
+impl RouteManager {
+    /// Validates `route` and returns the request [`add`](Self::add) would send, without
+    /// sending it. The defaults of the manager are applied.
+    pub fn dry_run_add(&self, route: &Route) -> io::Result<RouteRequest> {
+        self.request(RequestKind::Add, route)
+    }
+    /// Validates `route` and returns the request [`delete`](Self::delete) would send,
+    /// without sending it.
+    pub fn dry_run_delete(&self, route: &Route) -> io::Result<RouteRequest> {
+        self.request(RequestKind::Delete, route)
+    }
+    /// Validates `route` and returns the request [`update`](Self::update) would send,
+    /// without sending it.
+    pub fn dry_run_update(&self, route: &Route) -> io::Result<RouteRequest> {
+        self.request(RequestKind::Update, route)
+    }
+}
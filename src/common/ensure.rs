@@ -0,0 +1,143 @@
+use crate::{ListenerConfig, Route, RouteChange, RouteListener, RouteManager};
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A route kept installed by a background listener, returned by [`RouteManager::ensure`].
+///
+/// Whenever the route is deleted, e.g. by a DHCP client or network manager, it is added
+/// again. Dropping the value stops the background thread and deletes the route, unless
+/// [`into_route`](Self::into_route) is called or the route was already installed by someone
+/// else and never had to be added again. Without the `shutdown` feature the thread only
+/// notices this on the next route event.
+pub struct EnsuredRoute {
+    route: Route,
+    inner: Arc<Inner>,
+    #[cfg(feature = "shutdown")]
+    shutdown_handle: crate::RouteListenerShutdown,
+}
+
+struct Inner {
+    // Held while re-adding, so that the route is not added again after it was released
+    stopped: Mutex<bool>,
+    running: AtomicBool,
+    restored: AtomicUsize,
+    // Whether the route was added by this value rather than found installed
+    installed: AtomicBool,
+}
+
+impl RouteManager {
+    /// Installs `route`, or keeps it if it is already installed, and adds it again whenever
+    /// it is deleted, until the returned value is dropped.
+    pub fn ensure(&self, route: &Route) -> io::Result<EnsuredRoute> {
+        let config = ListenerConfig::new()
+            .with_ipv4(route.destination.is_ipv4())
+            .with_ipv6(route.destination.is_ipv6());
+        // Subscribe first so that no deletion after the add is missed
+        let listener = RouteListener::with_config(config)?;
+        #[cfg(feature = "shutdown")]
+        let shutdown_handle = listener.shutdown_handle()?;
+        let installed = match self.add(route) {
+            Ok(()) => true,
+            Err(e) if crate::is_route_exists(&e) => false,
+            Err(e) => return Err(e),
+        };
+        let inner = Arc::new(Inner {
+            stopped: Mutex::new(false),
+            running: AtomicBool::new(true),
+            restored: AtomicUsize::new(0),
+            installed: AtomicBool::new(installed),
+        });
+        let thread_inner = inner.clone();
+        let thread_route = route.clone();
+        thread::Builder::new()
+            .name("route-ensure".into())
+            .spawn(move || thread_inner.run(listener, thread_route))?;
+        Ok(EnsuredRoute {
+            route: route.clone(),
+            inner,
+            #[cfg(feature = "shutdown")]
+            shutdown_handle,
+        })
+    }
+}
+
+impl EnsuredRoute {
+    /// The route kept installed.
+    pub fn route(&self) -> &Route {
+        &self.route
+    }
+    /// Returns how often the route was added again after it had been deleted.
+    pub fn restored(&self) -> usize {
+        self.inner.restored.load(Ordering::Relaxed)
+    }
+    /// Returns whether the background listener is still running.
+    pub fn is_running(&self) -> bool {
+        self.inner.running.load(Ordering::Relaxed)
+    }
+    /// Stops keeping the route installed and deletes it, unless it was installed by someone
+    /// else and never had to be added again.
+    pub fn remove(mut self) -> io::Result<()> {
+        if self.stop() && self.inner.installed.load(Ordering::Relaxed) {
+            RouteManager::new()?.delete_if_present(&self.route)?;
+        }
+        Ok(())
+    }
+    /// Stops keeping the route installed and returns it; it stays installed.
+    pub fn into_route(mut self) -> Route {
+        self.stop();
+        self.route.clone()
+    }
+    fn stop(&mut self) -> bool {
+        let mut stopped = self.inner.stopped.lock().unwrap();
+        let was_stopped = std::mem::replace(&mut *stopped, true);
+        #[cfg(feature = "shutdown")]
+        let _ = self.shutdown_handle.shutdown();
+        !was_stopped
+    }
+}
+
+impl Drop for EnsuredRoute {
+    fn drop(&mut self) {
+        if self.stop() && self.inner.installed.load(Ordering::Relaxed) {
+            if let Ok(manager) = RouteManager::new() {
+                let _ = manager.delete_if_present(&self.route);
+            }
+        }
+    }
+}
+
+impl Inner {
+    fn run(&self, mut listener: RouteListener, route: Route) {
+        loop {
+            let deleted = match listener.listen() {
+                Ok(RouteChange::Delete(deleted)) => {
+                    deleted.same_destination(&route) && deleted.same_table(&route)
+                }
+                Ok(_) => false,
+                // Events were lost, the route may be gone
+                Err(e) if crate::os_error(&e) == Some(crate::ENOBUFS) => true,
+                Err(_) => break,
+            };
+            let stopped = self.stopped.lock().unwrap();
+            if *stopped {
+                break;
+            }
+            if deleted {
+                match RouteManager::new().and_then(|v| v.add(&route)) {
+                    Ok(()) => {
+                        debug_event!(route = %route, "route deleted, added again");
+                        self.restored.fetch_add(1, Ordering::Relaxed);
+                        self.installed.store(true, Ordering::Relaxed);
+                    }
+                    Err(e) if crate::is_route_exists(&e) => {}
+                    Err(_e) => {
+                        debug_event!(route = %route, error = %_e, "failed to add route again");
+                    }
+                }
+            }
+        }
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
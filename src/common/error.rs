@@ -0,0 +1,159 @@
+use crate::Route;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Returns the OS error code of `e`, also when this crate attached details to it.
+///
+/// Errors carrying details (e.g. [`RouteError`]) are not plain OS errors, so
+/// [`io::Error::raw_os_error`] returns `None` for them; use this function to check for a
+/// specific code such as `EEXIST`.
+pub fn os_error(e: &io::Error) -> Option<i32> {
+    if let Some(code) = e.raw_os_error() {
+        return Some(code);
+    }
+    let inner = e.get_ref()?;
+    if let Some(e) = inner.downcast_ref::<RouteError>() {
+        return os_error(&e.source);
+    }
+    #[cfg(target_os = "linux")]
+    if let Some(ext_ack) = inner.downcast_ref::<ExtAckError>() {
+        return Some(ext_ack.code);
+    }
+    inner.downcast_ref::<io::Error>().and_then(os_error)
+}
+
+/// A copy of `e`, for a failure reported for several requests, that keeps its OS error code.
+#[cfg(target_os = "linux")]
+pub(crate) fn copy_error(e: &io::Error) -> io::Error {
+    match os_error(e) {
+        Some(code) => io::Error::from_raw_os_error(code),
+        None => io::Error::new(e.kind(), e.to_string()),
+    }
+}
+
+/// A failed change together with the operation and the route it was attempted on, e.g.
+/// `failed to add 10.0.0.0/24 via 10.0.0.1 dev tun0: File exists (os error 17)`.
+///
+/// The `add`, `delete` and `update` methods of the route managers return it as the inner error
+/// of an [`io::Error`] of the same kind as the underlying error, which is available through
+/// [`source`](Error::source) or [`io_error`](Self::io_error), e.g. to reach the
+/// [`ValidationErrors`](crate::ValidationErrors) of an invalid route.
+///
+/// Plain OS errors are returned unchanged, so [`io::Error::raw_os_error`] keeps working for
+/// them; errors that already carry details (such as a netlink extended acknowledgement) are
+/// wrapped, and [`os_error`] reads the code of either.
+#[derive(Debug)]
+pub struct RouteError {
+    operation: &'static str,
+    route: Route,
+    source: io::Error,
+}
+
+impl RouteError {
+    /// Returns the operation that failed: `add`, `delete` or `update`.
+    pub fn operation(&self) -> &'static str {
+        self.operation
+    }
+    /// Returns the route the operation was attempted on.
+    pub fn route(&self) -> &Route {
+        &self.route
+    }
+    /// Returns the underlying error.
+    pub fn io_error(&self) -> &io::Error {
+        &self.source
+    }
+    /// Returns the underlying error.
+    pub fn into_io_error(self) -> io::Error {
+        self.source
+    }
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to {} {}: {}",
+            self.operation,
+            self.route.display_compact(),
+            self.source
+        )
+    }
+}
+
+impl Error for RouteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Returns a function wrapping an error of `operation` on `route` in a [`RouteError`], unless
+/// it is a plain OS error.
+pub(crate) fn context<'a>(
+    operation: &'static str,
+    route: &'a Route,
+) -> impl FnOnce(io::Error) -> io::Error + 'a {
+    move |source| {
+        if source.raw_os_error().is_some() {
+            return source;
+        }
+        let kind = source.kind();
+        let e = RouteError {
+            operation,
+            route: route.clone(),
+            source,
+        };
+        io::Error::new(kind, e)
+    }
+}
+
+/// (Linux only) A netlink error with the extended acknowledgement (`NETLINK_EXT_ACK`) of the
+/// kernel, which explains what was wrong with a request.
+///
+/// It is returned as the inner error of an [`io::Error`] of the kind of the error code; see
+/// [`os_error`] to read the code.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtAckError {
+    pub(crate) code: i32,
+    pub(crate) message: Option<String>,
+    pub(crate) offset: Option<u32>,
+}
+
+#[cfg(target_os = "linux")]
+impl ExtAckError {
+    /// Returns the error code (`errno`).
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+    /// Returns the message of the kernel, if any.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+    /// Returns the offset of the offending attribute in the request, counted from the start
+    /// of its netlink header, if the kernel reported one.
+    pub fn offset(&self) -> Option<u32> {
+        self.offset
+    }
+    pub(crate) fn into_io(self) -> io::Error {
+        let kind = io::Error::from_raw_os_error(self.code).kind();
+        io::Error::new(kind, self)
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl fmt::Display for ExtAckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", io::Error::from_raw_os_error(self.code))?;
+        if let Some(message) = &self.message {
+            write!(f, ": {message}")?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " (attribute at offset {offset})")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Error for ExtAckError {}
@@ -0,0 +1,124 @@
+use crate::Route;
+use std::fmt;
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+
+/// Normalized route flags, see [`Route::flags`].
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RouteFlags(u32);
+
+impl RouteFlags {
+    /// The route is usable.
+    pub const UP: RouteFlags = RouteFlags(1);
+    /// The destination is reached through a gateway.
+    pub const GATEWAY: RouteFlags = RouteFlags(1 << 1);
+    /// The route is to a single host.
+    pub const HOST: RouteFlags = RouteFlags(1 << 2);
+    /// The route was configured administratively rather than by the system.
+    pub const STATIC: RouteFlags = RouteFlags(1 << 3);
+    /// The route was created by an ICMP redirect.
+    pub const DYNAMIC: RouteFlags = RouteFlags(1 << 4);
+    /// Packets to the destination are rejected with an ICMP error.
+    pub const REJECT: RouteFlags = RouteFlags(1 << 5);
+    /// Packets to the destination are silently discarded.
+    pub const BLACKHOLE: RouteFlags = RouteFlags(1 << 6);
+    /// The route was cloned from another one, e.g. a cached path.
+    pub const CLONED: RouteFlags = RouteFlags(1 << 7);
+
+    const NAMES: [(RouteFlags, &'static str); 8] = [
+        (Self::UP, "UP"),
+        (Self::GATEWAY, "GATEWAY"),
+        (Self::HOST, "HOST"),
+        (Self::STATIC, "STATIC"),
+        (Self::DYNAMIC, "DYNAMIC"),
+        (Self::REJECT, "REJECT"),
+        (Self::BLACKHOLE, "BLACKHOLE"),
+        (Self::CLONED, "CLONED"),
+    ];
+
+    /// Returns the empty set.
+    pub const fn empty() -> Self {
+        RouteFlags(0)
+    }
+    /// Returns the raw bits.
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+    /// Whether no flag is set.
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+    /// Whether all flags of `other` are set.
+    pub const fn contains(&self, other: RouteFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+    /// Sets the flags of `other`.
+    pub fn insert(&mut self, other: RouteFlags) {
+        self.0 |= other.0;
+    }
+    /// Clears the flags of `other`.
+    pub fn remove(&mut self, other: RouteFlags) {
+        self.0 &= !other.0;
+    }
+    /// Sets the flags of `other` if `value` is true, clears them otherwise.
+    pub fn set(&mut self, other: RouteFlags, value: bool) {
+        if value {
+            self.insert(other)
+        } else {
+            self.remove(other)
+        }
+    }
+}
+
+impl BitOr for RouteFlags {
+    type Output = RouteFlags;
+    fn bitor(self, rhs: RouteFlags) -> RouteFlags {
+        RouteFlags(self.0 | rhs.0)
+    }
+}
+impl BitOrAssign for RouteFlags {
+    fn bitor_assign(&mut self, rhs: RouteFlags) {
+        self.0 |= rhs.0;
+    }
+}
+impl BitAnd for RouteFlags {
+    type Output = RouteFlags;
+    fn bitand(self, rhs: RouteFlags) -> RouteFlags {
+        RouteFlags(self.0 & rhs.0)
+    }
+}
+
+impl fmt::Debug for RouteFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RouteFlags({self})")
+    }
+}
+impl fmt::Display for RouteFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names = Self::NAMES
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| *name);
+        match names.next() {
+            None => f.write_str("empty"),
+            Some(first) => {
+                f.write_str(first)?;
+                names.try_for_each(|name| write!(f, " | {name}"))
+            }
+        }
+    }
+}
+
+impl Route {
+    /// Returns the normalized flags of the route.
+    ///
+    /// `GATEWAY` and `HOST` follow from the route itself; the other flags are only set for
+    /// routes read from the system, from `rtm_flags` on BSD, the protocol, type and flags of
+    /// the netlink message on Linux and the origin and protocol on Windows.
+    pub fn flags(&self) -> RouteFlags {
+        let mut flags = crate::reported_flags(self);
+        flags.set(RouteFlags::GATEWAY, self.gateway.is_some());
+        let max_prefix = if self.destination.is_ipv4() { 32 } else { 128 };
+        flags.set(RouteFlags::HOST, self.prefix == max_prefix);
+        flags
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{RouteChange, RouteListener};
+use std::io;
+use std::sync::mpsc;
+
+/// Destination of the events forwarded by [`RouteListener::forward_to`].
+///
+/// Implemented for the senders of `std::sync::mpsc` and for closures that return whether the
+/// receiver still exists, e.g. `|change| tx.send(change).is_ok()` for a crossbeam or flume
+/// sender.
+pub trait EventSender {
+    /// Sends `change`, returning `false` once the receiver is gone.
+    fn send_event(&mut self, change: RouteChange) -> bool;
+}
+
+impl EventSender for mpsc::Sender<RouteChange> {
+    fn send_event(&mut self, change: RouteChange) -> bool {
+        self.send(change).is_ok()
+    }
+}
+impl EventSender for mpsc::SyncSender<RouteChange> {
+    fn send_event(&mut self, change: RouteChange) -> bool {
+        self.send(change).is_ok()
+    }
+}
+impl<F: FnMut(RouteChange) -> bool> EventSender for F {
+    fn send_event(&mut self, change: RouteChange) -> bool {
+        self(change)
+    }
+}
+
+/// Destination of the events forwarded by
+/// [`AsyncRouteListener::forward_to`](crate::AsyncRouteListener::forward_to).
+///
+/// Implemented for every [`EventSender`] and, with the `async` feature, for the tokio mpsc
+/// senders.
+#[cfg(any(feature = "async", feature = "async_io"))]
+pub trait AsyncEventSender {
+    /// Sends `change`, resolving to `false` once the receiver is gone.
+    fn send_event(&mut self, change: RouteChange) -> crate::BoxFuture<'_, bool>;
+}
+
+#[cfg(any(feature = "async", feature = "async_io"))]
+impl<S: EventSender + Send> AsyncEventSender for S {
+    fn send_event(&mut self, change: RouteChange) -> crate::BoxFuture<'_, bool> {
+        Box::pin(std::future::ready(EventSender::send_event(self, change)))
+    }
+}
+#[cfg(feature = "async")]
+impl AsyncEventSender for tokio::sync::mpsc::Sender<RouteChange> {
+    fn send_event(&mut self, change: RouteChange) -> crate::BoxFuture<'_, bool> {
+        Box::pin(async move { self.send(change).await.is_ok() })
+    }
+}
+#[cfg(feature = "async")]
+impl AsyncEventSender for tokio::sync::mpsc::UnboundedSender<RouteChange> {
+    fn send_event(&mut self, change: RouteChange) -> crate::BoxFuture<'_, bool> {
+        Box::pin(std::future::ready(self.send(change).is_ok()))
+    }
+}
+
+impl RouteListener {
+    /// Forwards route change events to `sender`, e.g. to merge them into an existing event
+    /// channel, until its receiver is dropped.
+    ///
+    /// Events are read in batches, see [`listen_batch`](Self::listen_batch). Returns `Ok` once
+    /// the receiver is gone, or the error that ended listening, e.g. after a shutdown.
+    pub fn forward_to<S: EventSender>(&mut self, mut sender: S) -> io::Result<()> {
+        loop {
+            for change in self.listen_batch(usize::MAX)? {
+                if !sender.send_event(change) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "async", feature = "async_io"))]
+impl crate::AsyncRouteListener {
+    /// Asynchronously forwards route change events to `sender` until its receiver is
+    /// dropped.
+    ///
+    /// Returns `Ok` once the receiver is gone, or the error that ended listening. Unlike
+    /// [`listen`](Self::listen), this is not cancel safe: an event being sent when the future
+    /// is dropped is lost.
+    pub async fn forward_to<S: AsyncEventSender>(&mut self, mut sender: S) -> io::Result<()> {
+        loop {
+            let change = self.listen().await?;
+            if !sender.send_event(change).await {
+                return Ok(());
+            }
+        }
+    }
+}
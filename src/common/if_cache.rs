@@ -0,0 +1,136 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+#[derive(Default)]
+struct Entries {
+    names: HashMap<u32, Arc<str>>,
+    indexes: HashMap<Arc<str>, u32>,
+}
+
+static CACHE: Mutex<Option<Entries>> = Mutex::new(None);
+static RESOLVE_NAMES: AtomicBool = AtomicBool::new(true);
+/// Resolved names, shared by the routes that use them also while the cache is disabled.
+static NAMES: RwLock<Option<HashSet<Arc<str>>>> = RwLock::new(None);
+/// Far more interfaces than a host usually has; beyond that the names are dropped, as
+/// interfaces come and go.
+const NAMES_LEN: usize = 4096;
+
+/// Process-wide cache for interface name/index lookups.
+///
+/// Parsing a route resolves its interface name, which costs a syscall per entry.
+/// The cache is disabled by default. While it is enabled, route listeners drop stale
+/// entries when they observe link events (Linux and BSD). On Windows, call
+/// [`InterfaceCache::invalidate`] after interfaces are renamed or removed.
+///
+/// Callers that only need interface indexes can skip the lookup altogether with
+/// [`InterfaceCache::set_resolve_names`].
+pub struct InterfaceCache {
+    _private: std::marker::PhantomData<()>,
+}
+impl InterfaceCache {
+    /// Enables the cache. Listeners created afterwards also subscribe to link events.
+    pub fn enable() {
+        let mut cache = CACHE.lock().unwrap();
+        if cache.is_none() {
+            *cache = Some(Entries::default());
+        }
+    }
+    /// Disables the cache and drops all entries.
+    pub fn disable() {
+        CACHE.lock().unwrap().take();
+    }
+    /// Returns whether the cache is enabled.
+    pub fn is_enabled() -> bool {
+        CACHE.lock().unwrap().is_some()
+    }
+    /// Sets whether routes read from the system (listed, looked up or received by a listener)
+    /// carry the name of their interface; enabled by default.
+    ///
+    /// When disabled, [`Route::if_name`](crate::Route::if_name) of such routes is `None` and
+    /// [`Route::resolve_if_name`](crate::Route::resolve_if_name) looks the name up on demand.
+    pub fn set_resolve_names(resolve: bool) {
+        RESOLVE_NAMES.store(resolve, Ordering::Relaxed);
+    }
+    /// Returns whether routes read from the system carry the name of their interface.
+    pub fn resolves_names() -> bool {
+        RESOLVE_NAMES.load(Ordering::Relaxed)
+    }
+    /// Drops all cached entries.
+    pub fn invalidate() {
+        if let Some(entries) = CACHE.lock().unwrap().as_mut() {
+            entries.names.clear();
+            entries.indexes.clear();
+        }
+    }
+    /// Drops the cached entries of a single interface.
+    pub fn invalidate_index(index: u32) {
+        if let Some(entries) = CACHE.lock().unwrap().as_mut() {
+            if let Some(name) = entries.names.remove(&index) {
+                entries.indexes.remove(&name);
+            }
+            entries.indexes.retain(|_, v| *v != index);
+        }
+    }
+}
+
+fn insert(index: u32, name: &str) {
+    if let Some(entries) = CACHE.lock().unwrap().as_mut() {
+        let name = intern(name);
+        entries.names.insert(index, name.clone());
+        entries.indexes.insert(name, index);
+    }
+}
+
+/// Returns the shared allocation of `name`.
+fn intern(name: &str) -> Arc<str> {
+    if let Some(found) = NAMES.read().unwrap().as_ref().and_then(|v| v.get(name)) {
+        return found.clone();
+    }
+    let mut names = NAMES.write().unwrap();
+    let names = names.get_or_insert_with(HashSet::new);
+    if let Some(found) = names.get(name) {
+        return found.clone();
+    }
+    if names.len() == NAMES_LEN {
+        names.clear();
+    }
+    let name: Arc<str> = name.into();
+    names.insert(name.clone());
+    name
+}
+
+pub(crate) fn cached_if_index_to_name(index: u32) -> io::Result<Arc<str>> {
+    if let Some(entries) = CACHE.lock().unwrap().as_ref() {
+        if let Some(name) = entries.names.get(&index) {
+            return Ok(name.clone());
+        }
+    } else {
+        return crate::if_index_to_name(index).map(|v| intern(&v));
+    }
+    let name = crate::if_index_to_name(index)?;
+    insert(index, &name);
+    Ok(intern(&name))
+}
+
+/// Returns the name to store in a route read from the system, if names are resolved.
+pub(crate) fn route_if_name(index: u32) -> Option<Arc<str>> {
+    if !InterfaceCache::resolves_names() {
+        return None;
+    }
+    cached_if_index_to_name(index).ok()
+}
+
+pub(crate) fn cached_if_name_to_index(name: &str) -> io::Result<u32> {
+    if let Some(entries) = CACHE.lock().unwrap().as_ref() {
+        if let Some(index) = entries.indexes.get(name) {
+            return Ok(*index);
+        }
+    } else {
+        return crate::if_name_to_index(name);
+    }
+    let index = crate::if_name_to_index(name)?;
+    insert(index, name);
+    Ok(index)
+}
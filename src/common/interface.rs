@@ -0,0 +1,77 @@
+use std::fmt;
+use std::net::IpAddr;
+
+/// An address configured on an interface, with the length of its on-link prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct InterfaceAddress {
+    pub(crate) index: u32,
+    pub(crate) addr: IpAddr,
+    pub(crate) prefix: u8,
+}
+
+/// Network interface information.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interface {
+    pub(crate) index: u32,
+    pub(crate) name: String,
+    pub(crate) mac: Option<[u8; 6]>,
+    pub(crate) mtu: u32,
+    #[cfg(unix)]
+    pub(crate) flags: u32,
+    pub(crate) is_up: bool,
+    #[cfg(target_os = "windows")]
+    pub(crate) luid: u64,
+}
+impl Interface {
+    /// Interface index.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+    /// Interface name (e.g., "eth0"). On Windows this is the interface alias.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Hardware (MAC) address, if the interface has an Ethernet-style address.
+    pub fn mac(&self) -> Option<[u8; 6]> {
+        self.mac
+    }
+    /// Maximum transmission unit.
+    pub fn mtu(&self) -> u32 {
+        self.mtu
+    }
+    /// (Unix only) Raw `IFF_*` interface flags.
+    #[cfg(unix)]
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+    /// Whether the interface is administratively up.
+    pub fn is_up(&self) -> bool {
+        self.is_up
+    }
+    /// (Windows only) LUID (Local Unique Identifier) of the interface.
+    #[cfg(target_os = "windows")]
+    pub fn luid(&self) -> u64 {
+        self.luid
+    }
+}
+impl fmt::Display for Interface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Interface {{ index: {}, name: {}, mac: ",
+            self.index, self.name
+        )?;
+        match self.mac {
+            Some(mac) => write!(
+                f,
+                "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+                mac[0], mac[1], mac[2], mac[3], mac[4], mac[5]
+            ),
+            None => write!(f, "None"),
+        }?;
+        write!(f, ", mtu: {}, is_up: {}", self.mtu, self.is_up)?;
+        #[cfg(target_os = "windows")]
+        write!(f, ", luid: {}", self.luid)?;
+        write!(f, " }}")
+    }
+}
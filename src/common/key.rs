@@ -0,0 +1,115 @@
+use crate::{Route, RouteManager};
+use std::io;
+use std::net::IpAddr;
+
+/// The identity of a route, returned by [`Route::key`].
+///
+/// Unlike a full [`Route`] it holds no interface names or other details that may change while
+/// the route is installed, so it can be persisted and later passed to
+/// [`RouteManager::delete_by_key`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RouteKey {
+    pub(crate) destination: IpAddr,
+    pub(crate) prefix: u8,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) table: Option<u8>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) metric: Option<u32>,
+}
+
+impl RouteKey {
+    /// Returns the network address of the destination.
+    pub fn destination(&self) -> IpAddr {
+        self.destination
+    }
+    /// Returns the prefix length of the destination.
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+    /// (Linux only) Returns the routing table.
+    pub fn table(&self) -> Option<u8> {
+        self.table
+    }
+    /// (Windows/Linux) Returns the metric.
+    pub fn metric(&self) -> Option<u32> {
+        self.metric
+    }
+    /// Whether `route` has this identity.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub(crate) fn matches(&self, route: &Route) -> bool {
+        route.key() == *self
+    }
+}
+
+impl Route {
+    /// Returns the identity of the route: its destination, and on Linux the table and metric,
+    /// on Windows the metric.
+    pub fn key(&self) -> RouteKey {
+        RouteKey {
+            destination: self.network(),
+            prefix: self.prefix,
+            #[cfg(target_os = "linux")]
+            table: Some(self.table),
+            #[cfg(not(target_os = "linux"))]
+            table: None,
+            #[cfg(any(target_os = "windows", target_os = "linux"))]
+            metric: self.metric,
+            #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+            metric: None,
+        }
+    }
+    /// Whether both routes are the same installed route: they have the same key, gateway and
+    /// interface. Details reported by the system, such as the interface name or protocol,
+    /// are not compared, as they may differ between the events of one route.
+    pub(crate) fn same_identity(&self, other: &Route) -> bool {
+        if self.key() != other.key() || self.gateway != other.gateway {
+            return false;
+        }
+        match (self.if_index, other.if_index) {
+            (Some(a), Some(b)) => a == b,
+            _ => self.get_index() == other.get_index(),
+        }
+    }
+}
+
+impl RouteManager {
+    /// Deletes the route identified by `key`.
+    ///
+    /// On Linux and BSD the key is passed to the kernel as is. Windows needs the interface of
+    /// the route, so the route is looked up first; it fails with `InvalidInput` if several
+    /// routes on different interfaces have the key, and with `ERROR_NOT_FOUND` if none has.
+    pub fn delete_by_key(&self, key: &RouteKey) -> io::Result<()> {
+        #[cfg(windows)]
+        {
+            let mut found: Vec<Route> = self
+                .list()?
+                .into_iter()
+                .filter(|v| key.matches(v))
+                .collect();
+            match found.len() {
+                0 => Err(io::Error::from_raw_os_error(
+                    windows_sys::Win32::Foundation::ERROR_NOT_FOUND as i32,
+                )),
+                1 => self.delete(&found.remove(0)),
+                n => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{n} routes have the key {}/{}", key.destination, key.prefix),
+                )),
+            }
+        }
+        #[cfg(not(windows))]
+        {
+            #[allow(unused_mut)]
+            let mut route = Route::new(key.destination, key.prefix);
+            #[cfg(target_os = "linux")]
+            {
+                if let Some(table) = key.table {
+                    route = route.with_table(table);
+                }
+                route.metric = key.metric;
+            }
+            self.delete(&route)
+        }
+    }
+}
@@ -0,0 +1,545 @@
+use crate::{Route, RouteChange};
+use std::collections::HashMap;
+#[cfg(unix)]
+use std::collections::VecDeque;
+use std::io;
+use std::net::IpAddr;
+
+/// Default number of events a listener buffers before its [`OverflowPolicy`] applies.
+pub const DEFAULT_QUEUE_CAPACITY: usize = 128;
+
+#[cfg(unix)]
+pub(crate) const ENOBUFS: i32 = libc::ENOBUFS;
+#[cfg(windows)]
+pub(crate) const ENOBUFS: i32 = windows_sys::Win32::Networking::WinSock::WSAENOBUFS;
+// Never reported, as no listener can be created on unsupported targets
+#[cfg(not(any(unix, windows)))]
+pub(crate) const ENOBUFS: i32 = -1;
+
+/// (Windows only) What a listener does when its event queue is full.
+///
+/// On Unix events are read from the socket one message at a time, as they are listened for,
+/// so the backlog stays in the kernel socket buffer (see
+/// [`with_recv_buffer_size`](ListenerConfig::with_recv_buffer_size)), which reports `ENOBUFS`
+/// once it overflows.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Keep every event and stop taking in new ones until the queue drains, which blocks the
+    /// system notification thread.
+    #[default]
+    Block,
+    /// Discard the oldest queued event to make room.
+    DropOldest,
+    /// Discard the incoming event.
+    DropNewest,
+    /// Discard the incoming event and fail the next `listen` with `ENOBUFS`.
+    Error,
+}
+
+/// Options for creating a `RouteListener` or `AsyncRouteListener`.
+#[derive(Clone, Debug)]
+pub struct ListenerConfig {
+    pub(crate) queue_capacity: usize,
+    pub(crate) overflow_policy: OverflowPolicy,
+    pub(crate) ipv4: bool,
+    pub(crate) ipv6: bool,
+    pub(crate) suppress_own_changes: bool,
+    pub(crate) ignore_system_routes: bool,
+    pub(crate) initial_table: bool,
+    pub(crate) previous_routes: bool,
+    pub(crate) recv_buffer_size: Option<usize>,
+    // Set by `subscribe`: keep the dump and drop events it already reflects
+    pub(crate) snapshot: bool,
+}
+
+impl Default for ListenerConfig {
+    fn default() -> Self {
+        Self {
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            overflow_policy: OverflowPolicy::default(),
+            ipv4: true,
+            ipv6: true,
+            suppress_own_changes: false,
+            ignore_system_routes: false,
+            initial_table: false,
+            previous_routes: false,
+            recv_buffer_size: None,
+            snapshot: false,
+        }
+    }
+}
+
+impl ListenerConfig {
+    /// Creates the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// (Windows only) Sets the number of events buffered before the overflow policy applies
+    /// (at least 1).
+    ///
+    /// Creating a listener with a capacity other than [`DEFAULT_QUEUE_CAPACITY`] fails with
+    /// `Unsupported` on Unix; see [`OverflowPolicy`].
+    pub fn with_queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = capacity.max(1);
+        self
+    }
+    /// (Windows only) Sets what happens when the queue is full.
+    ///
+    /// Creating a listener with a policy other than [`OverflowPolicy::Block`] fails with
+    /// `Unsupported` on Unix.
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+    /// Sets whether IPv4 route changes are received (default `true`).
+    ///
+    /// Turning a family off happens at the socket level (netlink group membership on Linux,
+    /// the routing socket protocol on BSD), so the process is not woken up for it at all.
+    pub fn with_ipv4(mut self, ipv4: bool) -> Self {
+        self.ipv4 = ipv4;
+        self
+    }
+    /// Sets whether IPv6 route changes are received (default `true`).
+    pub fn with_ipv6(mut self, ipv6: bool) -> Self {
+        self.ipv6 = ipv6;
+        self
+    }
+    /// (Unix only) Sets whether events caused by this process's own route operations are
+    /// dropped (default `false`).
+    ///
+    /// Events are matched by sender: the netlink port ids of this process's sockets on Linux,
+    /// the process id on BSD. Windows does not report the sender, so creating a listener with
+    /// this option fails with `Unsupported` there.
+    pub fn with_suppress_own_changes(mut self, suppress: bool) -> Self {
+        self.suppress_own_changes = suppress;
+        self
+    }
+    /// Sets whether changes to routes the system installs by itself are dropped (default
+    /// `false`), so that only static and application-installed routes are reported.
+    ///
+    /// System routes are those with protocol kernel, redirect, RA or DHCP on Linux, those
+    /// without `RTF_STATIC` on BSD, and those not of origin `NlroManual` on Windows.
+    pub fn with_ignore_system_routes(mut self, ignore: bool) -> Self {
+        self.ignore_system_routes = ignore;
+        self
+    }
+    /// Sets whether a new listener first delivers the current routing table as `Add` events,
+    /// before any live change (default `false`).
+    ///
+    /// The table is dumped after the listener has subscribed, so no change is missed in
+    /// between; a change racing with the dump may repeat a route of the replay. Replayed
+    /// events do not count against the queue capacity, are filtered by address family only
+    /// and carry an empty [`EventOrigin`].
+    pub fn with_initial_table(mut self, initial_table: bool) -> Self {
+        self.initial_table = initial_table;
+        self
+    }
+    /// Sets whether [`RouteChange::Change`] events carry the route before the change (default
+    /// `false`).
+    ///
+    /// The listener then dumps the routing table when it is created and keeps a copy of it,
+    /// which costs memory in proportion to the table; creating the listener fails if the
+    /// dump fails. Without it, `old` is always `None`.
+    pub fn with_previous_routes(mut self, previous_routes: bool) -> Self {
+        self.previous_routes = previous_routes;
+        self
+    }
+    /// (Unix only) Sets the kernel receive buffer size of the listener socket, in bytes
+    /// (default: the system default).
+    ///
+    /// A larger buffer keeps bursts of events, e.g. while a routing daemon converges, from
+    /// overflowing the socket. On Linux `SO_RCVBUFFORCE` is used when the process has
+    /// `CAP_NET_ADMIN`, and `SO_RCVBUF`, capped by `net.core.rmem_max`, otherwise. Has no
+    /// effect on Windows, where notifications are not delivered through a socket.
+    pub fn with_recv_buffer_size(mut self, size: usize) -> Self {
+        self.recv_buffer_size = Some(size);
+        self
+    }
+    /// Returns the queue capacity.
+    pub fn queue_capacity(&self) -> usize {
+        self.queue_capacity
+    }
+    /// Returns the overflow policy.
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+    /// Returns whether IPv4 route changes are received.
+    pub fn ipv4(&self) -> bool {
+        self.ipv4
+    }
+    /// Returns whether IPv6 route changes are received.
+    pub fn ipv6(&self) -> bool {
+        self.ipv6
+    }
+    /// Returns whether events caused by this process are dropped.
+    pub fn suppress_own_changes(&self) -> bool {
+        self.suppress_own_changes
+    }
+    /// Returns whether changes to system-installed routes are dropped.
+    pub fn ignore_system_routes(&self) -> bool {
+        self.ignore_system_routes
+    }
+    /// Returns whether the current table is replayed first.
+    pub fn initial_table(&self) -> bool {
+        self.initial_table
+    }
+    /// Returns whether change events carry the previous route.
+    pub fn previous_routes(&self) -> bool {
+        self.previous_routes
+    }
+    /// Returns the configured receive buffer size.
+    pub fn recv_buffer_size(&self) -> Option<usize> {
+        self.recv_buffer_size
+    }
+    /// The `Add` events replaying `routes` if the initial table is requested.
+    pub(crate) fn replay(&self, routes: Vec<Route>) -> impl Iterator<Item = RouteChange> + '_ {
+        routes
+            .into_iter()
+            .filter(|v| {
+                self.initial_table
+                    && if v.destination.is_ipv4() {
+                        self.ipv4
+                    } else {
+                        self.ipv6
+                    }
+            })
+            .map(RouteChange::Add)
+    }
+    /// Whether a new listener dumps the routing table.
+    pub(crate) fn needs_routes(&self) -> bool {
+        self.initial_table || self.previous_routes || self.snapshot
+    }
+    /// The route cache of a new listener, given the dumped `routes`, if one is kept.
+    pub(crate) fn route_cache(&self, routes: &[Route]) -> Option<RouteCache> {
+        (self.previous_routes || self.snapshot).then(|| RouteCache::new(routes, self.snapshot))
+    }
+    /// Checks that the queue options are left at their defaults, which Unix listeners do not
+    /// implement.
+    #[cfg(unix)]
+    pub(crate) fn check_queue(&self) -> io::Result<()> {
+        if self.queue_capacity != DEFAULT_QUEUE_CAPACITY
+            || self.overflow_policy != OverflowPolicy::Block
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "listeners read the socket as events are listened for on Unix, \
+                 the queue capacity and overflow policy only apply on Windows",
+            ));
+        }
+        Ok(())
+    }
+    pub(crate) fn check_families(&self) -> io::Result<()> {
+        if !self.ipv4 && !self.ipv6 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "listener must receive at least one address family",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Applies the configured receive buffer size to a listener socket.
+#[cfg(unix)]
+pub(crate) fn set_recv_buffer<S: std::os::fd::AsRawFd>(
+    socket: &S,
+    config: &ListenerConfig,
+) -> io::Result<()> {
+    let Some(size) = config.recv_buffer_size else {
+        return Ok(());
+    };
+    let size = libc::c_int::try_from(size)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "receive buffer too large"))?;
+    let set = |option| {
+        let rs = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_SOCKET,
+                option,
+                (&size as *const libc::c_int).cast(),
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if rs < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    };
+    // Exceeding rmem_max needs CAP_NET_ADMIN
+    #[cfg(target_os = "linux")]
+    if set(libc::SO_RCVBUFFORCE).is_ok() {
+        return Ok(());
+    }
+    set(libc::SO_RCVBUF)
+}
+
+/// Error returned by a listener whose queue overflowed under [`OverflowPolicy::Error`].
+#[cfg(windows)]
+pub(crate) fn overflow_error() -> io::Error {
+    io::Error::from_raw_os_error(ENOBUFS)
+}
+
+/// Sender of a route change event, as reported by the kernel.
+///
+/// On Linux `pid` is the netlink port id of the socket whose request caused the change (the
+/// process id for the first netlink socket of a process); on BSD it is `rtm_pid`. Changes made
+/// by the kernel itself report 0. Windows does not report a sender.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct EventOrigin {
+    pub(crate) pid: Option<u32>,
+    pub(crate) seq: Option<u32>,
+    // The route was installed by the system rather than statically
+    pub(crate) system_route: bool,
+}
+
+impl EventOrigin {
+    /// Returns the netlink port id (Linux) or process id (BSD) of the sender.
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+    /// Returns the sequence number of the request that caused the change.
+    pub fn seq(&self) -> Option<u32> {
+        self.seq
+    }
+    /// Returns whether the change was made by the kernel rather than by a process.
+    pub fn is_kernel(&self) -> bool {
+        self.pid == Some(0)
+    }
+}
+
+/// What the system matches on when it changes a route in place.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct RouteIdentity {
+    destination: IpAddr,
+    prefix: u8,
+    #[cfg(target_os = "linux")]
+    table: u8,
+    #[cfg(target_os = "linux")]
+    metric: Option<u32>,
+    #[cfg(windows)]
+    if_index: Option<u32>,
+    #[cfg(windows)]
+    gateway: Option<IpAddr>,
+}
+
+impl From<&Route> for RouteIdentity {
+    fn from(route: &Route) -> Self {
+        Self {
+            destination: route.destination,
+            prefix: route.prefix,
+            #[cfg(target_os = "linux")]
+            table: route.table,
+            #[cfg(target_os = "linux")]
+            metric: route.metric,
+            #[cfg(windows)]
+            if_index: route.if_index,
+            #[cfg(windows)]
+            gateway: route.gateway,
+        }
+    }
+}
+
+/// Copy of the routing table as seen by one listener, used to fill in the previous route of
+/// [`RouteChange::Change`] events.
+pub(crate) struct RouteCache {
+    // Routes by what the system matches on; ECMP and per-interface siblings share a key
+    routes: HashMap<RouteIdentity, Vec<Route>>,
+    // Drop additions of cached routes and deletions of unknown ones
+    dedup: bool,
+}
+
+impl RouteCache {
+    /// Creates a cache holding `routes`, a dump taken after the listener has subscribed so
+    /// that no change is lost in between.
+    pub(crate) fn new(routes: &[Route], dedup: bool) -> Self {
+        let mut cache = Self {
+            routes: HashMap::new(),
+            dedup,
+        };
+        for route in routes {
+            cache.insert(route.clone());
+        }
+        cache
+    }
+    /// Inserts `route`, returning the cached route with the same identity it replaces.
+    fn insert(&mut self, route: Route) -> Option<Route> {
+        let siblings = self.routes.entry(RouteIdentity::from(&route)).or_default();
+        match siblings.iter_mut().find(|v| v.same_identity(&route)) {
+            Some(cached) => Some(std::mem::replace(cached, route)),
+            None => {
+                siblings.push(route);
+                None
+            }
+        }
+    }
+    /// Removes the cached route with the same identity as `route`.
+    fn remove(&mut self, route: &Route) -> Option<Route> {
+        let key = RouteIdentity::from(route);
+        let siblings = self.routes.get_mut(&key)?;
+        let index = siblings.iter().position(|v| v.same_identity(route))?;
+        let removed = siblings.remove(index);
+        if siblings.is_empty() {
+            self.routes.remove(&key);
+        }
+        Some(removed)
+    }
+    /// Replaces the route that `new` changes in place, returning it.
+    fn replace(&mut self, new: Route) -> Option<Route> {
+        let siblings = self.routes.entry(RouteIdentity::from(&new)).or_default();
+        // The system replaces the first matching route unless the nexthop tells them apart
+        let index = siblings
+            .iter()
+            .position(|v| v.same_identity(&new))
+            .or((!siblings.is_empty()).then_some(0));
+        match index {
+            Some(index) => Some(std::mem::replace(&mut siblings[index], new)),
+            None => {
+                siblings.push(new);
+                None
+            }
+        }
+    }
+    /// Applies `change` to the cache, filling in the previous route of a change event.
+    ///
+    /// Returns `None` if deduplication is on and the cache already reflects the change.
+    pub(crate) fn track(&mut self, change: RouteChange) -> Option<RouteChange> {
+        match change {
+            RouteChange::Add(route) => {
+                let cached = self.insert(route.clone());
+                if self.dedup && cached.as_ref() == Some(&route) {
+                    return None;
+                }
+                Some(RouteChange::Add(route))
+            }
+            RouteChange::Delete(route) => {
+                let cached = self.remove(&route);
+                if self.dedup && cached.is_none() {
+                    return None;
+                }
+                Some(RouteChange::Delete(route))
+            }
+            RouteChange::Change { old, new } => {
+                let cached = self.replace(new.clone());
+                Some(RouteChange::Change {
+                    old: old.or(cached),
+                    new,
+                })
+            }
+        }
+    }
+}
+
+/// Queue of the parsed events of the last message read, and of the replayed initial table.
+#[cfg(unix)]
+pub(crate) struct EventQueue {
+    list: VecDeque<(RouteChange, EventOrigin)>,
+    deferred: Option<io::Error>,
+    suppress_own_changes: bool,
+    ignore_system_routes: bool,
+    cache: Option<RouteCache>,
+    snapshot: Vec<Route>,
+}
+
+#[cfg(unix)]
+impl EventQueue {
+    pub(crate) fn new(config: &ListenerConfig) -> io::Result<Self> {
+        config.check_queue()?;
+        let routes = if config.needs_routes() {
+            crate::RouteManager::new()?.list()?
+        } else {
+            Vec::new()
+        };
+        Ok(Self::with_routes(config, routes))
+    }
+    /// Like [`new`](Self::new), but starts with an empty route cache if the routes cannot be
+    /// listed.
+    pub(crate) fn new_or_empty(config: &ListenerConfig) -> Self {
+        Self::new(config).unwrap_or_else(|_| Self::with_routes(config, Vec::new()))
+    }
+    fn with_routes(config: &ListenerConfig, routes: Vec<Route>) -> Self {
+        Self {
+            cache: config.route_cache(&routes),
+            list: config
+                .replay(routes.clone())
+                .map(|change| (change, EventOrigin::default()))
+                .collect(),
+            snapshot: if config.snapshot { routes } else { Vec::new() },
+            deferred: None,
+            suppress_own_changes: config.suppress_own_changes,
+            ignore_system_routes: config.ignore_system_routes,
+        }
+    }
+    pub(crate) fn push(&mut self, change: RouteChange, origin: EventOrigin) {
+        // Filtered events still update the cache
+        let change = match &mut self.cache {
+            Some(cache) => cache.track(change),
+            None => Some(change),
+        };
+        let Some(change) = change else {
+            return;
+        };
+        if self.suppress_own_changes && crate::is_own_origin(&origin) {
+            return;
+        }
+        if self.ignore_system_routes && origin.system_route {
+            return;
+        }
+        self.list.push_back((change, origin));
+    }
+    /// Pops the next event, or reports a deferred error first.
+    pub(crate) fn pop(&mut self) -> io::Result<Option<RouteChange>> {
+        Ok(self.pop_with_origin()?.map(|(change, _)| change))
+    }
+    pub(crate) fn take_snapshot(&mut self) -> Vec<Route> {
+        std::mem::take(&mut self.snapshot)
+    }
+    /// Keeps `error` to be reported by the next pop.
+    pub(crate) fn defer_error(&mut self, error: io::Error) {
+        self.deferred = Some(error);
+    }
+    pub(crate) fn pop_with_origin(&mut self) -> io::Result<Option<(RouteChange, EventOrigin)>> {
+        if let Some(error) = self.deferred.take() {
+            return Err(error);
+        }
+        Ok(self.list.pop_front())
+    }
+}
+
+impl crate::RouteManager {
+    /// Returns the current routing table together with a listener for changes to it.
+    ///
+    /// The listener subscribes before the table is dumped, so no change is lost in between;
+    /// changes the snapshot already reflects are not delivered again. Applying the events in
+    /// order to the snapshot keeps it in sync with the system.
+    pub fn subscribe() -> io::Result<(Vec<Route>, crate::RouteListener)> {
+        Self::subscribe_with_config(ListenerConfig::default())
+    }
+    /// Like [`subscribe`](Self::subscribe), with the given listener configuration.
+    pub fn subscribe_with_config(
+        mut config: ListenerConfig,
+    ) -> io::Result<(Vec<Route>, crate::RouteListener)> {
+        config.snapshot = true;
+        let mut listener = crate::RouteListener::with_config(config)?;
+        Ok((listener.take_snapshot(), listener))
+    }
+}
+
+#[cfg(any(feature = "async", feature = "async_io"))]
+impl crate::AsyncRouteManager {
+    /// Returns the current routing table together with an asynchronous listener for changes
+    /// to it.
+    ///
+    /// See [`RouteManager::subscribe`](crate::RouteManager::subscribe); the table is dumped
+    /// synchronously.
+    pub fn subscribe() -> io::Result<(Vec<Route>, crate::AsyncRouteListener)> {
+        Self::subscribe_with_config(ListenerConfig::default())
+    }
+    /// Like [`subscribe`](Self::subscribe), with the given listener configuration.
+    pub fn subscribe_with_config(
+        mut config: ListenerConfig,
+    ) -> io::Result<(Vec<Route>, crate::AsyncRouteListener)> {
+        config.snapshot = true;
+        let mut listener = crate::AsyncRouteListener::with_config(config)?;
+        Ok((listener.take_snapshot(), listener))
+    }
+}
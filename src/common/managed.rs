@@ -0,0 +1,107 @@
+use crate::{Route, RouteManager};
+use std::io;
+
+/// Route manager that deletes the routes it added when it is closed or dropped, created by
+/// [`ManagedRouteManager::new`] or [`RouteManager::into_managed`].
+///
+/// Every route added through it is recorded until it is deleted through it again. A
+/// recorded route that vanished in the meantime, e.g. because its interface went down, can
+/// be installed again with [`restore`](Self::restore); cleanup treats routes that are
+/// already gone as removed.
+pub struct ManagedRouteManager {
+    manager: RouteManager,
+    routes: Vec<Route>,
+}
+
+impl RouteManager {
+    /// Turns the manager into one that deletes the routes it adds on close or drop.
+    pub fn into_managed(self) -> ManagedRouteManager {
+        ManagedRouteManager {
+            manager: self,
+            routes: Vec::new(),
+        }
+    }
+}
+
+impl ManagedRouteManager {
+    /// Creates a managed route manager.
+    pub fn new() -> io::Result<Self> {
+        Ok(RouteManager::new()?.into_managed())
+    }
+    /// Adds a route and records it for cleanup.
+    pub fn add(&mut self, route: &Route) -> io::Result<()> {
+        self.manager.add(route)?;
+        if !self.routes.contains(route) {
+            self.routes.push(route.clone());
+        }
+        Ok(())
+    }
+    /// Deletes a route; a recorded route is no longer cleaned up.
+    pub fn delete(&mut self, route: &Route) -> io::Result<()> {
+        self.manager.delete(route)?;
+        self.routes.retain(|v| v != route);
+        Ok(())
+    }
+    /// Returns the recorded routes, in the order they were added.
+    pub fn routes(&self) -> &[Route] {
+        &self.routes
+    }
+    /// Stops managing `route`, which stays installed. Returns whether it was recorded.
+    pub fn forget(&mut self, route: &Route) -> bool {
+        let len = self.routes.len();
+        self.routes.retain(|v| v != route);
+        self.routes.len() != len
+    }
+    /// Adds the recorded routes that are no longer installed again, e.g. after an interface
+    /// flap, and returns them.
+    ///
+    /// Routes whose interface is still down fail to install and are skipped; the first such
+    /// error is returned only if no route could be restored.
+    pub fn restore(&mut self) -> io::Result<Vec<Route>> {
+        let mut restored = Vec::new();
+        let mut error = None;
+        let results = self.manager.add_all(&self.routes);
+        for (route, rs) in self.routes.iter().zip(results) {
+            match rs {
+                Ok(()) => restored.push(route.clone()),
+                Err(e) if crate::is_route_exists(&e) => {}
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        match error {
+            Some(e) if restored.is_empty() => Err(e),
+            _ => Ok(restored),
+        }
+    }
+    /// Returns the underlying manager. Routes added through it directly are not managed.
+    pub fn inner(&mut self) -> &mut RouteManager {
+        &mut self.manager
+    }
+    /// Deletes all recorded routes, most recent first.
+    ///
+    /// Every route is attempted; the first error is returned. Routes that are already gone
+    /// count as deleted.
+    pub fn close(mut self) -> io::Result<()> {
+        self.cleanup()
+    }
+    fn cleanup(&mut self) -> io::Result<()> {
+        let mut result = Ok(());
+        while let Some(route) = self.routes.pop() {
+            if let Err(e) = self.manager.delete_if_present(&route) {
+                debug_event!(route = %route, error = %e, "failed to delete managed route");
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+}
+
+impl Drop for ManagedRouteManager {
+    fn drop(&mut self) {
+        let _ = self.cleanup();
+    }
+}
@@ -0,0 +1,130 @@
+use crate::Route;
+use std::io;
+use std::net::IpAddr;
+#[cfg(any(feature = "async", feature = "async_io"))]
+use std::{future::Future, pin::Pin};
+
+/// Object-safe interface over route managers, e.g. for `Box<dyn RouteManagement>`.
+///
+/// Implemented by [`RouteManager`](crate::RouteManager) and, with the `mock` and `command`
+/// features, by `MockRouteManager` and `CommandRouteManager`.
+pub trait RouteManagement {
+    /// Lists all current routes.
+    fn list(&mut self) -> io::Result<Vec<Route>>;
+    /// Adds a new route.
+    fn add(&mut self, route: &Route) -> io::Result<()>;
+    /// Deletes an existing route.
+    fn delete(&mut self, route: &Route) -> io::Result<()>;
+    /// Route Lookup by Destination Address
+    fn find_route(&mut self, dest: &IpAddr) -> io::Result<Option<Route>>;
+}
+
+/// Boxed future returned by [`AsyncRouteManagement`] methods.
+#[cfg(any(feature = "async", feature = "async_io"))]
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Object-safe asynchronous counterpart of [`RouteManagement`].
+///
+/// Implemented by [`AsyncRouteManager`](crate::AsyncRouteManager) and, with the `mock`
+/// feature, by `MockRouteManager`.
+#[cfg(any(feature = "async", feature = "async_io"))]
+pub trait AsyncRouteManagement {
+    /// Asynchronously lists all current routes.
+    fn list(&mut self) -> BoxFuture<'_, io::Result<Vec<Route>>>;
+    /// Asynchronously adds a new route.
+    fn add<'a>(&'a mut self, route: &'a Route) -> BoxFuture<'a, io::Result<()>>;
+    /// Asynchronously deletes an existing route.
+    fn delete<'a>(&'a mut self, route: &'a Route) -> BoxFuture<'a, io::Result<()>>;
+    /// Asynchronously looks up the route used for a destination address.
+    fn find_route<'a>(&'a mut self, dest: &'a IpAddr) -> BoxFuture<'a, io::Result<Option<Route>>>;
+}
+
+impl RouteManagement for crate::RouteManager {
+    fn list(&mut self) -> io::Result<Vec<Route>> {
+        crate::RouteManager::list(self)
+    }
+    fn add(&mut self, route: &Route) -> io::Result<()> {
+        crate::RouteManager::add(self, route)
+    }
+    fn delete(&mut self, route: &Route) -> io::Result<()> {
+        crate::RouteManager::delete(self, route)
+    }
+    fn find_route(&mut self, dest: &IpAddr) -> io::Result<Option<Route>> {
+        crate::RouteManager::find_route(self, dest)
+    }
+}
+
+#[cfg(any(feature = "async", feature = "async_io"))]
+impl AsyncRouteManagement for crate::AsyncRouteManager {
+    fn list(&mut self) -> BoxFuture<'_, io::Result<Vec<Route>>> {
+        Box::pin(crate::AsyncRouteManager::list(self))
+    }
+    fn add<'a>(&'a mut self, route: &'a Route) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(crate::AsyncRouteManager::add(self, route))
+    }
+    fn delete<'a>(&'a mut self, route: &'a Route) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(crate::AsyncRouteManager::delete(self, route))
+    }
+    fn find_route<'a>(&'a mut self, dest: &'a IpAddr) -> BoxFuture<'a, io::Result<Option<Route>>> {
+        Box::pin(async move {
+            #[cfg(target_os = "windows")]
+            {
+                crate::RouteManager::new()?.find_route(dest)
+            }
+            #[cfg(not(target_os = "windows"))]
+            {
+                let list = crate::AsyncRouteManager::list(self).await?;
+                Ok(super::select_route(&list, dest).cloned())
+            }
+        })
+    }
+}
+
+#[cfg(feature = "mock")]
+impl RouteManagement for crate::MockRouteManager {
+    fn list(&mut self) -> io::Result<Vec<Route>> {
+        crate::MockRouteManager::list(self)
+    }
+    fn add(&mut self, route: &Route) -> io::Result<()> {
+        crate::MockRouteManager::add(self, route)
+    }
+    fn delete(&mut self, route: &Route) -> io::Result<()> {
+        crate::MockRouteManager::delete(self, route)
+    }
+    fn find_route(&mut self, dest: &IpAddr) -> io::Result<Option<Route>> {
+        crate::MockRouteManager::find_route(self, dest)
+    }
+}
+
+#[cfg(feature = "command")]
+impl RouteManagement for crate::CommandRouteManager {
+    fn list(&mut self) -> io::Result<Vec<Route>> {
+        crate::CommandRouteManager::list(self)
+    }
+    fn add(&mut self, route: &Route) -> io::Result<()> {
+        crate::CommandRouteManager::add(self, route)
+    }
+    fn delete(&mut self, route: &Route) -> io::Result<()> {
+        crate::CommandRouteManager::delete(self, route)
+    }
+    fn find_route(&mut self, dest: &IpAddr) -> io::Result<Option<Route>> {
+        crate::CommandRouteManager::find_route(self, dest)
+    }
+}
+
+#[cfg(feature = "mock")]
+#[cfg(any(feature = "async", feature = "async_io"))]
+impl AsyncRouteManagement for crate::MockRouteManager {
+    fn list(&mut self) -> BoxFuture<'_, io::Result<Vec<Route>>> {
+        Box::pin(async move { crate::MockRouteManager::list(self) })
+    }
+    fn add<'a>(&'a mut self, route: &'a Route) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move { crate::MockRouteManager::add(self, route) })
+    }
+    fn delete<'a>(&'a mut self, route: &'a Route) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move { crate::MockRouteManager::delete(self, route) })
+    }
+    fn find_route<'a>(&'a mut self, dest: &'a IpAddr) -> BoxFuture<'a, io::Result<Option<Route>>> {
+        Box::pin(async move { crate::MockRouteManager::find_route(self, dest) })
+    }
+}
@@ -0,0 +1,84 @@
+use crate::Route;
+use std::borrow::Cow;
+use std::net::{IpAddr, Ipv6Addr};
+
+fn unmap(addr: IpAddr) -> IpAddr {
+    match addr {
+        IpAddr::V6(ip) => ip.to_ipv4_mapped().map_or(addr, IpAddr::V4),
+        v => v,
+    }
+}
+
+fn unmap_prefix(addr: Ipv6Addr, prefix: u8) -> Option<(IpAddr, u8)> {
+    if prefix < 96 {
+        return None;
+    }
+    Some((IpAddr::V4(addr.to_ipv4_mapped()?), prefix - 96))
+}
+
+impl Route {
+    /// Converts IPv4-mapped addresses to plain IPv4.
+    ///
+    /// A mapped destination of `/96` or longer becomes the IPv4 network with the prefix
+    /// shortened by 96; gateway and source addresses of the resulting IPv4 route are unmapped
+    /// as well. Other routes are returned unchanged.
+    pub fn to_canonical(&self) -> Route {
+        let mut route = self.clone();
+        if let IpAddr::V6(ip) = route.destination {
+            if let Some((destination, prefix)) = unmap_prefix(ip, route.prefix) {
+                route.destination = destination;
+                route.prefix = prefix;
+            }
+        }
+        route.unmap_nexthops();
+        route
+    }
+    fn unmap_nexthops(&mut self) {
+        if !self.destination.is_ipv4() {
+            return;
+        }
+        self.gateway = self.gateway.map(unmap);
+        #[cfg(unix)]
+        {
+            self.pref_source = self.pref_source.map(unmap);
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(IpAddr::V6(ip)) = self.source {
+            if let Some((source, prefix)) = unmap_prefix(ip, self.source_prefix) {
+                self.source = Some(source);
+                self.source_prefix = prefix;
+            }
+        }
+    }
+    /// The route converted with [`to_canonical`](Self::to_canonical) if `normalize` is set,
+    /// as by a manager with mapped addresses normalized.
+    pub(crate) fn normalized(&self, normalize: bool) -> Cow<'_, Route> {
+        if normalize {
+            Cow::Owned(self.to_canonical())
+        } else {
+            Cow::Borrowed(self)
+        }
+    }
+    /// The form in which the route is handed to the system: nexthops of IPv4 routes in the
+    /// mapped form, which denote IPv4 hosts, are converted to IPv4.
+    pub(crate) fn input_form(&self) -> Cow<'_, Route> {
+        let mapped =
+            |v: Option<IpAddr>| matches!(v, Some(IpAddr::V6(ip)) if ip.to_ipv4_mapped().is_some());
+        #[cfg(unix)]
+        let pref_source = self.pref_source;
+        #[cfg(not(unix))]
+        let pref_source = None;
+        #[cfg(target_os = "linux")]
+        let source = self.source;
+        #[cfg(not(target_os = "linux"))]
+        let source = None;
+        if self.destination.is_ipv4()
+            && (mapped(self.gateway) || mapped(pref_source) || mapped(source))
+        {
+            let mut route = self.clone();
+            route.unmap_nexthops();
+            return Cow::Owned(route);
+        }
+        Cow::Borrowed(self)
+    }
+}
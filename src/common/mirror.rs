@@ -0,0 +1,174 @@
+use crate::{Route, RouteChange, RouteListener, RouteManager, RouteTable};
+use std::io;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::thread;
+
+/// In-memory copy of the kernel routing table, kept up to date by a background listener.
+///
+/// The listener is started before the initial dump so no change is lost in between.
+/// When the kernel reports that events were dropped (`ENOBUFS`), the table is dumped again.
+///
+/// Dropping the mirror stops the background thread. Without the `shutdown` feature the
+/// thread only notices this on the next route event.
+pub struct RouteTableMirror {
+    inner: Arc<Inner>,
+    #[cfg(feature = "shutdown")]
+    shutdown_handle: crate::RouteListenerShutdown,
+}
+
+struct Inner {
+    table: RwLock<RouteTable>,
+    stop: AtomicBool,
+    running: AtomicBool,
+}
+
+impl RouteTableMirror {
+    /// Dumps the current table and starts mirroring changes on a background thread.
+    pub fn spawn() -> io::Result<Self> {
+        let listener = RouteManager::listener()?;
+        #[cfg(feature = "shutdown")]
+        let shutdown_handle = listener.shutdown_handle()?;
+        let inner = Arc::new(Inner {
+            table: RwLock::new(RouteTable::new()),
+            stop: AtomicBool::new(false),
+            running: AtomicBool::new(true),
+        });
+        inner.resync()?;
+        let thread_inner = inner.clone();
+        thread::Builder::new()
+            .name("route-table-mirror".into())
+            .spawn(move || thread_inner.run(listener))?;
+        Ok(Self {
+            inner,
+            #[cfg(feature = "shutdown")]
+            shutdown_handle,
+        })
+    }
+    /// Longest-prefix-match lookup on the mirrored table.
+    pub fn lookup(&self, dest: &IpAddr) -> Option<Route> {
+        self.read().lookup(dest).cloned()
+    }
+    /// Returns all mirrored routes.
+    pub fn list(&self) -> Vec<Route> {
+        self.read().iter().cloned().collect()
+    }
+    /// Locks the mirrored table for reading, e.g. to iterate it without copying.
+    pub fn read(&self) -> RwLockReadGuard<'_, RouteTable> {
+        self.inner.table.read().unwrap()
+    }
+    /// Replaces the mirrored table with a fresh dump of the kernel table.
+    pub fn resync(&self) -> io::Result<()> {
+        self.inner.resync()
+    }
+    /// Returns whether the background listener is still running.
+    pub fn is_running(&self) -> bool {
+        self.inner.running.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for RouteTableMirror {
+    fn drop(&mut self) {
+        self.inner.stop.store(true, Ordering::Relaxed);
+        #[cfg(feature = "shutdown")]
+        let _ = self.shutdown_handle.shutdown();
+    }
+}
+
+impl Inner {
+    fn resync(&self) -> io::Result<()> {
+        let table: RouteTable = RouteManager::new()?.list()?.into_iter().collect();
+        *self.table.write().unwrap() = table;
+        Ok(())
+    }
+    fn run(&self, mut listener: RouteListener) {
+        while !self.stop.load(Ordering::Relaxed) {
+            match listener.listen() {
+                Ok(change) => self.apply(change),
+                Err(e) if crate::os_error(&e) == Some(crate::ENOBUFS) => {
+                    let _ = self.resync();
+                }
+                Err(_) => break,
+            }
+        }
+        self.running.store(false, Ordering::Relaxed);
+    }
+    fn apply(&self, change: RouteChange) {
+        self.table.write().unwrap().apply(change);
+    }
+}
+
+#[cfg(feature = "async")]
+impl RouteTable {
+    /// Dumps the current table and spawns a task on the current tokio runtime that applies
+    /// every change to it, returning a receiver of the up-to-date table.
+    ///
+    /// Unlike [`RouteTableMirror`] the table is published through a `tokio::sync::watch`
+    /// channel, so any number of tasks can await changes and read the current snapshot
+    /// without each owning a listener. Snapshots held by receivers stay unchanged; the next
+    /// change copies the table instead. When the kernel reports that events were dropped
+    /// (`ENOBUFS`), the table is dumped again on the blocking thread pool, retried with
+    /// backoff until it succeeds. The task ends once every receiver is dropped, or when
+    /// listening fails, after which [`changed`](tokio::sync::watch::Receiver::changed)
+    /// returns an error.
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub fn watch() -> io::Result<tokio::sync::watch::Receiver<Arc<RouteTable>>> {
+        let (routes, listener) = crate::AsyncRouteManager::subscribe()?;
+        let (tx, rx) = tokio::sync::watch::channel(Arc::new(routes.into_iter().collect()));
+        tokio::spawn(run_watch(tx, listener));
+        Ok(rx)
+    }
+}
+
+#[cfg(feature = "async")]
+async fn run_watch(
+    tx: tokio::sync::watch::Sender<Arc<RouteTable>>,
+    mut listener: crate::AsyncRouteListener,
+) {
+    loop {
+        let change = tokio::select! {
+            change = listener.listen() => change,
+            _ = tx.closed() => return,
+        };
+        match change {
+            Ok(change) => tx.send_modify(|table| Arc::make_mut(table).apply(change)),
+            Err(e) if crate::os_error(&e) == Some(crate::ENOBUFS) => {
+                let Some((routes, new_listener)) = resubscribe(&tx).await else {
+                    return;
+                };
+                listener = new_listener;
+                tx.send_replace(Arc::new(routes.into_iter().collect()));
+            }
+            Err(_) => return,
+        }
+    }
+}
+
+/// Longest wait between two attempts to dump the table again.
+#[cfg(feature = "async")]
+const MAX_RESUBSCRIBE_DELAY: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Dumps the table again with a new listener, off the runtime threads, until it succeeds;
+/// `None` once every receiver is dropped.
+#[cfg(feature = "async")]
+async fn resubscribe(
+    tx: &tokio::sync::watch::Sender<Arc<RouteTable>>,
+) -> Option<(Vec<Route>, crate::AsyncRouteListener)> {
+    let mut delay = std::time::Duration::from_millis(100);
+    loop {
+        match tokio::task::spawn_blocking(crate::AsyncRouteManager::subscribe).await {
+            Ok(Ok(subscribed)) => return Some(subscribed),
+            Ok(Err(_e)) => {
+                debug_event!(error = %_e, "failed to dump the route table again");
+            }
+            Err(_) => return None,
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = tx.closed() => return None,
+        }
+        delay = (delay * 2).min(MAX_RESUBSCRIBE_DELAY);
+    }
+}
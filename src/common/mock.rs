@@ -0,0 +1,195 @@
+use crate::{Route, RouteChange, RouteTable};
+use std::io;
+use std::net::IpAddr;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// (Feature `mock`) In-memory stand-in for [`RouteManager`](crate::RouteManager).
+///
+/// Simulates a routing table without touching the kernel, so code built on this crate can be
+/// tested without root. Clones share the same table and listeners.
+#[derive(Clone, Default)]
+pub struct MockRouteManager {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    table: RouteTable,
+    listeners: Vec<Sender<RouteChange>>,
+}
+
+impl State {
+    fn notify(&mut self, change: &RouteChange) {
+        self.listeners.retain(|v| v.send(change.clone()).is_ok());
+    }
+}
+
+fn same_route(a: &Route, b: &Route) -> bool {
+    if !a.same_destination(b) {
+        return false;
+    }
+    if a.gateway.is_some() && b.gateway.is_some() && a.gateway != b.gateway {
+        return false;
+    }
+    if a.if_index.is_some() && b.if_index.is_some() && a.if_index != b.if_index {
+        return false;
+    }
+    if a.if_name.is_some() && b.if_name.is_some() && a.if_name != b.if_name {
+        return false;
+    }
+    #[cfg(target_os = "linux")]
+    if a.table != b.table {
+        return false;
+    }
+    true
+}
+
+impl MockRouteManager {
+    /// Creates a manager with an empty table.
+    pub fn new() -> io::Result<Self> {
+        Ok(Self::default())
+    }
+    /// Creates a manager whose table already holds `routes`, without emitting events.
+    pub fn with_routes<I: IntoIterator<Item = Route>>(routes: I) -> Self {
+        let manager = Self::default();
+        manager.state.lock().unwrap().table.extend(routes);
+        manager
+    }
+    /// Creates a listener that receives every change made through this manager or its clones.
+    pub fn listener(&self) -> io::Result<MockRouteListener> {
+        let (tx, rx) = channel();
+        self.state.lock().unwrap().listeners.push(tx);
+        Ok(MockRouteListener { receiver: rx })
+    }
+    /// Lists all routes in the simulated table.
+    pub fn list(&mut self) -> io::Result<Vec<Route>> {
+        Ok(self.state.lock().unwrap().table.iter().cloned().collect())
+    }
+    /// Adds a route. Fails with `AlreadyExists` if a matching route is present.
+    pub fn add(&mut self, route: &Route) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state
+            .table
+            .get(&route.destination, route.prefix)
+            .iter()
+            .any(|v| same_route(v, route))
+        {
+            return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+        }
+        state.table.insert(route.clone());
+        state.notify(&RouteChange::Add(route.clone()));
+        Ok(())
+    }
+    /// Deletes the first route matching `route`. Fails with `NotFound` if there is none.
+    ///
+    /// Unset gateway and interface fields of `route` match any value.
+    pub fn delete(&mut self, route: &Route) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let Some(existing) = state
+            .table
+            .get(&route.destination, route.prefix)
+            .iter()
+            .find(|v| same_route(v, route))
+            .cloned()
+        else {
+            return Err(io::Error::from(io::ErrorKind::NotFound));
+        };
+        state.table.remove(&existing);
+        state.notify(&RouteChange::Delete(existing));
+        Ok(())
+    }
+    /// Route Lookup by Destination Address
+    pub fn find_route(&mut self, dest: &IpAddr) -> io::Result<Option<Route>> {
+        let state = self.state.lock().unwrap();
+        Ok(super::select_route(state.table.iter(), dest).cloned())
+    }
+    /// Applies `change` to the simulated table as if the kernel had reported it, and
+    /// forwards it to all listeners.
+    ///
+    /// A change event without `old` is forwarded with the route it replaces in the table.
+    pub fn inject(&self, mut change: RouteChange) {
+        let mut state = self.state.lock().unwrap();
+        match &mut change {
+            RouteChange::Add(route) => state.table.insert(route.clone()),
+            RouteChange::Delete(route) => {
+                state.table.remove(route);
+            }
+            RouteChange::Change { old, new } => {
+                let stale: Vec<Route> = state
+                    .table
+                    .get(&new.destination, new.prefix)
+                    .iter()
+                    .filter(|v| same_route(v, new))
+                    .cloned()
+                    .collect();
+                if old.is_none() {
+                    *old = stale.first().cloned();
+                }
+                for v in stale {
+                    state.table.remove(&v);
+                }
+                state.table.insert(new.clone());
+            }
+        }
+        state.notify(&change);
+    }
+    /// Forwards `change` to all listeners without touching the simulated table.
+    pub fn inject_event(&self, change: RouteChange) {
+        self.state.lock().unwrap().notify(&change);
+    }
+}
+
+/// (Feature `mock`) Listener created by [`MockRouteManager::listener`].
+pub struct MockRouteListener {
+    receiver: Receiver<RouteChange>,
+}
+
+impl MockRouteListener {
+    /// Waits for the next change. Fails with `BrokenPipe` once the manager and all its
+    /// clones are dropped.
+    pub fn listen(&mut self) -> io::Result<RouteChange> {
+        self.receiver
+            .recv()
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+    /// Waits at most `timeout` for the next change. Fails with `TimedOut` if none arrives.
+    pub fn listen_timeout(&mut self, timeout: Duration) -> io::Result<RouteChange> {
+        self.receiver.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => io::Error::from(io::ErrorKind::TimedOut),
+            RecvTimeoutError::Disconnected => io::Error::from(io::ErrorKind::BrokenPipe),
+        })
+    }
+    /// Returns the next pending change without blocking.
+    pub fn try_listen(&mut self) -> io::Result<Option<RouteChange>> {
+        match self.receiver.try_recv() {
+            Ok(change) => Ok(Some(change)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(io::Error::from(io::ErrorKind::BrokenPipe)),
+        }
+    }
+    /// Returns all pending changes without blocking, possibly none.
+    pub fn try_drain(&mut self) -> io::Result<Vec<RouteChange>> {
+        let mut events: Vec<RouteChange> = self.receiver.try_iter().collect();
+        if events.is_empty() {
+            events.extend(self.try_listen()?);
+        }
+        Ok(events)
+    }
+    /// Waits for the next change and returns it together with the pending changes, at most
+    /// `max` (but at least one) in total.
+    pub fn listen_batch(&mut self, max: usize) -> io::Result<Vec<RouteChange>> {
+        let mut events = vec![self.listen()?];
+        events.extend(self.receiver.try_iter().take(max.saturating_sub(1)));
+        Ok(events)
+    }
+    /// Waits for the next change and appends it to `events` together with the pending
+    /// changes. Returns the number of changes appended.
+    pub fn listen_into(&mut self, events: &mut Vec<RouteChange>) -> io::Result<usize> {
+        let start = events.len();
+        events.push(self.listen()?);
+        events.extend(self.receiver.try_iter());
+        Ok(events.len() - start)
+    }
+}
@@ -1,12 +1,121 @@
 use std::cmp::Ordering;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+#[cfg(not(target_os = "linux"))]
+#[cfg(unix)]
+use std::time::SystemTime;
 use std::{fmt, io};
 
+#[cfg(any(feature = "async", feature = "async_io"))]
+mod async_listener;
+#[cfg(unix)]
+mod buf_pool;
+mod builder;
+mod cache_info;
+mod coalesce;
+#[cfg(feature = "command")]
+mod command;
+mod compact;
+#[cfg(feature = "serde")]
+mod config;
+mod dry_run;
+mod ensure;
+mod error;
+mod flags;
+mod forward;
+mod if_cache;
+mod interface;
+mod key;
+mod listener;
+mod managed;
+mod management;
+mod mapped;
+mod mirror;
+#[cfg(feature = "mock")]
+mod mock;
+mod permissions;
+mod pin;
+mod platform_ext;
+mod prefix;
+mod reliable;
+mod resolve;
+mod retry;
+mod stats;
+mod table;
+mod validation;
+mod watch;
+#[cfg(any(feature = "async", feature = "async_io"))]
+#[cfg(unix)]
+pub(crate) use async_listener::timeout_with;
+#[cfg(unix)]
+pub(crate) use buf_pool::BufPool;
+pub use builder::RouteManagerBuilder;
+pub use cache_info::RouteCacheInfo;
+pub use coalesce::*;
+#[cfg(feature = "command")]
+#[cfg(unix)]
+pub(crate) use command::unsupported;
+#[cfg(feature = "command")]
+pub use command::CommandRouteManager;
+pub use compact::{CompactRoute, CompactRouteChange};
+#[cfg(feature = "serde")]
+pub use config::*;
+pub(crate) use dry_run::RequestKind;
+pub use dry_run::RouteRequest;
+pub use ensure::EnsuredRoute;
+pub use error::*;
+pub use flags::RouteFlags;
+#[cfg(any(feature = "async", feature = "async_io"))]
+pub use forward::AsyncEventSender;
+pub use forward::EventSender;
+pub use if_cache::InterfaceCache;
+pub(crate) use if_cache::{cached_if_index_to_name, cached_if_name_to_index, route_if_name};
+pub use interface::*;
+pub use key::RouteKey;
+#[cfg(windows)]
+#[cfg(windows)]
+pub(crate) use listener::overflow_error;
+#[cfg(unix)]
+pub(crate) use listener::set_recv_buffer;
+#[cfg(unix)]
+pub(crate) use listener::EventQueue;
+#[cfg(windows)]
+pub(crate) use listener::RouteCache;
+pub(crate) use listener::ENOBUFS;
+pub use listener::{EventOrigin, ListenerConfig, OverflowPolicy, DEFAULT_QUEUE_CAPACITY};
+pub use managed::ManagedRouteManager;
+pub use management::*;
+pub use mirror::*;
+#[cfg(feature = "mock")]
+pub use mock::*;
+pub use pin::*;
+pub use platform_ext::PlatformExt;
+pub(crate) use platform_ext::*;
+pub use prefix::*;
+pub use reliable::*;
+#[cfg(target_os = "linux")]
+pub use resolve::PolicyRule;
+pub use resolve::ResolveOptions;
+pub use retry::RetryPolicy;
+pub(crate) use retry::*;
+pub use stats::RouteStats;
+pub use table::*;
+pub use validation::*;
+pub use watch::*;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RouteChange {
     Add(Route),
     Delete(Route),
-    Change(Route),
+    /// A route was modified in place.
+    ///
+    /// `old` is the route before the change, as last seen by the listener. It is only filled
+    /// in by listeners created with [`ListenerConfig::with_previous_routes`], and is `None` if
+    /// the listener has not seen the route before.
+    Change {
+        old: Option<Route>,
+        new: Route,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -14,7 +123,8 @@ pub struct Route {
     pub(crate) destination: IpAddr,
     pub(crate) prefix: u8,
     pub(crate) gateway: Option<IpAddr>,
-    pub(crate) if_name: Option<String>,
+    pub(crate) gateway_scope_id: Option<u32>,
+    pub(crate) if_name: Option<Arc<str>>,
     pub(crate) if_index: Option<u32>,
     #[cfg(target_os = "linux")]
     pub(crate) table: u8,
@@ -22,17 +132,51 @@ pub struct Route {
     pub(crate) source: Option<IpAddr>,
     #[cfg(target_os = "linux")]
     pub(crate) source_prefix: u8,
-    #[cfg(any(
-        target_os = "linux",
-        target_os = "freebsd",
-        target_os = "openbsd",
-        target_os = "netbsd"
-    ))]
+    #[cfg(target_os = "linux")]
+    pub(crate) protocol: Option<u8>,
+    #[cfg(unix)]
     pub(crate) pref_source: Option<IpAddr>,
     #[cfg(any(target_os = "windows", target_os = "linux"))]
     pub(crate) metric: Option<u32>,
     #[cfg(target_os = "windows")]
     pub(crate) luid: Option<u64>,
+    #[cfg(unix)]
+    pub(crate) raw_attrs: Vec<RawAttribute>,
+    #[cfg(not(target_os = "linux"))]
+    #[cfg(unix)]
+    pub(crate) raw_flags: u32,
+    #[cfg(not(target_os = "linux"))]
+    #[cfg(unix)]
+    pub(crate) mtu: Option<u32>,
+    #[cfg(not(target_os = "linux"))]
+    #[cfg(unix)]
+    pub(crate) expire: Option<SystemTime>,
+    #[cfg(target_os = "openbsd")]
+    pub(crate) priority: u8,
+    pub(crate) reported: Reported,
+}
+/// (Unix only) A raw route attribute that is not modelled by [`Route`].
+///
+/// On Linux, `kind` is the netlink attribute type (`RTA_*`) and `value` is its payload.
+/// On BSD, `kind` is the sockaddr slot (`RTAX_*`) and `value` is the raw sockaddr, including
+/// its `sa_len` and `sa_family` header.
+#[cfg(unix)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RawAttribute {
+    kind: u16,
+    value: Vec<u8>,
+}
+#[cfg(unix)]
+impl RawAttribute {
+    pub fn new(kind: u16, value: Vec<u8>) -> Self {
+        Self { kind, value }
+    }
+    pub fn kind(&self) -> u16 {
+        self.kind
+    }
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
 }
 impl Route {
     pub fn destination(&self) -> IpAddr {
@@ -44,8 +188,19 @@ impl Route {
     pub fn gateway(&self) -> Option<IpAddr> {
         self.gateway
     }
-    pub fn if_name(&self) -> Option<&String> {
-        self.if_name.as_ref()
+    /// Scope (zone) ID of an IPv6 link-local gateway, usually the interface index.
+    pub fn gateway_scope_id(&self) -> Option<u32> {
+        self.gateway_scope_id
+    }
+    /// Name of the network interface. Routes read from the system share the allocation of
+    /// each name.
+    pub fn if_name(&self) -> Option<&str> {
+        self.if_name.as_deref()
+    }
+    /// Returns the name of the network interface, looking it up by index if the route does
+    /// not carry it, e.g. because [`InterfaceCache::set_resolve_names`] is disabled.
+    pub fn resolve_if_name(&self) -> Option<Arc<str>> {
+        self.get_name()
     }
     pub fn if_index(&self) -> Option<u32> {
         self.if_index
@@ -62,12 +217,12 @@ impl Route {
     pub fn source_prefix(&self) -> u8 {
         self.source_prefix
     }
-    #[cfg(any(
-        target_os = "linux",
-        target_os = "freebsd",
-        target_os = "openbsd",
-        target_os = "netbsd"
-    ))]
+    /// (Linux only) Routing protocol (`RTPROT_*`) that installed the route.
+    #[cfg(target_os = "linux")]
+    pub fn protocol(&self) -> Option<u8> {
+        self.protocol
+    }
+    #[cfg(unix)]
     pub fn pref_source(&self) -> Option<IpAddr> {
         self.pref_source
     }
@@ -79,6 +234,55 @@ impl Route {
     pub fn luid(&self) -> Option<u64> {
         self.luid
     }
+    /// (Windows only) The metric Windows actually uses to select between routes: the route
+    /// metric plus the interface metric of its interface.
+    #[cfg(target_os = "windows")]
+    pub fn effective_metric(&self) -> io::Result<u32> {
+        use windows_sys::Win32::Networking::WinSock::{AF_INET, AF_INET6};
+        let family = if self.destination.is_ipv4() {
+            AF_INET
+        } else {
+            AF_INET6
+        };
+        let if_index = self.get_index();
+        if if_index.is_none() && self.luid.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("route {self} has no interface"),
+            ));
+        }
+        let row = crate::ip_interface_row(if_index.unwrap_or(0), self.luid, family)?;
+        Ok(self.metric.unwrap_or(0).saturating_add(row.Metric))
+    }
+    /// (Unix only) Raw attributes that were attached to the route or not recognized when parsing.
+    #[cfg(unix)]
+    pub fn raw_attrs(&self) -> &[RawAttribute] {
+        &self.raw_attrs
+    }
+    /// (BSD only) Extra `RTF_*` flags set on the route message, see
+    /// [`with_raw_flags`](Self::with_raw_flags); always 0 for routes read from the system.
+    #[cfg(not(target_os = "linux"))]
+    #[cfg(unix)]
+    pub fn raw_flags(&self) -> u32 {
+        self.raw_flags
+    }
+    /// (BSD only) Path MTU of the route (`rmx_mtu`), if the kernel reports one.
+    #[cfg(not(target_os = "linux"))]
+    #[cfg(unix)]
+    pub fn mtu(&self) -> Option<u32> {
+        self.mtu
+    }
+    /// (BSD only) Time at which the route expires (`rmx_expire`), for routes that do.
+    #[cfg(not(target_os = "linux"))]
+    #[cfg(unix)]
+    pub fn expire(&self) -> Option<SystemTime> {
+        self.expire
+    }
+    /// (OpenBSD only) Route priority (`RTP_*`); lower values are preferred.
+    #[cfg(target_os = "openbsd")]
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
 }
 impl Route {
     pub fn new(destination: IpAddr, prefix: u8) -> Self {
@@ -86,6 +290,7 @@ impl Route {
             destination,
             prefix,
             gateway: None,
+            gateway_scope_id: None,
             if_name: None,
             if_index: None,
             #[cfg(target_os = "linux")]
@@ -94,27 +299,78 @@ impl Route {
             source: None,
             #[cfg(target_os = "linux")]
             source_prefix: 0,
-            #[cfg(any(
-                target_os = "linux",
-                target_os = "freebsd",
-                target_os = "openbsd",
-                target_os = "netbsd"
-            ))]
+            #[cfg(target_os = "linux")]
+            protocol: None,
+            #[cfg(unix)]
             pref_source: None,
             #[cfg(any(target_os = "windows", target_os = "linux"))]
             metric: None,
             #[cfg(target_os = "windows")]
             luid: None,
+            #[cfg(unix)]
+            raw_attrs: Vec::new(),
+            #[cfg(not(target_os = "linux"))]
+            #[cfg(unix)]
+            raw_flags: 0,
+            #[cfg(not(target_os = "linux"))]
+            #[cfg(unix)]
+            mtu: None,
+            #[cfg(not(target_os = "linux"))]
+            #[cfg(unix)]
+            expire: None,
+            #[cfg(target_os = "openbsd")]
+            priority: 0,
+            reported: Reported::default(),
+        }
+    }
+    /// Returns a copy of the route to modify with the `with_*` methods, e.g. to change the
+    /// gateway of a listed route and [`update`](crate::RouteManager::update) it.
+    ///
+    /// Details the system reported with the route (see [`platform_ext`](Self::platform_ext))
+    /// are dropped. If the interface index is known, the interface name and, on Windows, the
+    /// LUID are dropped as well, so that [`with_if_index`](Self::with_if_index) alone moves the
+    /// route to another interface.
+    pub fn to_builder(&self) -> Route {
+        let mut route = self.clone();
+        route.reported = Reported::default();
+        if route.if_index.is_some() {
+            route.if_name = None;
+            #[cfg(target_os = "windows")]
+            {
+                route.luid = None;
+            }
         }
+        route
+    }
+    /// Creates the IPv4 default route `0.0.0.0/0`.
+    pub fn default_v4() -> Self {
+        Self::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0)
+    }
+    /// Creates the IPv6 default route `::/0`.
+    pub fn default_v6() -> Self {
+        Self::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0)
     }
-    /// Sets the gateway (next hop) for the route.
+    /// Creates a host route to `addr` (/32 for IPv4, /128 for IPv6).
+    pub fn host(addr: IpAddr) -> Self {
+        let prefix = if addr.is_ipv4() { 32 } else { 128 };
+        Self::new(addr, prefix)
+    }
+    /// Sets the gateway (next hop) for the route, clearing the scope ID of a previous gateway.
     pub fn with_gateway(mut self, gateway: IpAddr) -> Self {
         self.gateway = Some(gateway);
+        self.gateway_scope_id = None;
+        self
+    }
+    /// Sets an IPv6 gateway together with its scope (zone) ID, as needed for link-local
+    /// (`fe80::/10`) gateways.
+    pub fn with_gateway_scoped(mut self, gateway: Ipv6Addr, scope_id: u32) -> Self {
+        self.gateway = Some(IpAddr::V6(gateway));
+        self.gateway_scope_id = Some(scope_id);
         self
     }
     /// Sets the network interface by name (e.g., "eth0").
-    pub fn with_if_name(mut self, if_name: String) -> Self {
-        self.if_name = Some(if_name);
+    pub fn with_if_name(mut self, if_name: impl Into<Arc<str>>) -> Self {
+        self.if_name = Some(if_name.into());
         self
     }
     /// Sets the network interface by index.
@@ -136,13 +392,17 @@ impl Route {
         self.source_prefix = prefix;
         self
     }
-    /// Sets the preferred source address for the route.
-    #[cfg(any(
-        target_os = "linux",
-        target_os = "freebsd",
-        target_os = "openbsd",
-        target_os = "netbsd"
-    ))]
+    /// (Linux only) Sets the routing protocol (`RTPROT_*`).
+    ///
+    /// Added routes default to `RTPROT_STATIC`; on delete, the protocol only restricts which
+    /// route is removed when it is set.
+    #[cfg(target_os = "linux")]
+    pub fn with_protocol(mut self, protocol: u8) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+    /// (Unix only) Sets the preferred source address for the route.
+    #[cfg(unix)]
     pub fn with_pref_source(mut self, pref_source: IpAddr) -> Self {
         self.pref_source = Some(pref_source);
         self
@@ -159,91 +419,247 @@ impl Route {
         self.luid = Some(luid);
         self
     }
+    /// (Unix only) Attaches a raw attribute that is sent as-is when the route is added or deleted.
+    ///
+    /// On BSD, the kind is the `RTAX_*` slot of the sockaddr. Adding or deleting the route
+    /// fails with `InvalidInput` if the slot does not exist, is taken by another raw
+    /// attribute, or is filled in from the route itself: the destination, gateway and
+    /// netmask, and the interface or preferred source if set.
+    #[cfg(unix)]
+    pub fn with_raw_attr(mut self, attr: RawAttribute) -> Self {
+        self.raw_attrs.push(attr);
+        self
+    }
+    /// (BSD only) Sets extra `RTF_*` flags that are OR'ed into the route message flags.
+    ///
+    /// They are only sent, never parsed: for routes read from the system the reported
+    /// `rtm_flags` are in [`platform_ext`](Self::platform_ext).
+    #[cfg(not(target_os = "linux"))]
+    #[cfg(unix)]
+    pub fn with_raw_flags(mut self, flags: u32) -> Self {
+        self.raw_flags = flags;
+        self
+    }
+    /// (OpenBSD only) Sets the route priority (`RTP_*`, e.g. `RTP_STATIC`), which selects
+    /// between routes to the same destination from different sources (static, ospfd, bgpd).
+    ///
+    /// 0 (`RTP_NONE`) lets the kernel pick its default for added routes and matches any
+    /// priority on delete.
+    #[cfg(target_os = "openbsd")]
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
 }
 impl Route {
+    /// Validates the route.
+    ///
+    /// On failure, the returned error has kind `InvalidInput` and wraps [`ValidationErrors`].
     pub fn check(&self) -> io::Result<()> {
-        if self.destination.is_ipv4() {
-            if self.prefix > 32 {
-                return Err(io::Error::other("prefix error"));
-            }
-        } else if self.prefix > 128 {
-            return Err(io::Error::other("prefix error"));
+        Ok(self.validate()?)
+    }
+    /// network address
+    pub fn network(&self) -> IpAddr {
+        network_address(self.destination, self.prefix)
+    }
+    /// Determine whether the target address is included in the route
+    ///
+    /// IPv4-mapped IPv6 addresses match the corresponding IPv4 routes and vice versa.
+    pub fn contains(&self, dest: &IpAddr) -> bool {
+        if dest.is_ipv4() == self.destination.is_ipv4() {
+            return self.network() == network_address(*dest, self.prefix);
+        }
+        let route = self.to_canonical();
+        let dest = dest.to_canonical();
+        dest.is_ipv4()
+            && route.destination.is_ipv4()
+            && route.network() == network_address(dest, route.prefix)
+    }
+    /// Determine whether both routes cover exactly the same network
+    pub fn same_destination(&self, other: &Route) -> bool {
+        self.destination.is_ipv4() == other.destination.is_ipv4()
+            && self.prefix == other.prefix
+            && self.network() == other.network()
+    }
+    /// Determine whether the networks of both routes share any address
+    pub fn overlaps(&self, other: &Route) -> bool {
+        if self.destination.is_ipv4() != other.destination.is_ipv4() {
+            return false;
         }
-        if let Some(index) = self.if_index {
-            crate::if_index_to_name(index)?;
+        let prefix = self.prefix.min(other.prefix);
+        network_address(self.destination, prefix) == network_address(other.destination, prefix)
+    }
+    /// Split the network of the route into the minimal set of more specific routes that cover
+    /// it except for the networks of `excluded`.
+    ///
+    /// The returned routes keep the nexthop and other attributes of this route. Excluding nothing
+    /// from `0.0.0.0/0` returns it unchanged; excluding a single address yields one route per
+    /// prefix length (the generalized "two /1s" split).
+    pub fn exclude(&self, excluded: &[Route]) -> Vec<Route> {
+        let max = if self.destination.is_ipv4() { 32 } else { 128 };
+        let excluded: Vec<&Route> = excluded
+            .iter()
+            .filter(|v| v.destination.is_ipv4() == self.destination.is_ipv4() && v.prefix <= max)
+            .collect();
+        let mut route = self.clone();
+        route.prefix = route.prefix.min(max);
+        route.destination = route.network();
+        let mut list = Vec::new();
+        route.exclude_into(&excluded, &mut list);
+        list
+    }
+    fn exclude_into(self, excluded: &[&Route], list: &mut Vec<Route>) {
+        if excluded
+            .iter()
+            .any(|v| v.prefix <= self.prefix && v.contains(&self.destination))
+        {
+            return;
         }
-        if let Some(gateway) = self.gateway {
-            if gateway.is_ipv4() != self.destination.is_ipv4() {
-                return Err(io::Error::other("gateway error"));
-            }
+        if !excluded.iter().any(|v| self.overlaps(v)) {
+            list.push(self);
+            return;
         }
-        if let Some(name) = self.if_name.as_ref() {
-            let index = crate::if_name_to_index(name)?;
-            if let Some(if_index) = self.if_index {
-                if index != if_index {
-                    return Err(io::Error::other("if_index mismatch"));
-                }
+        // An excluded network lies strictly inside this one, so split it in halves
+        let mut low = self;
+        low.prefix += 1;
+        let mut high = low.clone();
+        high.destination = match high.destination {
+            IpAddr::V4(ip) => IpAddr::V4(Ipv4Addr::from(
+                u32::from(ip) | (1 << (32 - high.prefix as u32)),
+            )),
+            IpAddr::V6(ip) => IpAddr::V6(Ipv6Addr::from(
+                u128::from(ip) | (1 << (128 - high.prefix as u32)),
+            )),
+        };
+        low.exclude_into(excluded, list);
+        high.exclude_into(excluded, list);
+    }
+    /// Collapse routes into the minimal set of routes covering the same addresses.
+    ///
+    /// Only routes that are identical apart from destination and prefix (same nexthop, metric,
+    /// table, ...) are merged: networks contained in another are dropped and adjacent halves
+    /// are joined into their parent, repeatedly. Invalid prefix lengths are clamped.
+    pub fn aggregate(routes: &[Route]) -> Vec<Route> {
+        // Routes with equal attributes, as (template, [(network, prefix)])
+        let mut groups: Vec<(Route, Vec<(u128, u8)>)> = Vec::new();
+        for route in routes {
+            let max = if route.destination.is_ipv4() { 32 } else { 128 };
+            let prefix = route.prefix.min(max);
+            let network = match network_address(route.destination, prefix) {
+                IpAddr::V4(ip) => u32::from(ip) as u128,
+                IpAddr::V6(ip) => u128::from(ip),
+            };
+            let mut template = route.clone();
+            template.destination = match route.destination {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            };
+            template.prefix = 0;
+            match groups.iter_mut().find(|(v, _)| *v == template) {
+                Some((_, list)) => list.push((network, prefix)),
+                None => groups.push((template, vec![(network, prefix)])),
             }
         }
-        Ok(())
-    }
-    /// network address
-    pub fn network(&self) -> IpAddr {
-        Route::network_addr(self.destination, self.prefix)
-    }
-    fn network_addr(ip: IpAddr, prefix: u8) -> IpAddr {
-        match ip {
-            IpAddr::V4(ipv4) => {
-                let ip = u32::from(ipv4);
-                let mask = if prefix == 0 { 0 } else { !0 << (32 - prefix) };
-                IpAddr::V4(Ipv4Addr::from(ip & mask))
+        let mut rs = Vec::new();
+        for (template, mut list) in groups {
+            let bits = if template.destination.is_ipv4() {
+                32
+            } else {
+                128
+            };
+            list.sort_unstable();
+            let mut merged: Vec<(u128, u8)> = Vec::with_capacity(list.len());
+            for (network, prefix) in list {
+                if let Some(&(last, last_prefix)) = merged.last() {
+                    if last_prefix <= prefix && prefix_contains(last, last_prefix, network, bits) {
+                        continue;
+                    }
+                }
+                merged.push((network, prefix));
+                // Join sibling halves as long as the last two entries form a parent
+                while let [.., (low, low_prefix), (high, high_prefix)] = merged[..] {
+                    if low_prefix != high_prefix || low_prefix == 0 {
+                        break;
+                    }
+                    let bit = 1u128 << (bits - low_prefix as u32);
+                    if low & bit != 0 || low | bit != high {
+                        break;
+                    }
+                    merged.truncate(merged.len() - 2);
+                    merged.push((low, low_prefix - 1));
+                }
             }
-            IpAddr::V6(ipv6) => {
-                let ip = u128::from(ipv6);
-                let mask = if prefix == 0 {
-                    0
-                } else {
-                    !0_u128 << (128 - prefix)
+            for (network, prefix) in merged {
+                let mut route = template.clone();
+                route.destination = match template.destination {
+                    IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from(network as u32)),
+                    IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(network)),
                 };
-                IpAddr::V6(Ipv6Addr::from(ip & mask))
+                route.prefix = prefix;
+                rs.push(route);
             }
         }
+        rs
     }
-    /// Determine whether the target address is included in the route
-    pub fn contains(&self, dest: &IpAddr) -> bool {
-        if dest.is_ipv4() != self.destination.is_ipv4() {
+    /// Determine whether both routes target the same destination (and table on Linux)
+    /// but differ in nexthop or metric
+    pub fn conflicts_with(&self, other: &Route) -> bool {
+        if !self.same_destination(other) {
+            return false;
+        }
+        #[cfg(target_os = "linux")]
+        if self.table != other.table {
             return false;
         }
-        let route_network = self.network();
-        let addr_network = Route::network_addr(*dest, self.prefix);
-        route_network == addr_network
+        #[cfg(any(target_os = "windows", target_os = "linux"))]
+        if self.metric != other.metric {
+            return true;
+        }
+        !self.same_nexthop(other)
+    }
+    pub(crate) fn same_table(&self, other: &Route) -> bool {
+        // Table 0 is installed into the main table
+        #[cfg(target_os = "linux")]
+        {
+            let table = |v: &Route| if v.table == 0 { 254 } else { v.table };
+            table(self) == table(other)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = other;
+            true
+        }
+    }
+    fn same_nexthop(&self, other: &Route) -> bool {
+        if self.gateway != other.gateway {
+            return false;
+        }
+        if let (Some(a), Some(b)) = (self.if_index, other.if_index) {
+            return a == b;
+        }
+        if let (Some(a), Some(b)) = (&self.if_name, &other.if_name) {
+            return a == b;
+        }
+        self.get_index() == other.get_index()
     }
     /// Subnet Mask
     pub fn mask(&self) -> IpAddr {
-        match self.destination {
-            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from(
-                u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0),
-            )),
-            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(
-                u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0),
-            )),
-        }
+        prefix::mask(self.destination, self.prefix)
     }
     #[allow(dead_code)]
     pub(crate) fn get_index(&self) -> Option<u32> {
         self.if_index.or_else(|| {
             if let Some(name) = &self.if_name {
-                crate::if_name_to_index(name).ok()
+                crate::cached_if_name_to_index(name).ok()
             } else {
                 None
             }
         })
     }
-    #[allow(dead_code)]
-    pub(crate) fn get_name(&self) -> Option<String> {
+    pub(crate) fn get_name(&self) -> Option<Arc<str>> {
         self.if_name.clone().or_else(|| {
             if let Some(index) = &self.if_index {
-                crate::if_index_to_name(*index).ok()
+                crate::cached_if_index_to_name(*index).ok()
             } else {
                 None
             }
@@ -267,24 +683,447 @@ impl Ord for Route {
 }
 impl crate::RouteManager {
     /// Route Lookup by Destination Address
+    ///
+    /// The route is selected from the listed routes the way the kernel selects it: only
+    /// routes of the family of `dest` that are up and neither reject nor blackhole traffic
+    /// are considered, and the longest prefix wins, then the lowest metric. On Linux the
+    /// `local`, `main` and `default` tables are consulted in this order and other tables are
+    /// ignored, as they only apply through policy rules.
     #[cfg(not(target_os = "windows"))]
-    pub fn find_route(&mut self, dest: &IpAddr) -> io::Result<Option<Route>> {
-        let mut list = self.list()?;
-        list.sort_by(|v1, v2| v2.cmp(v1));
-        let rs = list
+    pub fn find_route(&self, dest: &IpAddr) -> io::Result<Option<Route>> {
+        let list = self.list()?;
+        Ok(select_route(&list, dest).cloned())
+    }
+    /// (Windows/Linux) Returns the lowest metric, starting from the metric of `route`
+    /// (0 if unset), that no existing route to the same destination (and table on Linux) uses.
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    pub fn next_free_metric(&self, route: &Route) -> io::Result<u32> {
+        let used: Vec<u32> = self
+            .list()?
             .iter()
-            .filter(|v| v.destination.is_ipv4() == dest.is_ipv4())
-            .find(|v| v.contains(dest))
-            .cloned();
-        Ok(rs)
+            .filter(|v| v.same_destination(route) && v.same_table(route))
+            .map(|v| v.metric.unwrap_or(0))
+            .collect();
+        let mut metric = route.metric.unwrap_or(0);
+        while used.contains(&metric) {
+            metric = metric
+                .checked_add(1)
+                .ok_or_else(|| io::Error::other("no free metric"))?;
+        }
+        Ok(metric)
+    }
+    /// (Windows/Linux) Adds `route` with the metric chosen by
+    /// [`next_free_metric`](Self::next_free_metric) and returns the route as installed.
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    pub fn add_with_auto_metric(&self, route: &Route) -> io::Result<Route> {
+        let metric = self.next_free_metric(route)?;
+        let route = route.clone().with_metric(metric);
+        self.add(&route)?;
+        Ok(route)
+    }
+    /// Fills in the output interface of a route that has a gateway but no interface, using the
+    /// interface of the route the system would use to reach the gateway.
+    ///
+    /// Routes that already name an interface, or have no gateway, are returned unchanged.
+    pub fn infer_interface(&self, route: &Route) -> io::Result<Route> {
+        let mut route = route.clone();
+        #[cfg(target_os = "windows")]
+        if route.luid.is_some() {
+            return Ok(route);
+        }
+        let Some(gateway) = route.gateway else {
+            return Ok(route);
+        };
+        if route.if_index.is_some() || route.if_name.is_some() {
+            return Ok(route);
+        }
+        let via = self.find_route(&gateway)?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no route to gateway {gateway}"),
+            )
+        })?;
+        route.if_index = via.get_index();
+        route.if_name = via.if_name;
+        #[cfg(target_os = "windows")]
+        {
+            route.luid = via.luid;
+        }
+        if route.if_index.is_none() && route.if_name.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no interface found for gateway {gateway}"),
+            ));
+        }
+        Ok(route)
+    }
+    /// Adds `route` after filling in its output interface with
+    /// [`infer_interface`](Self::infer_interface), and returns the route as installed.
+    pub fn add_with_inferred_interface(&self, route: &Route) -> io::Result<Route> {
+        let route = self.infer_interface(route)?;
+        self.add(&route)?;
+        Ok(route)
+    }
+    /// Lists all default routes (`0.0.0.0/0` and `::/0`) across tables and interfaces.
+    ///
+    /// IPv4 routes come first, each family ordered by preference: by effective metric on
+    /// Windows, by table (main first) and metric on Linux, and in kernel order elsewhere.
+    pub fn list_default_routes(&self) -> io::Result<Vec<Route>> {
+        let mut list: Vec<Route> = self.list()?.into_iter().filter(|v| v.prefix == 0).collect();
+        list.sort_by_cached_key(|v| (v.destination.is_ipv6(), default_route_priority(v)));
+        Ok(list)
+    }
+    /// Lists the routes whose output interface is `if_index`.
+    ///
+    /// On Linux the kernel filters the dump (4.20 and later); elsewhere the full table is
+    /// fetched and filtered.
+    pub fn list_by_interface(&self, if_index: u32) -> io::Result<Vec<Route>> {
+        #[cfg(target_os = "linux")]
+        let list = self.list_oif(if_index)?;
+        #[cfg(not(target_os = "linux"))]
+        let list = self.list()?;
+        Ok(list
+            .into_iter()
+            .filter(|v| v.get_index() == Some(if_index))
+            .collect())
+    }
+    /// Returns the gateways of `family` that routes through the interface `if_index` use,
+    /// e.g. the LAN gateway of the physical uplink before rerouting traffic into a tunnel.
+    ///
+    /// Each gateway is returned once; gateways of default routes come first, in the order of
+    /// [`list_default_routes`](Self::list_default_routes), followed by those of other routes.
+    pub fn gateway_for_interface(
+        &self,
+        if_index: u32,
+        family: crate::IpFamily,
+    ) -> io::Result<Vec<IpAddr>> {
+        let mut routes: Vec<Route> = self
+            .list_by_interface(if_index)?
+            .into_iter()
+            .filter(|v| v.destination.is_ipv4() == (family == crate::IpFamily::V4))
+            .collect();
+        routes.sort_by_cached_key(|v| (v.prefix != 0, default_route_priority(v)));
+        let mut gateways = Vec::new();
+        for gateway in routes.into_iter().filter_map(|v| v.gateway) {
+            if !gateways.contains(&gateway) {
+                gateways.push(gateway);
+            }
+        }
+        Ok(gateways)
+    }
+    /// Lists the routes whose output interface is named `if_name`.
+    pub fn list_by_interface_name(&self, if_name: &str) -> io::Result<Vec<Route>> {
+        let if_index = crate::cached_if_name_to_index(if_name)?;
+        self.list_by_interface(if_index)
+    }
+    /// Deletes a route, treating a route that does not exist as success.
+    ///
+    /// Returns whether a route was actually removed.
+    pub fn delete_if_present(&self, route: &Route) -> io::Result<bool> {
+        match self.delete(route) {
+            Ok(()) => Ok(true),
+            Err(e) if is_route_not_found(&e) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+    /// Adds the IPv4 route `v4` and its IPv6 counterpart `v6` together: if `v6` cannot be
+    /// added, `v4` is deleted again and the error is returned, so that a dual-stack setup is
+    /// not left half-configured.
+    ///
+    /// Should deleting `v4` fail as well, it stays installed; the error of adding `v6` is
+    /// still the one returned.
+    pub fn add_dual(&self, v4: &Route, v6: &Route) -> io::Result<()> {
+        check_dual(v4, v6)?;
+        self.add(v4)?;
+        if let Err(e) = self.add(v6) {
+            if let Err(_rollback) = self.delete(v4) {
+                debug_event!(route = %v4, error = %_rollback, "failed to roll back dual-stack route");
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+    /// Makes `new` the default route of its family (and table on Linux) in place of the
+    /// current ones, so that there is never a moment without a default route, and returns the
+    /// route as installed.
+    ///
+    /// (Windows/Linux) `new` is added first, with its metric if that is lower than those of
+    /// the current default routes and otherwise with one that is where possible, and checked
+    /// to be listed, and deleted again if it is not; only then are the old routes deleted.
+    /// Elsewhere the current default route is changed in place, which the kernel does
+    /// atomically.
+    pub fn replace_default_route(&self, new: &Route) -> io::Result<Route> {
+        check_default(new)?;
+        let olds = replaced_defaults(new, self.list()?);
+        #[cfg(any(target_os = "windows", target_os = "linux"))]
+        {
+            let (route, kept) = preferred_default(new, &olds);
+            if kept.is_none() {
+                self.add(&route)?;
+                if let Err(e) = self.list().and_then(|routes| check_listed(&route, &routes)) {
+                    if let Err(_rollback) = self.delete(&route) {
+                        debug_event!(route = %route, error = %_rollback, "failed to roll back default route");
+                    }
+                    return Err(e);
+                }
+            }
+            for (i, old) in olds.iter().enumerate() {
+                if Some(i) != kept {
+                    self.delete_if_present(old)?;
+                }
+            }
+            Ok(route)
+        }
+        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+        {
+            if olds.is_empty() {
+                self.add(new)?;
+            } else {
+                self.update(new)?;
+            }
+            Ok(new.clone())
+        }
+    }
+    /// Adds `routes` one after the other and returns the result for each in order.
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn add_all(&self, routes: &[Route]) -> Vec<io::Result<()>> {
+        routes.iter().map(|route| self.add(route)).collect()
+    }
+}
+#[cfg(any(feature = "async", feature = "async_io"))]
+impl crate::AsyncRouteManager {
+    /// Asynchronously deletes a route, treating a route that does not exist as success.
+    ///
+    /// Returns whether a route was actually removed.
+    pub async fn delete_if_present(&self, route: &Route) -> io::Result<bool> {
+        match self.delete(route).await {
+            Ok(()) => Ok(true),
+            Err(e) if is_route_not_found(&e) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+    /// Asynchronously adds the IPv4 route `v4` and its IPv6 counterpart `v6` together. See
+    /// `RouteManager::add_dual`.
+    pub async fn add_dual(&self, v4: &Route, v6: &Route) -> io::Result<()> {
+        check_dual(v4, v6)?;
+        self.add(v4).await?;
+        if let Err(e) = self.add(v6).await {
+            if let Err(_rollback) = self.delete(v4).await {
+                debug_event!(route = %v4, error = %_rollback, "failed to roll back dual-stack route");
+            }
+            return Err(e);
+        }
+        Ok(())
     }
+    /// Asynchronously makes `new` the default route of its family without a moment without a
+    /// default route. See `RouteManager::replace_default_route`.
+    pub async fn replace_default_route(&self, new: &Route) -> io::Result<Route> {
+        check_default(new)?;
+        let olds = replaced_defaults(new, self.list().await?);
+        #[cfg(any(target_os = "windows", target_os = "linux"))]
+        {
+            let (route, kept) = preferred_default(new, &olds);
+            if kept.is_none() {
+                self.add(&route).await?;
+                let listed = match self.list().await {
+                    Ok(routes) => check_listed(&route, &routes),
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = listed {
+                    if let Err(_rollback) = self.delete(&route).await {
+                        debug_event!(route = %route, error = %_rollback, "failed to roll back default route");
+                    }
+                    return Err(e);
+                }
+            }
+            for (i, old) in olds.iter().enumerate() {
+                if Some(i) != kept {
+                    self.delete_if_present(old).await?;
+                }
+            }
+            Ok(route)
+        }
+        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+        {
+            if olds.is_empty() {
+                self.add(new).await?;
+            } else {
+                self.update(new).await?;
+            }
+            Ok(new.clone())
+        }
+    }
+}
+/// Checks that `v4` is an IPv4 route and `v6` an IPv6 route.
+fn check_dual(v4: &Route, v6: &Route) -> io::Result<()> {
+    if v4.destination.is_ipv4() && v6.destination.is_ipv6() {
+        return Ok(());
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "add_dual takes an IPv4 route and an IPv6 route",
+    ))
+}
+fn check_default(route: &Route) -> io::Result<()> {
+    if route.prefix == 0 {
+        return Ok(());
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "replace_default_route takes a default route",
+    ))
+}
+/// Returns the default routes among `list` that `new` replaces.
+fn replaced_defaults(new: &Route, list: Vec<Route>) -> Vec<Route> {
+    list.into_iter()
+        .filter(|v| v.prefix == 0 && v.same_destination(new) && v.same_table(new))
+        .collect()
+}
+/// Returns `new` with a metric preferred over those of the default routes `olds`, or the one of
+/// them that already is `new`, together with its index.
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn preferred_default(new: &Route, olds: &[Route]) -> (Route, Option<usize>) {
+    let installed = |v: &Route| match new.metric {
+        Some(metric) => v.same_nexthop(new) && v.metric.unwrap_or(0) == metric,
+        None => v.same_nexthop(new),
+    };
+    if let Some(i) = olds.iter().position(installed) {
+        return (olds[i].clone(), Some(i));
+    }
+    let used: Vec<u32> = olds.iter().map(|v| v.metric.unwrap_or(0)).collect();
+    let Some(&best) = used.iter().min() else {
+        return (new.clone(), None);
+    };
+    let metric = match new.metric {
+        Some(metric) if metric < best => metric,
+        _ if best > 0 => best - 1,
+        // Nothing beats metric 0; the old routes are still only deleted once `new` is in
+        _ => {
+            let mut metric = new.metric.unwrap_or(0);
+            while used.contains(&metric) {
+                let Some(next) = metric.checked_add(1) else {
+                    break;
+                };
+                metric = next;
+            }
+            metric
+        }
+    };
+    (new.clone().with_metric(metric), None)
+}
+/// Checks that `route` was added, as it is among the listed routes `list`.
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn check_listed(route: &Route, list: &[Route]) -> io::Result<()> {
+    if list
+        .iter()
+        .any(|v| v.same_destination(route) && v.same_table(route) && v.same_nexthop(route))
+    {
+        return Ok(());
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} not listed after adding it", route.display_compact()),
+    ))
+}
+/// Selects the route the system uses to reach `dest` among `routes`.
+///
+/// Only routes of the family of `dest` are considered, an IPv4-mapped IPv6 address counting
+/// as IPv4, and routes reported as down, rejecting or blackholing are skipped. The longest
+/// prefix wins, then the lowest metric (on Windows including the interface metric). On Linux
+/// the tables are consulted in the order of the default rules, `local`, `main` and `default`,
+/// and routes in other tables, which only apply through policy rules, are ignored.
+#[cfg_attr(windows, allow(dead_code))]
+pub(crate) fn select_route<'a>(
+    routes: impl IntoIterator<Item = &'a Route>,
+    dest: &IpAddr,
+) -> Option<&'a Route> {
+    let dest = dest.to_canonical();
+    routes
+        .into_iter()
+        .filter(|v| v.destination.is_ipv4() == dest.is_ipv4() && v.contains(&dest))
+        .filter(|v| is_usable(v))
+        .filter_map(|v| Some((table_rank(v)?, v)))
+        .min_by_key(|(rank, v)| {
+            (
+                *rank,
+                std::cmp::Reverse(v.prefix),
+                default_route_priority(v),
+            )
+        })
+        .map(|(_, v)| v)
+}
+/// Whether the system forwards packets over the route; routes built by the caller are
+/// assumed to be usable.
+#[cfg_attr(windows, allow(dead_code))]
+fn is_usable(route: &Route) -> bool {
+    let flags = route.flags();
+    (flags & (RouteFlags::REJECT | RouteFlags::BLACKHOLE)).is_empty()
+        && (!route.is_reported() || flags.contains(RouteFlags::UP))
+}
+/// Position of the table of `route` in the lookup, `None` if it is not consulted.
+#[cfg_attr(windows, allow(dead_code))]
+fn table_rank(route: &Route) -> Option<u8> {
+    #[cfg(target_os = "linux")]
+    return match route.table {
+        libc::RT_TABLE_LOCAL => Some(0),
+        0 | libc::RT_TABLE_MAIN => Some(1),
+        libc::RT_TABLE_DEFAULT => Some(2),
+        _ => None,
+    };
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = route;
+        Some(0)
+    }
+}
+/// Sort key of a default route; lower is preferred.
+fn default_route_priority(route: &Route) -> u64 {
+    #[cfg(target_os = "windows")]
+    {
+        let metric = route.metric.unwrap_or(0);
+        route.effective_metric().unwrap_or(metric) as u64
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let other_table = !matches!(route.table, 0 | libc::RT_TABLE_MAIN) as u64;
+        (other_table << 32) | route.metric.unwrap_or(0) as u64
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        let _ = route;
+        0
+    }
+}
+/// Whether the system rejected a delete because no such route is installed.
+pub(crate) fn is_route_not_found(e: &io::Error) -> bool {
+    #[cfg(unix)]
+    return os_error(e) == Some(libc::ESRCH);
+    #[cfg(windows)]
+    return os_error(e) == Some(windows_sys::Win32::Foundation::ERROR_NOT_FOUND as i32);
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = e;
+        false
+    }
+}
+pub(crate) fn is_route_exists(e: &io::Error) -> bool {
+    #[cfg(unix)]
+    return os_error(e) == Some(libc::EEXIST);
+    #[cfg(windows)]
+    return os_error(e) == Some(windows_sys::Win32::Foundation::ERROR_OBJECT_ALREADY_EXISTS as i32);
+    #[cfg(not(any(unix, windows)))]
+    return e.kind() == io::ErrorKind::AlreadyExists;
 }
 impl fmt::Display for RouteChange {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             RouteChange::Add(route) => write!(f, "Add({route})"),
             RouteChange::Delete(route) => write!(f, "Delete({route})"),
-            RouteChange::Change(route) => write!(f, "Change({route})"),
+            RouteChange::Change {
+                old: Some(old),
+                new,
+            } => write!(f, "Change({old} -> {new})"),
+            RouteChange::Change { old: None, new } => write!(f, "Change({new})"),
         }
     }
 }
@@ -296,9 +1135,10 @@ impl fmt::Display for Route {
             self.destination, self.prefix
         )?;
 
-        match self.gateway {
-            Some(addr) => write!(f, "{addr}"),
-            None => write!(f, "None"),
+        match (self.gateway, self.gateway_scope_id) {
+            (Some(addr), Some(scope_id)) => write!(f, "{addr}%{scope_id}"),
+            (Some(addr), None) => write!(f, "{addr}"),
+            (None, _) => write!(f, "None"),
         }?;
 
         write!(f, ", if_index: ")?;
@@ -344,12 +1184,7 @@ impl fmt::Display for Route {
             };
         }
 
-        #[cfg(any(
-            target_os = "linux",
-            target_os = "freebsd",
-            target_os = "openbsd",
-            target_os = "netbsd"
-        ))]
+        #[cfg(unix)]
         {
             write!(f, ", pref_source: ")?;
             match self.pref_source {
@@ -358,6 +1193,20 @@ impl fmt::Display for Route {
             }?;
         }
 
+        #[cfg(not(target_os = "linux"))]
+        #[cfg(unix)]
+        {
+            #[cfg(target_os = "openbsd")]
+            write!(f, ", priority: {}", self.priority)?;
+            if let Some(mtu) = self.mtu {
+                write!(f, ", mtu: {mtu}")?;
+            }
+            if let Some(expire) = self.expire {
+                let remaining = expire.duration_since(SystemTime::now()).unwrap_or_default();
+                write!(f, ", expire: {}s", remaining.as_secs())?;
+            }
+        }
+
         write!(f, " }}")
     }
 }
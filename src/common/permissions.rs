@@ -0,0 +1,76 @@
+use std::io;
+
+impl crate::RouteManager {
+    /// Checks whether the process may modify routes, without modifying any.
+    ///
+    /// Requires `CAP_NET_ADMIN` on Linux, root on BSD and an elevated token on Windows (members
+    /// of the Network Configuration Operators group may modify routes without elevation, but
+    /// are reported as lacking it). Fails with `PermissionDenied` otherwise, so applications
+    /// can report the problem at startup.
+    pub fn check_permissions() -> io::Result<()> {
+        if has_privilege()? {
+            return Ok(());
+        }
+        #[cfg(target_os = "linux")]
+        let msg = "modifying routes requires CAP_NET_ADMIN";
+        #[cfg(all(unix, not(target_os = "linux")))]
+        let msg = "modifying routes requires root";
+        #[cfg(windows)]
+        let msg = "modifying routes requires an elevated process";
+        #[cfg(not(any(unix, windows)))]
+        let msg = "modifying routes is not supported on this target";
+        Err(io::Error::new(io::ErrorKind::PermissionDenied, msg))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn has_privilege() -> io::Result<bool> {
+    const CAP_NET_ADMIN: u32 = 12;
+    let status = std::fs::read_to_string("/proc/self/status")?;
+    let caps = status
+        .lines()
+        .find_map(|v| v.strip_prefix("CapEff:"))
+        .and_then(|v| u64::from_str_radix(v.trim(), 16).ok())
+        .ok_or_else(|| io::Error::other("no effective capabilities in /proc/self/status"))?;
+    Ok(caps & (1 << CAP_NET_ADMIN) != 0)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn has_privilege() -> io::Result<bool> {
+    Ok(unsafe { libc::geteuid() } == 0)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn has_privilege() -> io::Result<bool> {
+    Err(crate::unsupported_target())
+}
+
+#[cfg(windows)]
+fn has_privilege() -> io::Result<bool> {
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::Security::{
+        GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY,
+    };
+    use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+    let mut token: HANDLE = std::ptr::null_mut();
+    if unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut elevation = TOKEN_ELEVATION::default();
+    let mut len = 0;
+    let rs = unsafe {
+        GetTokenInformation(
+            token,
+            TokenElevation,
+            (&mut elevation as *mut TOKEN_ELEVATION).cast(),
+            std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut len,
+        )
+    };
+    let err = io::Error::last_os_error();
+    unsafe { CloseHandle(token) };
+    if rs == 0 {
+        return Err(err);
+    }
+    Ok(elevation.TokenIsElevated != 0)
+}
@@ -0,0 +1,83 @@
+use crate::{Route, RouteManager};
+use std::io;
+use std::net::IpAddr;
+
+/// A host route to a remote endpoint through its original gateway, installed by
+/// [`RouteManager::pin_endpoint`].
+///
+/// Typically used by VPN clients so that traffic to the VPN server keeps using the physical
+/// network once a catch-all route into the tunnel is added. The route is deleted when the
+/// value is dropped, unless [`into_route`](Self::into_route) is called.
+#[derive(Debug)]
+pub struct PinnedRoute {
+    route: Option<Route>,
+}
+
+impl PinnedRoute {
+    /// The installed host route.
+    pub fn route(&self) -> &Route {
+        self.route.as_ref().unwrap()
+    }
+    /// Deletes the host route.
+    pub fn remove(mut self) -> io::Result<()> {
+        let route = self.route.take().unwrap();
+        RouteManager::new()?.delete(&route)
+    }
+    /// Keeps the host route installed and returns it.
+    pub fn into_route(mut self) -> Route {
+        self.route.take().unwrap()
+    }
+}
+
+impl Drop for PinnedRoute {
+    fn drop(&mut self) {
+        if let Some(route) = self.route.take() {
+            if let Ok(manager) = RouteManager::new() {
+                let _ = manager.delete(&route);
+            }
+        }
+    }
+}
+
+impl RouteManager {
+    /// Installs a host route (/32 or /128) to `endpoint` through the gateway and interface of
+    /// the route currently used to reach it.
+    ///
+    /// Call this before adding a catch-all tunnel route.
+    pub fn pin_endpoint(&self, endpoint: IpAddr) -> io::Result<PinnedRoute> {
+        let via = self.find_route(&endpoint)?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no route to endpoint {endpoint}"),
+            )
+        })?;
+        self.pin_endpoint_via(endpoint, &via)
+    }
+    /// Installs a host route (/32 or /128) to `endpoint` through the gateway and interface
+    /// of `via`, e.g. the current default route.
+    pub fn pin_endpoint_via(&self, endpoint: IpAddr, via: &Route) -> io::Result<PinnedRoute> {
+        if endpoint.is_ipv4() != via.destination.is_ipv4() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("endpoint {endpoint} is not in the address family of {via}"),
+            ));
+        }
+        let mut route = Route::host(endpoint);
+        route.gateway = via.gateway;
+        route.gateway_scope_id = via.gateway_scope_id;
+        route.if_index = via.get_index();
+        if route.if_index.is_none() {
+            route.if_name = via.if_name.clone();
+        }
+        #[cfg(target_os = "linux")]
+        {
+            route.table = via.table;
+        }
+        #[cfg(target_os = "windows")]
+        {
+            route.luid = via.luid;
+        }
+        self.add(&route)?;
+        Ok(PinnedRoute { route: Some(route) })
+    }
+}
@@ -0,0 +1,185 @@
+use crate::Route;
+
+/// OS-specific details of a route, returned by [`Route::platform_ext`].
+///
+/// Fields marked as reported are only set for routes read from the system (listed, looked up
+/// or received from a listener).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PlatformExt {
+    Linux {
+        /// Routing table (`RT_TABLE_*`).
+        table: u8,
+        /// Routing protocol (`RTPROT_*`).
+        protocol: Option<u8>,
+        /// Reported scope (`RT_SCOPE_*`).
+        scope: Option<u8>,
+        /// Reported route type (`RTN_*`).
+        kind: Option<u8>,
+        /// Reported route flags (`RTM_F_*`).
+        flags: Option<u32>,
+    },
+    Windows {
+        /// Locally unique identifier of the interface.
+        luid: Option<u64>,
+        /// Network compartment the route was listed in; not reported for events.
+        compartment: Option<u32>,
+        /// Reported routing protocol (`NL_ROUTE_PROTOCOL`).
+        protocol: Option<i32>,
+        /// Reported origin (`NL_ROUTE_ORIGIN`).
+        origin: Option<i32>,
+        /// Reported loopback flag.
+        loopback: bool,
+        /// Reported flag of routes created for autoconfigured addresses.
+        autoconfigure_address: bool,
+        /// Reported flag of routes published in router advertisements.
+        publish: bool,
+        /// Reported flag of routes that survive reboots.
+        immortal: bool,
+        /// Reported valid lifetime in seconds.
+        valid_lifetime: Option<u32>,
+        /// Reported preferred lifetime in seconds.
+        preferred_lifetime: Option<u32>,
+    },
+    Bsd {
+        /// Reported route flags (`RTF_*`).
+        flags: Option<u32>,
+        /// (OpenBSD only) Reported routing table.
+        fib: Option<u32>,
+        /// (OpenBSD only) Route priority.
+        priority: Option<u8>,
+    },
+    /// A target without a route API binding.
+    Other,
+}
+
+/// Details the system reported for a route, exposed through [`Route::platform_ext`].
+///
+/// They describe the route rather than identify it, so they are ignored when routes are
+/// compared: an event for a route still equals the route in an earlier dump.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Reported {
+    #[cfg(target_os = "linux")]
+    pub(crate) scope: Option<u8>,
+    #[cfg(target_os = "linux")]
+    pub(crate) kind: Option<u8>,
+    #[cfg(target_os = "linux")]
+    pub(crate) flags: Option<u32>,
+    #[cfg(target_os = "linux")]
+    pub(crate) cache_info: Option<Box<crate::RouteCacheInfo>>,
+    #[cfg(windows)]
+    pub(crate) compartment: Option<u32>,
+    #[cfg(windows)]
+    pub(crate) row: Option<RowDetails>,
+    #[cfg(unix)]
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) flags: Option<u32>,
+    #[cfg(target_os = "openbsd")]
+    pub(crate) fib: Option<u32>,
+}
+
+/// The fields of a `MIB_IPFORWARD_ROW2` that are not modelled by [`Route`].
+#[cfg(windows)]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RowDetails {
+    pub(crate) protocol: i32,
+    pub(crate) origin: i32,
+    pub(crate) loopback: bool,
+    pub(crate) autoconfigure_address: bool,
+    pub(crate) publish: bool,
+    pub(crate) immortal: bool,
+    pub(crate) valid_lifetime: u32,
+    pub(crate) preferred_lifetime: u32,
+}
+
+impl PartialEq for Reported {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+impl Eq for Reported {}
+
+impl Route {
+    /// Whether the route was read from the system rather than built by the caller.
+    #[cfg_attr(windows, allow(dead_code))]
+    pub(crate) fn is_reported(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        return self.reported.flags.is_some();
+        #[cfg(windows)]
+        return self.reported.row.is_some();
+        #[cfg(any(
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "macos",
+            target_os = "netbsd",
+            target_os = "dragonfly"
+        ))]
+        return self.reported.flags.is_some();
+        #[cfg(not(any(
+            windows,
+            target_os = "linux",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "macos",
+            target_os = "netbsd",
+            target_os = "dragonfly"
+        )))]
+        false
+    }
+    /// Returns the OS-specific details of the route, including those the system reported
+    /// beyond the common fields.
+    pub fn platform_ext(&self) -> PlatformExt {
+        #[cfg(target_os = "linux")]
+        return PlatformExt::Linux {
+            table: self.table,
+            protocol: self.protocol,
+            scope: self.reported.scope,
+            kind: self.reported.kind,
+            flags: self.reported.flags,
+        };
+        #[cfg(windows)]
+        return {
+            let row = &self.reported.row;
+            PlatformExt::Windows {
+                luid: self.luid,
+                compartment: self.reported.compartment,
+                protocol: row.as_ref().map(|v| v.protocol),
+                origin: row.as_ref().map(|v| v.origin),
+                loopback: row.as_ref().is_some_and(|v| v.loopback),
+                autoconfigure_address: row.as_ref().is_some_and(|v| v.autoconfigure_address),
+                publish: row.as_ref().is_some_and(|v| v.publish),
+                immortal: row.as_ref().is_some_and(|v| v.immortal),
+                valid_lifetime: row.as_ref().map(|v| v.valid_lifetime),
+                preferred_lifetime: row.as_ref().map(|v| v.preferred_lifetime),
+            }
+        };
+        #[cfg(any(
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "macos",
+            target_os = "netbsd",
+            target_os = "dragonfly"
+        ))]
+        return PlatformExt::Bsd {
+            flags: self.reported.flags,
+            #[cfg(target_os = "openbsd")]
+            fib: self.reported.fib,
+            #[cfg(not(target_os = "openbsd"))]
+            fib: None,
+            #[cfg(target_os = "openbsd")]
+            priority: Some(self.priority),
+            #[cfg(not(target_os = "openbsd"))]
+            priority: None,
+        };
+        #[cfg(not(any(
+            windows,
+            target_os = "linux",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "macos",
+            target_os = "netbsd",
+            target_os = "dragonfly"
+        )))]
+        PlatformExt::Other
+    }
+}
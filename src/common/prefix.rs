@@ -0,0 +1,81 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+fn max_prefix(addr: &IpAddr) -> u8 {
+    if addr.is_ipv4() {
+        32
+    } else {
+        128
+    }
+}
+
+/// Returns the netmask of `prefix` in the address family of `addr`.
+///
+/// Fails with `InvalidInput` if `prefix` exceeds the address length.
+pub fn prefix_to_mask(addr: IpAddr, prefix: u8) -> io::Result<IpAddr> {
+    let max = max_prefix(&addr);
+    if prefix > max {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("prefix length {prefix} exceeds {max}"),
+        ));
+    }
+    Ok(mask(addr, prefix))
+}
+
+/// Returns the prefix length of a netmask.
+///
+/// Fails with `InvalidInput` if the mask is not contiguous (e.g. `255.0.255.0`).
+pub fn mask_to_prefix(mask: IpAddr) -> io::Result<u8> {
+    let (ones, zeros) = match mask {
+        IpAddr::V4(mask) => {
+            let mask = u32::from(mask);
+            (mask.leading_ones(), mask.trailing_zeros())
+        }
+        IpAddr::V6(mask) => {
+            let mask = u128::from(mask);
+            (mask.leading_ones(), mask.trailing_zeros())
+        }
+    };
+    if ones + zeros != max_prefix(&mask) as u32 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("netmask {mask} is not contiguous"),
+        ));
+    }
+    Ok(ones as u8)
+}
+
+/// Returns the network address of `addr` with `prefix` bits kept.
+///
+/// A prefix beyond the address length keeps all bits.
+pub fn network_address(addr: IpAddr, prefix: u8) -> IpAddr {
+    let prefix = prefix.min(max_prefix(&addr)) as u32;
+    match addr {
+        IpAddr::V4(addr) => IpAddr::V4(Ipv4Addr::from(
+            u32::from(addr) & u32::MAX.checked_shl(32 - prefix).unwrap_or(0),
+        )),
+        IpAddr::V6(addr) => IpAddr::V6(Ipv6Addr::from(
+            u128::from(addr) & u128::MAX.checked_shl(128 - prefix).unwrap_or(0),
+        )),
+    }
+}
+
+/// Netmask of `prefix`, with prefix lengths beyond the address length clamped.
+pub(crate) fn mask(addr: IpAddr, prefix: u8) -> IpAddr {
+    let prefix = prefix.min(max_prefix(&addr)) as u32;
+    match addr {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from(
+            u32::MAX.checked_shl(32 - prefix).unwrap_or(0),
+        )),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(
+            u128::MAX.checked_shl(128 - prefix).unwrap_or(0),
+        )),
+    }
+}
+
+/// Whether the network `outer`/`prefix` contains the address `addr`, both as `bits`-bit integers.
+pub(crate) fn prefix_contains(outer: u128, prefix: u8, addr: u128, bits: u32) -> bool {
+    let shift = bits - prefix as u32;
+    outer.checked_shr(shift).unwrap_or(0) == addr.checked_shr(shift).unwrap_or(0)
+}
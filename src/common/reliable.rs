@@ -0,0 +1,266 @@
+use crate::{Route, RouteChange, RouteListener, RouteManager, RouteTable};
+use std::collections::VecDeque;
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Route listener that never misses a change, created by
+/// [`RouteManager::subscribe_reliable`].
+///
+/// It keeps a copy of the routing table. When the kernel reports that events were dropped
+/// (`ENOBUFS`), and optionally at a fixed interval to also catch events discarded by the
+/// [`OverflowPolicy`](crate::OverflowPolicy), the table is dumped again and compared to the
+/// copy; every route that appeared or vanished in between is reported as an `Add` or `Delete`
+/// event. Additions of routes already in the copy and deletions of unknown routes are dropped,
+/// so applying the events in order to the initial table always yields the current one.
+pub struct ReliableListener {
+    listener: RouteListener,
+    tracker: Tracker,
+}
+
+impl RouteManager {
+    /// Returns the current routing table together with a listener that recovers missed
+    /// changes by dumping the table again, every `resync_interval` if set and whenever the
+    /// kernel reports an overflow.
+    pub fn subscribe_reliable(
+        resync_interval: Option<Duration>,
+    ) -> io::Result<(Vec<Route>, ReliableListener)> {
+        let (routes, listener) = Self::subscribe()?;
+        let tracker = Tracker::new(&routes, resync_interval);
+        Ok((routes, ReliableListener { listener, tracker }))
+    }
+}
+
+impl ReliableListener {
+    /// Listens for the next route change event, including those synthesized after a re-dump.
+    pub fn listen(&mut self) -> io::Result<RouteChange> {
+        loop {
+            if let Some(change) = self.tracker.pending.pop_front() {
+                return Ok(change);
+            }
+            let result = match self.tracker.remaining() {
+                Some(remaining) => self.listener.listen_timeout(remaining),
+                None => self.listener.listen(),
+            };
+            match result {
+                Ok(change) => {
+                    if let Some(change) = self.tracker.track(change) {
+                        return Ok(change);
+                    }
+                }
+                Err(e) if needs_resync(&e) => self.resync()?,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    /// Dumps the table now and queues the differences to the copy as events.
+    ///
+    /// Events still queued on the socket are discarded first, as the dump covers them.
+    pub fn resync(&mut self) -> io::Result<()> {
+        loop {
+            match self.listener.listen_timeout(Duration::ZERO) {
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => break,
+                Ok(_) => {}
+                Err(e) if needs_resync(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let routes = RouteManager::new()?.list()?;
+        self.tracker.resync(routes);
+        Ok(())
+    }
+    /// Returns the copy of the table, including changes that are still queued.
+    pub fn table(&self) -> &RouteTable {
+        &self.tracker.table
+    }
+    /// Returns the underlying listener. Queued events are discarded.
+    pub fn into_inner(self) -> RouteListener {
+        self.listener
+    }
+}
+
+/// Asynchronous counterpart of [`ReliableListener`], created by
+/// [`AsyncRouteManager::subscribe_reliable`](crate::AsyncRouteManager::subscribe_reliable).
+#[cfg(any(feature = "async", feature = "async_io"))]
+pub struct AsyncReliableListener {
+    listener: crate::AsyncRouteListener,
+    tracker: Tracker,
+}
+
+#[cfg(any(feature = "async", feature = "async_io"))]
+impl crate::AsyncRouteManager {
+    /// Returns the current routing table together with an asynchronous listener that
+    /// recovers missed changes.
+    ///
+    /// See [`RouteManager::subscribe_reliable`]; the initial table is dumped synchronously.
+    pub fn subscribe_reliable(
+        resync_interval: Option<Duration>,
+    ) -> io::Result<(Vec<Route>, AsyncReliableListener)> {
+        let (routes, listener) = Self::subscribe()?;
+        let tracker = Tracker::new(&routes, resync_interval);
+        Ok((routes, AsyncReliableListener { listener, tracker }))
+    }
+}
+
+#[cfg(any(feature = "async", feature = "async_io"))]
+impl AsyncReliableListener {
+    /// Asynchronously listens for the next route change event, including those synthesized
+    /// after a re-dump.
+    ///
+    /// This method is cancel safe, except while the table is being dumped again.
+    pub async fn listen(&mut self) -> io::Result<RouteChange> {
+        loop {
+            if let Some(change) = self.tracker.pending.pop_front() {
+                return Ok(change);
+            }
+            let result = match self.tracker.remaining() {
+                Some(remaining) => self.listener.listen_with_timeout(remaining).await,
+                None => self.listener.listen().await,
+            };
+            match result {
+                Ok(change) => {
+                    if let Some(change) = self.tracker.track(change) {
+                        return Ok(change);
+                    }
+                }
+                Err(e) if needs_resync(&e) => self.resync().await?,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    /// Asynchronously dumps the table now and queues the differences to the copy as events.
+    ///
+    /// Events still queued on the socket are discarded first, as the dump covers them.
+    pub async fn resync(&mut self) -> io::Result<()> {
+        loop {
+            match self.listener.listen_with_timeout(Duration::ZERO).await {
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => break,
+                Ok(_) => {}
+                Err(e) if needs_resync(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let routes = crate::AsyncRouteManager::new()?.list().await?;
+        self.tracker.resync(routes);
+        Ok(())
+    }
+    /// Returns the copy of the table, including changes that are still queued.
+    pub fn table(&self) -> &RouteTable {
+        &self.tracker.table
+    }
+    /// Returns the underlying listener. Queued events are discarded.
+    pub fn into_inner(self) -> crate::AsyncRouteListener {
+        self.listener
+    }
+}
+
+/// Timeouts are only requested for periodic re-dumps.
+fn needs_resync(e: &io::Error) -> bool {
+    crate::os_error(e) == Some(crate::ENOBUFS) || e.kind() == io::ErrorKind::TimedOut
+}
+
+struct Tracker {
+    table: RouteTable,
+    // Events synthesized by the last re-dump
+    pending: VecDeque<RouteChange>,
+    interval: Option<Duration>,
+    next_resync: Option<Instant>,
+}
+
+impl Tracker {
+    fn new(routes: &[Route], interval: Option<Duration>) -> Self {
+        Self {
+            table: routes.iter().cloned().collect(),
+            pending: VecDeque::new(),
+            interval,
+            next_resync: interval.map(|v| Instant::now() + v),
+        }
+    }
+    /// Time left until the next periodic re-dump.
+    fn remaining(&self) -> Option<Duration> {
+        self.next_resync
+            .map(|v| v.saturating_duration_since(Instant::now()))
+    }
+    /// Applies a live event to the copy, returning it unless the copy already reflects it.
+    fn track(&mut self, change: RouteChange) -> Option<RouteChange> {
+        match &change {
+            RouteChange::Add(route) if contains(&self.table, route) => {
+                // Keep the details of the latest report
+                self.table.insert(route.clone());
+                return None;
+            }
+            RouteChange::Delete(route) if !contains(&self.table, route) => return None,
+            _ => {}
+        }
+        self.table.apply(change.clone());
+        Some(change)
+    }
+    fn resync(&mut self, routes: Vec<Route>) {
+        let fresh: RouteTable = routes.into_iter().collect();
+        let deleted = self.table.iter().filter(|v| !contains(&fresh, v));
+        let added = fresh.iter().filter(|v| !contains(&self.table, v));
+        let missed: Vec<RouteChange> = deleted
+            .cloned()
+            .map(RouteChange::Delete)
+            .chain(added.cloned().map(RouteChange::Add))
+            .collect();
+        debug_event!(missed = missed.len(), "route table dumped again");
+        self.pending.extend(missed);
+        self.table = fresh;
+        self.next_resync = self.interval.map(|v| Instant::now() + v);
+    }
+}
+
+/// Whether `table` holds a route with the identity of `route`, whatever the details reported.
+fn contains(table: &RouteTable, route: &Route) -> bool {
+    table
+        .get(&route.destination, route.prefix)
+        .iter()
+        .any(|v| v.same_identity(route))
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resync_discards_stale_events() {
+        let manager = RouteManager::new().unwrap();
+        let route = Route::new("10.97.8.0".parse().unwrap(), 24)
+            .with_if_index(1)
+            .with_table(100);
+        let marker = Route::new("10.97.7.0".parse().unwrap(), 24)
+            .with_if_index(1)
+            .with_table(100);
+        let (_, mut listener) = RouteManager::subscribe_reliable(None).unwrap();
+        match manager.add(&route) {
+            // Needs CAP_NET_ADMIN
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => return,
+            rs => rs.unwrap(),
+        }
+        manager.delete(&route).unwrap();
+        manager.add(&route).unwrap();
+        // Add, Delete and Add of the route are still queued
+        listener.resync().unwrap();
+        manager.add(&marker).unwrap();
+        let mut changes = Vec::new();
+        loop {
+            let change = listener.listen().unwrap();
+            match &change {
+                RouteChange::Add(v) if v.destination == marker.destination => break,
+                RouteChange::Add(v) | RouteChange::Delete(v)
+                    if v.destination == route.destination =>
+                {
+                    changes.push(change)
+                }
+                _ => {}
+            }
+        }
+        let _ = manager.delete(&route);
+        let _ = manager.delete(&marker);
+        assert!(
+            matches!(&changes[..], [RouteChange::Add(v)] if v.same_identity(&route)),
+            "{changes:?}"
+        );
+        assert!(contains(listener.table(), &route));
+    }
+}
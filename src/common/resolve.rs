@@ -0,0 +1,260 @@
+use crate::{Route, RouteFlags, RouteTable};
+#[cfg(target_os = "windows")]
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// Inputs of the route selection emulated by [`RouteTable::resolve`] besides the
+/// destination.
+#[derive(Debug, Clone, Default)]
+pub struct ResolveOptions {
+    pub(crate) if_index: Option<u32>,
+    #[cfg(target_os = "linux")]
+    pub(crate) source: Option<IpAddr>,
+    #[cfg(target_os = "linux")]
+    pub(crate) mark: u32,
+    #[cfg(target_os = "linux")]
+    pub(crate) uid: u32,
+    #[cfg(target_os = "linux")]
+    pub(crate) rules: Option<Vec<PolicyRule>>,
+    #[cfg(target_os = "windows")]
+    pub(crate) interface_metrics: HashMap<u32, u32>,
+}
+
+impl ResolveOptions {
+    /// Creates options for a lookup without a bound interface or source.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Restricts the lookup to routes through `if_index`, as for a socket bound to the
+    /// interface (`SO_BINDTODEVICE` on Linux, `IP_BOUND_IF` on macOS).
+    pub fn with_if_index(mut self, if_index: u32) -> Self {
+        self.if_index = Some(if_index);
+        self
+    }
+    /// (Linux only) Sets the source address, matched by policy rules and source-specific
+    /// routes.
+    #[cfg(target_os = "linux")]
+    pub fn with_source(mut self, source: IpAddr) -> Self {
+        self.source = Some(source);
+        self
+    }
+    /// (Linux only) Sets the firewall mark (`SO_MARK`) matched by policy rules.
+    #[cfg(target_os = "linux")]
+    pub fn with_mark(mut self, mark: u32) -> Self {
+        self.mark = mark;
+        self
+    }
+    /// (Linux only) Sets the user id of the sending socket, matched by the `uidrange` of
+    /// policy rules; root (0) by default, as for forwarded packets.
+    #[cfg(target_os = "linux")]
+    pub fn with_uid(mut self, uid: u32) -> Self {
+        self.uid = uid;
+        self
+    }
+    /// (Linux only) Sets the policy rules to evaluate instead of the default ones, see
+    /// [`PolicyRule::defaults`].
+    #[cfg(target_os = "linux")]
+    pub fn with_rules(mut self, rules: Vec<PolicyRule>) -> Self {
+        self.rules = Some(rules);
+        self
+    }
+    /// (Windows only) Sets the interface metric of `if_index`, which is otherwise read from
+    /// the system.
+    #[cfg(target_os = "windows")]
+    pub fn with_interface_metric(mut self, if_index: u32, metric: u32) -> Self {
+        self.interface_metrics.insert(if_index, metric);
+        self
+    }
+}
+
+/// (Linux only) A policy routing rule that makes the lookup consult a routing table, see
+/// `ip rule`.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyRule {
+    pub(crate) priority: u32,
+    pub(crate) table: u8,
+    pub(crate) source: Option<(IpAddr, u8)>,
+    pub(crate) destination: Option<(IpAddr, u8)>,
+    pub(crate) fwmark: Option<(u32, u32)>,
+    pub(crate) oif: Option<u32>,
+    pub(crate) uid_range: Option<(u32, u32)>,
+    pub(crate) invert: bool,
+}
+
+#[cfg(target_os = "linux")]
+impl PolicyRule {
+    /// Creates a rule that looks up `table` for every packet.
+    pub fn new(priority: u32, table: u8) -> Self {
+        Self {
+            priority,
+            table,
+            source: None,
+            destination: None,
+            fwmark: None,
+            oif: None,
+            uid_range: None,
+            invert: false,
+        }
+    }
+    /// The rules of a system without policy routing: `local`, `main` and `default`.
+    pub fn defaults() -> Vec<PolicyRule> {
+        vec![
+            Self::new(0, libc::RT_TABLE_LOCAL),
+            Self::new(32766, libc::RT_TABLE_MAIN),
+            Self::new(32767, libc::RT_TABLE_DEFAULT),
+        ]
+    }
+    /// Matches only packets from `source/prefix`.
+    pub fn with_source(mut self, source: IpAddr, prefix: u8) -> Self {
+        self.source = Some((source, prefix));
+        self
+    }
+    /// Matches only packets to `destination/prefix`.
+    pub fn with_destination(mut self, destination: IpAddr, prefix: u8) -> Self {
+        self.destination = Some((destination, prefix));
+        self
+    }
+    /// Matches only packets whose firewall mark equals `mark` under `mask`.
+    pub fn with_fwmark(mut self, mark: u32, mask: u32) -> Self {
+        self.fwmark = Some((mark, mask));
+        self
+    }
+    /// Matches only packets of sockets bound to `if_index`.
+    pub fn with_oif(mut self, if_index: u32) -> Self {
+        self.oif = Some(if_index);
+        self
+    }
+    /// Matches only packets of sockets owned by a user from `start` to `end` inclusive, as
+    /// with per-user VPN routing (`ip rule add uidrange ...`).
+    pub fn with_uid_range(mut self, start: u32, end: u32) -> Self {
+        self.uid_range = Some((start, end));
+        self
+    }
+    /// Inverts the selectors of the rule (`ip rule add not ...`).
+    pub fn with_invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+    /// Returns the priority; rules are evaluated in ascending order.
+    pub fn priority(&self) -> u32 {
+        self.priority
+    }
+    /// Returns the table the rule looks up.
+    pub fn table(&self) -> u8 {
+        self.table
+    }
+    fn matches(&self, dest: &IpAddr, options: &ResolveOptions) -> bool {
+        let prefix_matches = |selector: Option<(IpAddr, u8)>, addr: Option<&IpAddr>| {
+            selector.is_none_or(|(network, prefix)| {
+                addr.is_some_and(|v| Route::new(network, prefix).contains(v))
+            })
+        };
+        let matches = prefix_matches(self.source, options.source.as_ref())
+            && prefix_matches(self.destination, Some(dest))
+            && self
+                .fwmark
+                .is_none_or(|(mark, mask)| options.mark & mask == mark)
+            && self.oif.is_none_or(|v| options.if_index == Some(v))
+            && self
+                .uid_range
+                .is_none_or(|(start, end)| (start..=end).contains(&options.uid));
+        matches != self.invert
+    }
+}
+
+impl RouteTable {
+    /// Emulates the selection of the route to `dest` among the routes of the table, e.g. to
+    /// find out which route the system would use after installing another one.
+    ///
+    /// Routes of another family, routes reported as down and, with a bound interface, routes
+    /// through other interfaces are skipped. On Linux the policy rules are evaluated in order
+    /// and each consults its table, where the longest prefix and then the lowest metric wins;
+    /// a rejecting or blackhole route ends the lookup and is returned. On macOS an unbound
+    /// lookup skips interface-scoped routes. On Windows the route metric plus the interface
+    /// metric decides between routes of equal prefix length. Other BSDs select by prefix
+    /// length only.
+    pub fn resolve(&self, dest: &IpAddr, options: &ResolveOptions) -> Option<&Route> {
+        let dest = dest.to_canonical();
+        let candidates = || {
+            self.iter().filter(move |v| {
+                v.destination.is_ipv4() == dest.is_ipv4()
+                    && v.contains(&dest)
+                    && is_up(v)
+                    && options.if_index.is_none_or(|i| v.get_index() == Some(i))
+                    && is_considered(v, options)
+            })
+        };
+        #[cfg(target_os = "linux")]
+        {
+            let defaults;
+            let rules = match &options.rules {
+                Some(rules) => rules,
+                None => {
+                    defaults = PolicyRule::defaults();
+                    &defaults
+                }
+            };
+            let mut rules: Vec<&PolicyRule> = rules.iter().collect();
+            rules.sort_by_key(|v| v.priority);
+            rules
+                .into_iter()
+                .filter(|rule| rule.matches(&dest, options))
+                .find_map(|rule| {
+                    candidates()
+                        .filter(|v| same_table(v.table, rule.table))
+                        .min_by_key(|v| (std::cmp::Reverse(v.prefix), v.metric.unwrap_or(0)))
+                })
+        }
+        #[cfg(not(target_os = "linux"))]
+        candidates().min_by_key(|v| (std::cmp::Reverse(v.prefix), metric(v, options)))
+    }
+}
+
+/// Whether the route is usable; routes built by the caller are assumed to be.
+fn is_up(route: &Route) -> bool {
+    !route.is_reported() || route.flags().contains(RouteFlags::UP)
+}
+
+#[cfg(target_os = "linux")]
+fn same_table(a: u8, b: u8) -> bool {
+    let main = |v| if v == 0 { libc::RT_TABLE_MAIN } else { v };
+    main(a) == main(b)
+}
+
+/// Whether the platform considers the route for the lookup described by `options`.
+fn is_considered(route: &Route, options: &ResolveOptions) -> bool {
+    #[cfg(target_os = "linux")]
+    return match (route.source, options.source) {
+        (Some(source), Some(addr)) => Route::new(source, route.source_prefix).contains(&addr),
+        (Some(_), None) => route.source_prefix == 0,
+        (None, _) => true,
+    };
+    #[cfg(target_os = "macos")]
+    return options.if_index.is_some() || !crate::is_interface_scoped(route);
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = (route, options);
+        true
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn metric(route: &Route, options: &ResolveOptions) -> u32 {
+    #[cfg(target_os = "windows")]
+    {
+        let metric = route.metric.unwrap_or(0);
+        let configured = route
+            .get_index()
+            .and_then(|v| options.interface_metrics.get(&v));
+        match configured {
+            Some(if_metric) => metric.saturating_add(*if_metric),
+            None => route.effective_metric().unwrap_or(metric),
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = (route, options);
+        0
+    }
+}
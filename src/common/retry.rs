@@ -0,0 +1,141 @@
+use std::io;
+use std::time::Duration;
+
+/// Retry policy for route operations that fail with a transient error.
+///
+/// Transient errors are `ENOBUFS`, `EBUSY` and `EAGAIN` on Unix and `ERROR_RETRY` and
+/// `ERROR_BUSY` on Windows. The wait between attempts starts at the backoff and doubles up
+/// to the maximum backoff. Retries are off unless a policy is set on a manager, with
+/// [`RouteManagerBuilder::with_retry_policy`](crate::RouteManagerBuilder::with_retry_policy)
+/// or `with_retry_policy` of the asynchronous and command managers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub(crate) max_attempts: u32,
+    pub(crate) backoff: Duration,
+    pub(crate) max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates the default policy: 3 attempts, 10 ms initial backoff, at most 1 s between
+    /// attempts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Sets the number of attempts, including the first one (at least 1).
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+    /// Sets the wait before the first retry.
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+    /// Sets the longest wait between attempts.
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+    /// Returns the number of attempts.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+    /// Returns the wait before the first retry.
+    pub fn backoff(&self) -> Duration {
+        self.backoff
+    }
+    /// Returns the longest wait between attempts.
+    pub fn max_backoff(&self) -> Duration {
+        self.max_backoff
+    }
+    /// The wait after failed attempt `attempt` (starting at 1).
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt - 1).unwrap_or(u32::MAX);
+        self.backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Whether `e` is worth retrying.
+fn is_transient(e: &io::Error) -> bool {
+    #[cfg(unix)]
+    let codes = [libc::ENOBUFS, libc::EBUSY, libc::EAGAIN];
+    #[cfg(windows)]
+    let codes = [
+        windows_sys::Win32::Foundation::ERROR_RETRY as i32,
+        windows_sys::Win32::Foundation::ERROR_BUSY as i32,
+    ];
+    #[cfg(not(any(unix, windows)))]
+    let codes: [i32; 0] = [];
+    crate::os_error(e).is_some_and(|code| codes.contains(&code))
+}
+
+/// Runs `op` under the retry policy of a manager, if it has one.
+pub(crate) fn retry<T>(
+    policy: Option<RetryPolicy>,
+    mut op: impl FnMut() -> io::Result<T>,
+) -> io::Result<T> {
+    let first = op();
+    retry_failed(policy, first, op)
+}
+
+/// Like [`retry`], for an operation whose first attempt was made elsewhere with the result
+/// `first`, e.g. as part of a batch.
+pub(crate) fn retry_failed<T>(
+    policy: Option<RetryPolicy>,
+    first: io::Result<T>,
+    mut op: impl FnMut() -> io::Result<T>,
+) -> io::Result<T> {
+    let Some(policy) = policy else {
+        return first;
+    };
+    let mut attempt = 1;
+    let mut result = first;
+    loop {
+        match result {
+            Err(e) if attempt < policy.max_attempts && is_transient(&e) => {
+                debug_event!(attempt, error = %e, "retrying route operation");
+                std::thread::sleep(policy.delay(attempt));
+                attempt += 1;
+                result = op();
+            }
+            rs => return rs,
+        }
+    }
+}
+
+/// Runs `op` under the retry policy of a manager, waiting asynchronously between attempts.
+#[cfg(all(unix, any(feature = "async", feature = "async_io")))]
+pub(crate) async fn retry_async<T, F, S>(
+    policy: Option<RetryPolicy>,
+    sleep: impl Fn(Duration) -> S,
+    mut op: impl FnMut() -> F,
+) -> io::Result<T>
+where
+    F: std::future::Future<Output = io::Result<T>>,
+    S: std::future::Future<Output = ()>,
+{
+    let Some(policy) = policy else {
+        return op().await;
+    };
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Err(e) if attempt < policy.max_attempts && is_transient(&e) => {
+                debug_event!(attempt, error = %e, "retrying route operation");
+                sleep(policy.delay(attempt)).await;
+                attempt += 1;
+            }
+            rs => return rs,
+        }
+    }
+}
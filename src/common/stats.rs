@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+
+/// Route counts returned by [`RouteManager::stats`](crate::RouteManager::stats).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteStats {
+    pub(crate) ipv4: usize,
+    pub(crate) ipv6: usize,
+    pub(crate) tables: BTreeMap<u32, usize>,
+    pub(crate) protocols: BTreeMap<u8, usize>,
+    pub(crate) interfaces: BTreeMap<u32, usize>,
+}
+
+impl RouteStats {
+    /// Returns the number of routes.
+    pub fn total(&self) -> usize {
+        self.ipv4 + self.ipv6
+    }
+    /// Returns the number of IPv4 routes.
+    pub fn ipv4(&self) -> usize {
+        self.ipv4
+    }
+    /// Returns the number of IPv6 routes.
+    pub fn ipv6(&self) -> usize {
+        self.ipv6
+    }
+    /// (Linux only) Returns the number of routes per routing table, including tables above 255.
+    pub fn tables(&self) -> &BTreeMap<u32, usize> {
+        &self.tables
+    }
+    /// (Linux only) Returns the number of routes in `table`.
+    pub fn table(&self, table: u32) -> usize {
+        self.tables.get(&table).copied().unwrap_or(0)
+    }
+    /// (Linux only) Returns the number of routes per routing protocol (`RTPROT_*`).
+    pub fn protocols(&self) -> &BTreeMap<u8, usize> {
+        &self.protocols
+    }
+    /// Returns the number of routes per output interface index; routes without a single
+    /// output interface, e.g. multipath routes, are not counted.
+    pub fn interfaces(&self) -> &BTreeMap<u32, usize> {
+        &self.interfaces
+    }
+    /// Returns the number of routes through the interface `if_index`.
+    pub fn interface(&self, if_index: u32) -> usize {
+        self.interfaces.get(&if_index).copied().unwrap_or(0)
+    }
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub(crate) fn merge(&mut self, other: RouteStats) {
+        self.ipv4 += other.ipv4;
+        self.ipv6 += other.ipv6;
+        for (map, other) in [
+            (&mut self.tables, other.tables),
+            (&mut self.interfaces, other.interfaces),
+        ] {
+            for (key, count) in other {
+                *map.entry(key).or_default() += count;
+            }
+        }
+        for (key, count) in other.protocols {
+            *self.protocols.entry(key).or_default() += count;
+        }
+    }
+    pub(crate) fn count(
+        &mut self,
+        ipv4: bool,
+        table: Option<u32>,
+        protocol: Option<u8>,
+        if_index: Option<u32>,
+    ) {
+        if ipv4 {
+            self.ipv4 += 1;
+        } else {
+            self.ipv6 += 1;
+        }
+        if let Some(table) = table {
+            *self.tables.entry(table).or_default() += 1;
+        }
+        if let Some(protocol) = protocol {
+            *self.protocols.entry(protocol).or_default() += 1;
+        }
+        if let Some(if_index) = if_index {
+            *self.interfaces.entry(if_index).or_default() += 1;
+        }
+    }
+}
+
+#[cfg(any(
+    windows,
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "macos",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+))]
+impl crate::RouteManager {
+    /// Returns the number of routes per family and interface.
+    pub fn stats(&self) -> std::io::Result<RouteStats> {
+        let mut stats = RouteStats::default();
+        for route in self.list()? {
+            stats.count(route.destination.is_ipv4(), None, None, route.if_index);
+        }
+        Ok(stats)
+    }
+}
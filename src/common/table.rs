@@ -0,0 +1,388 @@
+use crate::{Route, RouteChange};
+use std::net::IpAddr;
+
+/// In-memory routing table with longest-prefix-match lookups.
+///
+/// Routes are stored in a path-compressed binary (Patricia) trie per address family: a node
+/// only exists where routes are installed or prefixes branch, so each route costs at most two
+/// nodes and a lookup visits at most one node per branching bit, regardless of the number of
+/// routes.
+#[derive(Debug, Clone, Default)]
+pub struct RouteTable {
+    v4: Node,
+    v6: Node,
+    len: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    // The prefix of the node, masked to `len` bits
+    key: u128,
+    len: u8,
+    children: [Option<Box<Node>>; 2],
+    routes: Vec<Route>,
+}
+
+impl Node {
+    fn new(key: u128, len: u8) -> Self {
+        Self {
+            key: mask(key, len),
+            len,
+            ..Default::default()
+        }
+    }
+    /// Whether the prefix of the node covers the first `len` bits of `key`, `len` being at
+    /// least that of the node.
+    fn covers(&self, key: u128, len: u8) -> bool {
+        self.len <= len && common_len(self.key, key, self.len) == self.len
+    }
+    /// The node of exactly `key/len`, created if missing. `key/len` is within the node.
+    fn find_or_insert(&mut self, key: u128, len: u8) -> &mut Node {
+        if self.len == len {
+            return self;
+        }
+        let slot = &mut self.children[bit(key, self.len)];
+        let Some(child) = slot else {
+            return slot.insert(Box::new(Node::new(key, len)));
+        };
+        if child.covers(key, len) {
+            return slot.as_mut().unwrap().find_or_insert(key, len);
+        }
+        // Split the path where the prefixes diverge
+        let common = common_len(child.key, key, child.len.min(len));
+        let mut branch = Box::new(Node::new(key, common));
+        let child = slot.take().unwrap();
+        let side = bit(child.key, common);
+        branch.children[side] = Some(child);
+        let branch = slot.insert(branch);
+        if common == len {
+            return branch;
+        }
+        branch.children[bit(key, common)].insert(Box::new(Node::new(key, len)))
+    }
+    /// The node of exactly `key/len`, if any.
+    fn find(&self, key: u128, len: u8) -> Option<&Node> {
+        let mut node = self;
+        while node.len < len {
+            node = node.children[bit(key, node.len)].as_deref()?;
+            if !node.covers(key, len) {
+                return None;
+            }
+        }
+        Some(node)
+    }
+    /// Removes the route with the identity of `route` from the node of `key/len`, dropping
+    /// the nodes left without a purpose.
+    fn remove(&mut self, key: u128, len: u8, route: &Route) -> Option<Route> {
+        if self.len == len {
+            let pos = self.routes.iter().position(|v| v.same_identity(route))?;
+            return Some(self.routes.remove(pos));
+        }
+        let slot = &mut self.children[bit(key, self.len)];
+        let child = slot.as_mut().filter(|v| v.covers(key, len))?;
+        let removed = child.remove(key, len, route)?;
+        if child.routes.is_empty() {
+            match &mut child.children {
+                [None, None] => *slot = None,
+                [Some(_), None] => *slot = child.children[0].take(),
+                [None, Some(_)] => *slot = child.children[1].take(),
+                [Some(_), Some(_)] => {}
+            }
+        }
+        Some(removed)
+    }
+}
+
+fn key(ip: &IpAddr) -> u128 {
+    match ip {
+        IpAddr::V4(ip) => (u32::from(*ip) as u128) << 96,
+        IpAddr::V6(ip) => u128::from(*ip),
+    }
+}
+
+fn mask(key: u128, len: u8) -> u128 {
+    match len {
+        0 => 0,
+        len => key & (u128::MAX << (128 - len as u32)),
+    }
+}
+
+/// Length of the common prefix of `a` and `b`, up to `max` bits.
+fn common_len(a: u128, b: u128, max: u8) -> u8 {
+    ((a ^ b).leading_zeros() as u8).min(max)
+}
+
+fn bit(key: u128, depth: u8) -> usize {
+    ((key >> (127 - depth as u32)) & 1) as usize
+}
+
+impl RouteTable {
+    /// Creates an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Number of routes in the table.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Returns true if the table holds no routes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Removes all routes.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+    fn root(&self, ip: &IpAddr) -> &Node {
+        if ip.is_ipv4() {
+            &self.v4
+        } else {
+            &self.v6
+        }
+    }
+    fn root_mut(&mut self, ip: &IpAddr) -> &mut Node {
+        if ip.is_ipv4() {
+            &mut self.v4
+        } else {
+            &mut self.v6
+        }
+    }
+    /// Inserts a route. A route with the same identity already in the table, i.e. the same
+    /// destination, gateway and interface and on Linux the same table and metric, is replaced.
+    pub fn insert(&mut self, route: Route) {
+        let key = key(&route.destination);
+        let node = self
+            .root_mut(&route.destination)
+            .find_or_insert(key, route.prefix.min(128));
+        if let Some(existing) = node.routes.iter_mut().find(|v| v.same_identity(&route)) {
+            *existing = route;
+        } else {
+            node.routes.push(route);
+            self.len += 1;
+        }
+    }
+    /// Removes the route with the same identity as `route`, returning it if it was present.
+    ///
+    /// Details the system may report differently between events, such as the interface name
+    /// once the interface is gone, are not compared.
+    pub fn remove(&mut self, route: &Route) -> Option<Route> {
+        let key = key(&route.destination);
+        let removed = self
+            .root_mut(&route.destination)
+            .remove(key, route.prefix.min(128), route);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+    /// Applies a route change event, e.g. from a listener.
+    ///
+    /// A change event without the previous route replaces the routes to the same destination
+    /// on the same interface.
+    pub fn apply(&mut self, change: RouteChange) {
+        match change {
+            RouteChange::Add(route) => self.insert(route),
+            RouteChange::Delete(route) => {
+                self.remove(&route);
+            }
+            RouteChange::Change {
+                old: Some(old),
+                new,
+            } => {
+                self.remove(&old);
+                self.insert(new);
+            }
+            RouteChange::Change { old: None, new } => {
+                let stale: Vec<Route> = self
+                    .get(&new.destination, new.prefix)
+                    .iter()
+                    .filter(|v| v.if_index == new.if_index)
+                    .cloned()
+                    .collect();
+                for v in stale {
+                    self.remove(&v);
+                }
+                self.insert(new);
+            }
+        }
+    }
+    /// Returns the routes installed for exactly `destination/prefix`.
+    pub fn get(&self, destination: &IpAddr, prefix: u8) -> &[Route] {
+        match self
+            .root(destination)
+            .find(key(destination), prefix.min(128))
+        {
+            Some(node) => &node.routes,
+            None => &[],
+        }
+    }
+    /// Longest-prefix-match lookup. Among routes of equal prefix length the one with the
+    /// lowest metric wins.
+    pub fn lookup(&self, dest: &IpAddr) -> Option<&Route> {
+        let key = key(dest);
+        let max_depth = if dest.is_ipv4() { 32 } else { 128 };
+        let mut node = self.root(dest);
+        let mut best = node.routes.iter().max();
+        while node.len < max_depth {
+            match &node.children[bit(key, node.len)] {
+                Some(child) if child.covers(key, max_depth) => node = child,
+                _ => break,
+            }
+            if let Some(route) = node.routes.iter().max() {
+                best = Some(route);
+            }
+        }
+        best
+    }
+    /// Iterates over all routes, IPv4 first, in address order with each prefix before the
+    /// more specific prefixes it contains.
+    pub fn iter(&self) -> RouteTableIter<'_> {
+        RouteTableIter {
+            stack: vec![&self.v6, &self.v4],
+            routes: [].iter(),
+        }
+    }
+}
+
+/// Iterator over the routes of a [`RouteTable`].
+pub struct RouteTableIter<'a> {
+    stack: Vec<&'a Node>,
+    routes: std::slice::Iter<'a, Route>,
+}
+impl<'a> Iterator for RouteTableIter<'a> {
+    type Item = &'a Route;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(route) = self.routes.next() {
+                return Some(route);
+            }
+            let node = self.stack.pop()?;
+            for child in node.children.iter().rev().flatten() {
+                self.stack.push(child);
+            }
+            self.routes = node.routes.iter();
+        }
+    }
+}
+impl<'a> IntoIterator for &'a RouteTable {
+    type Item = &'a Route;
+    type IntoIter = RouteTableIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+impl FromIterator<Route> for RouteTable {
+    fn from_iter<T: IntoIterator<Item = Route>>(iter: T) -> Self {
+        let mut table = RouteTable::new();
+        table.extend(iter);
+        table
+    }
+}
+impl Extend<Route> for RouteTable {
+    fn extend<T: IntoIterator<Item = Route>>(&mut self, iter: T) {
+        for route in iter {
+            self.insert(route);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delete_without_if_name() {
+        let route = Route::new("10.0.0.0".parse().unwrap(), 8)
+            .with_gateway("192.168.1.1".parse().unwrap())
+            .with_if_index(7);
+        let mut table = RouteTable::new();
+        let mut added = route.clone();
+        added.if_name = Some("tun0".into());
+        table.apply(RouteChange::Add(added));
+        table.apply(RouteChange::Delete(route));
+        assert!(table.is_empty());
+    }
+
+    fn route(destination: &str, prefix: u8) -> Route {
+        Route::new(destination.parse().unwrap(), prefix).with_if_index(1)
+    }
+
+    fn lookup(table: &RouteTable, dest: &str) -> Option<(IpAddr, u8)> {
+        table
+            .lookup(&dest.parse().unwrap())
+            .map(|v| (v.destination, v.prefix))
+    }
+
+    #[test]
+    fn ipv6_longest_prefix_match() {
+        let mut table: RouteTable = [
+            route("::", 0),
+            route("2001:db8::", 32),
+            route("2001:db8:1::", 48),
+            route("2001:db8::1", 128),
+            route("2001:db8::", 127),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(table.len(), 5);
+        let host = Some(("2001:db8::1".parse().unwrap(), 128));
+        assert_eq!(lookup(&table, "2001:db8::1"), host);
+        assert_eq!(
+            lookup(&table, "2001:db8::"),
+            Some(("2001:db8::".parse().unwrap(), 127))
+        );
+        assert_eq!(
+            lookup(&table, "2001:db8:1::5"),
+            Some(("2001:db8:1::".parse().unwrap(), 48))
+        );
+        assert_eq!(
+            lookup(&table, "2001:db9::"),
+            Some(("::".parse().unwrap(), 0))
+        );
+        assert_eq!(table.get(&"2001:db8::1".parse().unwrap(), 128).len(), 1);
+        assert!(table.get(&"2001:db8::1".parse().unwrap(), 64).is_empty());
+
+        assert!(table.remove(&route("2001:db8::1", 128)).is_some());
+        assert!(table.remove(&route("2001:db8::1", 128)).is_none());
+        assert_eq!(
+            lookup(&table, "2001:db8::1"),
+            Some(("2001:db8::".parse().unwrap(), 127))
+        );
+        assert!(table.remove(&route("2001:db8::", 127)).is_some());
+        assert_eq!(
+            lookup(&table, "2001:db8::1"),
+            Some(("2001:db8::".parse().unwrap(), 32))
+        );
+        assert_eq!(table.len(), 3);
+    }
+
+    #[test]
+    fn remove_drops_unused_nodes() {
+        let routes = [
+            route("10.0.0.0", 8),
+            route("10.1.0.0", 16),
+            route("10.2.0.0", 16),
+            route("10.1.2.0", 24),
+        ];
+        let mut table: RouteTable = routes.iter().cloned().collect();
+        let order: Vec<u8> = table.iter().map(|v| v.prefix).collect();
+        assert_eq!(order, [8, 16, 24, 16]);
+        assert!(table.remove(&route("10.0.0.0", 8)).is_some());
+        assert_eq!(
+            lookup(&table, "10.1.2.3"),
+            Some(("10.1.2.0".parse().unwrap(), 24))
+        );
+        assert_eq!(
+            lookup(&table, "10.1.3.3"),
+            Some(("10.1.0.0".parse().unwrap(), 16))
+        );
+        assert_eq!(lookup(&table, "10.3.0.0"), None);
+        for route in &routes[1..] {
+            assert!(table.remove(route).is_some());
+        }
+        assert!(table.is_empty());
+        assert!(table.v4.children.iter().all(|v| v.is_none()));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::Route;
+use std::net::IpAddr;
+use std::{error, fmt, io};
+
+/// A problem found while validating a [`Route`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The prefix length exceeds the address length of the destination family.
+    InvalidPrefix { prefix: u8, max: u8 },
+    /// The gateway is not in the same address family as the destination.
+    ///
+    /// Linux accepts IPv6 gateways for IPv4 routes (RFC 5549), so this is not reported there.
+    GatewayFamilyMismatch {
+        destination: IpAddr,
+        gateway: IpAddr,
+    },
+    /// No interface with the given index exists.
+    UnknownInterfaceIndex(u32),
+    /// No interface with the given name exists.
+    UnknownInterfaceName(String),
+    /// The interface name resolves to a different index than the one set on the route.
+    InterfaceMismatch {
+        if_index: u32,
+        if_name: String,
+        resolved_index: u32,
+    },
+    /// The platform requires an output interface, but none is set.
+    MissingNexthop,
+    /// No subnet configured on the output interface (or on any interface, if none is set)
+    /// contains the gateway.
+    GatewayNotOnLink {
+        gateway: IpAddr,
+        if_index: Option<u32>,
+    },
+}
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::InvalidPrefix { prefix, max } => {
+                write!(f, "prefix length {prefix} exceeds {max}")
+            }
+            ValidationError::GatewayFamilyMismatch {
+                destination,
+                gateway,
+            } => write!(
+                f,
+                "gateway {gateway} is not in the address family of destination {destination}"
+            ),
+            ValidationError::UnknownInterfaceIndex(index) => {
+                write!(f, "no interface with index {index}")
+            }
+            ValidationError::UnknownInterfaceName(name) => {
+                write!(f, "no interface named {name}")
+            }
+            ValidationError::InterfaceMismatch {
+                if_index,
+                if_name,
+                resolved_index,
+            } => write!(
+                f,
+                "interface {if_name} has index {resolved_index}, but index {if_index} was set"
+            ),
+            ValidationError::MissingNexthop => write!(f, "an output interface is required"),
+            ValidationError::GatewayNotOnLink {
+                gateway,
+                if_index: Some(if_index),
+            } => write!(
+                f,
+                "gateway {gateway} is not within any subnet configured on interface {if_index}"
+            ),
+            ValidationError::GatewayNotOnLink {
+                gateway,
+                if_index: None,
+            } => write!(
+                f,
+                "gateway {gateway} is not within any subnet configured on this host"
+            ),
+        }
+    }
+}
+impl error::Error for ValidationError {}
+
+/// All problems found while validating a [`Route`].
+///
+/// Returned by [`Route::validate`], and carried as the inner error of the `io::Error`
+/// returned by [`Route::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationErrors(Vec<ValidationError>);
+impl ValidationErrors {
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.0
+    }
+    pub fn into_errors(self) -> Vec<ValidationError> {
+        self.0
+    }
+}
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid route: ")?;
+        for (i, e) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{e}")?;
+        }
+        Ok(())
+    }
+}
+impl error::Error for ValidationErrors {}
+impl From<ValidationErrors> for io::Error {
+    fn from(value: ValidationErrors) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, value)
+    }
+}
+
+impl Route {
+    /// Validates the route and reports every problem found.
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = Vec::new();
+        let max = if self.destination.is_ipv4() { 32 } else { 128 };
+        if self.prefix > max {
+            errors.push(ValidationError::InvalidPrefix {
+                prefix: self.prefix,
+                max,
+            });
+        }
+        // Mapped nexthops of IPv4 routes are unmapped before they reach the system
+        let input = self.input_form();
+        if let Some(gateway) = input.gateway {
+            // Linux installs these with RTA_VIA
+            let via = cfg!(target_os = "linux") && gateway.is_ipv6() && input.destination.is_ipv4();
+            if gateway.is_ipv4() != input.destination.is_ipv4() && !via {
+                errors.push(ValidationError::GatewayFamilyMismatch {
+                    destination: self.destination,
+                    gateway,
+                });
+            }
+        }
+        if let Some(index) = self.if_index {
+            if crate::cached_if_index_to_name(index).is_err() {
+                errors.push(ValidationError::UnknownInterfaceIndex(index));
+            }
+        }
+        if let Some(name) = self.if_name.as_ref() {
+            match crate::cached_if_name_to_index(name) {
+                Ok(index) => {
+                    if let Some(if_index) = self.if_index {
+                        if index != if_index {
+                            errors.push(ValidationError::InterfaceMismatch {
+                                if_index,
+                                if_name: name.to_string(),
+                                resolved_index: index,
+                            });
+                        }
+                    }
+                }
+                Err(_) => errors.push(ValidationError::UnknownInterfaceName(name.to_string())),
+            }
+        }
+        #[cfg(target_os = "windows")]
+        if self.if_index.is_none() && self.if_name.is_none() && self.luid.is_none() {
+            errors.push(ValidationError::MissingNexthop);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(errors))
+        }
+    }
+    /// Checks that the gateway is on-link, i.e. inside a subnet configured on the output
+    /// interface (or on any interface, if none is set).
+    ///
+    /// Routes without a gateway, with an unspecified gateway, or with an IPv6 link-local
+    /// gateway pass. On failure, the returned error has kind `InvalidInput` and wraps
+    /// [`ValidationErrors`].
+    pub fn check_gateway_on_link(&self) -> io::Result<()> {
+        let Some(gateway) = self.gateway else {
+            return Ok(());
+        };
+        if gateway.is_unspecified() {
+            return Ok(());
+        }
+        if let IpAddr::V6(v6) = gateway {
+            if v6.segments()[0] & 0xffc0 == 0xfe80 {
+                return Ok(());
+            }
+        }
+        let if_index = self.get_index();
+        let on_link = crate::interface_addresses()?
+            .iter()
+            .filter(|v| if_index.is_none() || Some(v.index) == if_index)
+            .any(|v| Route::new(v.addr, v.prefix).contains(&gateway));
+        if on_link {
+            Ok(())
+        } else {
+            Err(ValidationErrors(vec![ValidationError::GatewayNotOnLink {
+                gateway,
+                if_index,
+            }])
+            .into())
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use crate::{ListenerConfig, Route, RouteChange, RouteListener, RouteManager, RouteTable};
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// IP address family.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IpFamily {
+    V4,
+    V6,
+}
+
+impl IpFamily {
+    fn unspecified(self) -> IpAddr {
+        match self {
+            IpFamily::V4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            IpFamily::V6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        }
+    }
+    fn of(addr: &IpAddr) -> Self {
+        if addr.is_ipv4() {
+            IpFamily::V4
+        } else {
+            IpFamily::V6
+        }
+    }
+    fn config(self) -> ListenerConfig {
+        ListenerConfig::new()
+            .with_ipv4(self == IpFamily::V4)
+            .with_ipv6(self == IpFamily::V6)
+    }
+}
+
+/// A change of the route selected by a watcher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BestRouteChange {
+    pub(crate) old: Option<Route>,
+    pub(crate) new: Option<Route>,
+}
+
+impl BestRouteChange {
+    /// The route selected before the change, if any.
+    pub fn previous(&self) -> Option<&Route> {
+        self.old.as_ref()
+    }
+    /// The route selected after the change, if any.
+    pub fn current(&self) -> Option<&Route> {
+        self.new.as_ref()
+    }
+}
+
+impl fmt::Display for BestRouteChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |v: &Option<Route>| v.as_ref().map_or("none".to_string(), |v| v.to_string());
+        write!(f, "{} -> {}", show(&self.old), show(&self.new))
+    }
+}
+
+/// Tracks the route selected for a target in a private copy of the table.
+struct Selector {
+    target: IpAddr,
+    // Select among default routes only, rather than by longest prefix match
+    default_only: bool,
+    table: RouteTable,
+    current: Option<Route>,
+}
+
+impl Selector {
+    fn new(target: IpAddr, default_only: bool, routes: Vec<Route>) -> Self {
+        let mut selector = Self {
+            target,
+            default_only,
+            table: RouteTable::new(),
+            current: None,
+        };
+        selector.table = routes
+            .into_iter()
+            .filter(|v| selector.is_relevant(v))
+            .collect();
+        selector.current = selector.select();
+        selector
+    }
+    fn is_relevant(&self, route: &Route) -> bool {
+        if self.default_only {
+            route.prefix == 0 && route.destination.is_ipv4() == self.target.is_ipv4()
+        } else {
+            route.contains(&self.target)
+        }
+    }
+    fn select(&self) -> Option<Route> {
+        let candidates: Vec<&Route> = self
+            .table
+            .iter()
+            .filter(|v| is_effective(v) && self.is_relevant(v))
+            .collect();
+        let key = |v: &Route| {
+            (
+                std::cmp::Reverse(v.prefix),
+                super::default_route_priority(v),
+            )
+        };
+        let best = candidates.iter().map(|v| key(v)).min()?;
+        let mut tied = candidates.into_iter().filter(|v| key(v) == best);
+        let first = tied.next()?;
+        // Among equally preferred routes, stay with the current one to avoid flapping
+        let sticky = std::iter::once(first)
+            .chain(tied)
+            .find(|v| self.current.as_ref().is_some_and(|c| same_path(c, v)));
+        Some(sticky.unwrap_or(first).clone())
+    }
+    fn apply(&mut self, change: RouteChange) -> Option<BestRouteChange> {
+        let relevant = match &change {
+            RouteChange::Add(route) | RouteChange::Delete(route) => self.is_relevant(route),
+            RouteChange::Change { old, new } => {
+                self.is_relevant(new) || old.as_ref().is_some_and(|v| self.is_relevant(v))
+            }
+        };
+        if !relevant {
+            return None;
+        }
+        self.table.apply(change);
+        let new = self.select();
+        let changed = match (&self.current, &new) {
+            (Some(a), Some(b)) => !same_path(a, b),
+            (None, None) => false,
+            _ => true,
+        };
+        let old = std::mem::replace(&mut self.current, new);
+        changed.then(|| BestRouteChange {
+            old,
+            new: self.current.clone(),
+        })
+    }
+}
+
+/// Whether the system uses the route for its own traffic, as opposed to policy routing tables.
+pub(super) fn is_effective(route: &Route) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        matches!(route.table, 0 | libc::RT_TABLE_MAIN)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = route;
+        true
+    }
+}
+
+fn same_path(a: &Route, b: &Route) -> bool {
+    a.gateway == b.gateway && a.get_index() == b.get_index()
+}
+
+/// Watcher for the route the system selects, created by
+/// [`RouteManager::watch_default_route`] or [`RouteManager::watch_route`].
+pub struct RouteWatcher {
+    listener: RouteListener,
+    selector: Selector,
+}
+
+impl RouteManager {
+    /// Watches the effective default route of `family`.
+    ///
+    /// Of several default routes the preferred one is selected, in the order of
+    /// [`list_default_routes`](Self::list_default_routes); on Linux only the main table is
+    /// considered. Changes are reported only when the gateway or interface of the selected
+    /// route changes.
+    pub fn watch_default_route(family: IpFamily) -> io::Result<RouteWatcher> {
+        let (routes, listener) = Self::subscribe_with_config(family.config())?;
+        Ok(RouteWatcher {
+            listener,
+            selector: Selector::new(family.unspecified(), true, routes),
+        })
+    }
+    /// Watches the best route to `destination`, e.g. to keep a tunnel endpoint pinned to the
+    /// right uplink.
+    ///
+    /// The route is selected by longest prefix match, ties broken as in
+    /// [`watch_default_route`](Self::watch_default_route); on Linux only the main table is
+    /// considered. Changes are reported only when the gateway or interface of the selected
+    /// route changes.
+    pub fn watch_route(destination: IpAddr) -> io::Result<RouteWatcher> {
+        let (routes, listener) = Self::subscribe_with_config(IpFamily::of(&destination).config())?;
+        Ok(RouteWatcher {
+            listener,
+            selector: Selector::new(destination, false, routes),
+        })
+    }
+}
+
+impl RouteWatcher {
+    /// Returns the currently selected route.
+    pub fn current(&self) -> Option<&Route> {
+        self.selector.current.as_ref()
+    }
+    /// Waits until the selected route changes.
+    pub fn listen(&mut self) -> io::Result<BestRouteChange> {
+        loop {
+            let change = self.listener.listen()?;
+            if let Some(change) = self.selector.apply(change) {
+                return Ok(change);
+            }
+        }
+    }
+}
+
+/// Asynchronous counterpart of [`RouteWatcher`], created by
+/// [`AsyncRouteManager::watch_default_route`](crate::AsyncRouteManager::watch_default_route)
+/// or [`AsyncRouteManager::watch_route`](crate::AsyncRouteManager::watch_route).
+#[cfg(any(feature = "async", feature = "async_io"))]
+pub struct AsyncRouteWatcher {
+    listener: crate::AsyncRouteListener,
+    selector: Selector,
+}
+
+#[cfg(any(feature = "async", feature = "async_io"))]
+impl crate::AsyncRouteManager {
+    /// Watches the effective default route of `family`.
+    ///
+    /// See [`RouteManager::watch_default_route`]; the table is dumped synchronously.
+    pub fn watch_default_route(family: IpFamily) -> io::Result<AsyncRouteWatcher> {
+        let (routes, listener) = Self::subscribe_with_config(family.config())?;
+        Ok(AsyncRouteWatcher {
+            listener,
+            selector: Selector::new(family.unspecified(), true, routes),
+        })
+    }
+    /// Watches the best route to `destination`.
+    ///
+    /// See [`RouteManager::watch_route`]; the table is dumped synchronously.
+    pub fn watch_route(destination: IpAddr) -> io::Result<AsyncRouteWatcher> {
+        let (routes, listener) = Self::subscribe_with_config(IpFamily::of(&destination).config())?;
+        Ok(AsyncRouteWatcher {
+            listener,
+            selector: Selector::new(destination, false, routes),
+        })
+    }
+}
+
+#[cfg(any(feature = "async", feature = "async_io"))]
+impl AsyncRouteWatcher {
+    /// Returns the currently selected route.
+    pub fn current(&self) -> Option<&Route> {
+        self.selector.current.as_ref()
+    }
+    /// Asynchronously waits until the selected route changes.
+    ///
+    /// This method is cancel safe.
+    pub async fn listen(&mut self) -> io::Result<BestRouteChange> {
+        loop {
+            let change = self.listener.listen().await?;
+            if let Some(change) = self.selector.apply(change) {
+                return Ok(change);
+            }
+        }
+    }
+}
@@ -15,7 +15,7 @@ pub async fn main() {
     });
     // Need to set up the correct gateway
     let route = Route::new("192.168.2.0".parse().unwrap(), 24).with_if_index(1);
-    let mut manager = AsyncRouteManager::new().unwrap();
+    let manager = AsyncRouteManager::new().unwrap();
     let result = manager.add(&route).await;
     println!("route add {route} {result:?}");
     tokio::time::sleep(Duration::from_secs(1)).await;
@@ -40,7 +40,7 @@ thread::spawn(move || {
 });
 // Need to set up the correct gateway
 let route = Route::new("192.168.2.0".parse().unwrap(), 24).with_if_index(1);
-let mut manager = RouteManager::new().unwrap();
+let manager = RouteManager::new().unwrap();
 
 let result = manager.add(&route);
 println!("route add {route} {result:?}");
@@ -53,6 +53,27 @@ shutdown_handle.shutdown().unwrap();
 thread::sleep(Duration::from_secs(100));
 ```
  */
+// The stub backend of unsupported targets leaves the shared plumbing unused
+#![cfg_attr(
+    not(any(
+        windows,
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "macos",
+        target_os = "netbsd",
+        target_os = "dragonfly"
+    )),
+    allow(dead_code, unused_imports)
+)]
+/// Emits a `tracing` debug event when the `tracing` feature is enabled.
+macro_rules! debug_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
 mod common;
 #[cfg(windows)]
 mod windows;
@@ -69,20 +90,58 @@ pub use linux::*;
     target_os = "freebsd",
     target_os = "openbsd",
     target_os = "macos",
-    target_os = "netbsd"
+    target_os = "netbsd",
+    target_os = "dragonfly"
 ))]
 mod unix_bsd;
 #[cfg(any(
     target_os = "freebsd",
     target_os = "openbsd",
     target_os = "macos",
-    target_os = "netbsd"
+    target_os = "netbsd",
+    target_os = "dragonfly"
 ))]
 pub use unix_bsd::*;
 
-#[cfg(unix)]
+#[cfg(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "macos",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+))]
 mod unix;
 
-#[cfg(unix)]
+#[cfg(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "macos",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+))]
 #[allow(unused_imports)]
 pub use crate::unix::*;
+
+// Other targets, e.g. wasm32, Android or illumos, get a stub whose constructors fail
+#[cfg(not(any(
+    windows,
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "macos",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+)))]
+mod unsupported;
+#[cfg(not(any(
+    windows,
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "macos",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+)))]
+pub use unsupported::*;
@@ -1,123 +1,427 @@
+#[cfg(feature = "io_uring")]
+use crate::linux::uring::{Uring, UringSocket};
 use crate::linux::{
-    add_route_req, convert_add_route, delete_route_req, deserialize_res, list_route_req,
-    RouteSocket,
+    add_route_req, delete_route_req, deserialize_res, deserialize_res_with_origin,
+    deserialize_routes, dump_interrupted, is_dump_interrupted, list_route_req, strict_match,
+    update_route_req, RouteSocket, DUMP_ATTEMPTS, RECV_BUF_LEN,
 };
+#[cfg(not(feature = "io_uring"))]
 use crate::AsyncRoute;
-use crate::{Route, RouteChange};
+use crate::{AsyncRouteIo, DefaultRouteIo, EventOrigin, EventQueue, ListenerConfig};
+use crate::{RetryPolicy, Route, RouteChange};
 use netlink_packet_route::AddressFamily;
-use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
 use std::io;
+#[cfg(not(feature = "io_uring"))]
+use std::marker::PhantomData;
+use std::pin::pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
 /// AsyncRouteListener for asynchronously receiving route change events.
-pub struct AsyncRouteListener {
-    list: VecDeque<RouteChange>,
-    socket: AsyncRoute<RouteSocket>,
+///
+/// With the `io_uring` feature, its receives complete on an io_uring instead of being retried
+/// whenever the socket is readable, which saves syscalls at high event rates.
+pub struct AsyncRouteListener<Io = DefaultRouteIo> {
+    list: EventQueue,
+    socket: AsyncSocket<Io>,
+    buf: Vec<u8>,
 }
 impl AsyncRouteListener {
     /// Creates a new AsyncRouteListener.
     pub fn new() -> io::Result<Self> {
+        Self::with_config(ListenerConfig::default())
+    }
+    /// Creates a new AsyncRouteListener with the given queue configuration.
+    pub fn with_config(config: ListenerConfig) -> io::Result<Self> {
+        Self::with_config_and_io(config)
+    }
+}
+impl<Io: AsyncRouteIo> AsyncRouteListener<Io> {
+    /// (Unix only) Creates a new AsyncRouteListener whose socket is driven by the reactor `Io`.
+    pub fn new_with_io() -> io::Result<Self> {
+        Self::with_config_and_io(ListenerConfig::default())
+    }
+    /// (Unix only) Creates a new AsyncRouteListener with the given queue configuration, whose
+    /// socket is driven by the reactor `Io`.
+    pub fn with_config_and_io(config: ListenerConfig) -> io::Result<Self> {
         let mut route_socket = RouteSocket::new()?;
-        route_socket.add_membership()?;
-        let socket = AsyncRoute::new(route_socket)?;
+        route_socket.add_membership(&config)?;
+        crate::set_recv_buffer(&route_socket, &config)?;
+        let socket = Driver::new()?.socket(route_socket)?;
         Ok(Self {
-            list: Default::default(),
+            list: EventQueue::new(&config)?,
             socket,
+            buf: vec![0; RECV_BUF_LEN],
         })
     }
+    pub(crate) fn take_snapshot(&mut self) -> Vec<crate::Route> {
+        self.list.take_snapshot()
+    }
     /// Asynchronously listens for a route change event and returns a RouteChange.
+    ///
+    /// This method is cancel safe: if the future is dropped (e.g. in `select!`), no event is
+    /// lost and the next call picks up where this one left off.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self), ret(Display), err(level = "debug"))
+    )]
     pub async fn listen(&mut self) -> io::Result<RouteChange> {
-        if let Some(route) = self.list.pop_front() {
-            return Ok(route);
+        self.listen_with_origin().await.map(|(change, _)| change)
+    }
+    /// Asynchronously listens for a route change event and returns it together with its
+    /// sender. This method is cancel safe.
+    pub async fn listen_with_origin(&mut self) -> io::Result<(RouteChange, EventOrigin)> {
+        if let Some(event) = self.list.pop_with_origin()? {
+            return Ok(event);
         }
-        let mut buf = vec![0; 4096];
         loop {
-            let len = self.socket.read_with(|s| s.recv(&mut buf[..])).await?;
-            deserialize_res(
-                |route| {
-                    self.list.push_back(route);
+            let len = recv(&mut self.socket, &mut self.buf).await?;
+            deserialize_res_with_origin(
+                |route, origin| {
+                    self.list.push(route, origin);
                 },
-                &buf[..len],
+                &self.buf[..len],
             )?;
-            if let Some(route) = self.list.pop_front() {
-                return Ok(route);
+            if let Some(event) = self.list.pop_with_origin()? {
+                return Ok(event);
             }
         }
     }
 }
 /// AsyncRouteManager for asynchronously managing routes (adding, deleting, and listing).
-pub struct AsyncRouteManager {
-    _private: std::marker::PhantomData<()>,
+///
+/// Clones are cheap and share the manager; it can be used from several tasks at once. With
+/// the `io_uring` feature, they share an io_uring on which the replies of the kernel are
+/// received.
+pub struct AsyncRouteManager<Io = DefaultRouteIo> {
+    driver: Driver<Io>,
+    // Numbers of IPv4 and IPv6 routes of the last list, preallocated for the next
+    list_lens: Arc<[AtomicUsize; 2]>,
+    timeout: Option<Duration>,
+    normalize_mapped: bool,
+    retry: Option<RetryPolicy>,
+}
+impl<Io> Clone for AsyncRouteManager<Io> {
+    fn clone(&self) -> Self {
+        Self {
+            driver: self.driver.clone(),
+            list_lens: self.list_lens.clone(),
+            timeout: self.timeout,
+            normalize_mapped: self.normalize_mapped,
+            retry: self.retry,
+        }
+    }
 }
 impl AsyncRouteManager {
     /// Creates a new AsyncRouteManager.
     pub fn new() -> io::Result<AsyncRouteManager> {
-        Ok(AsyncRouteManager {
-            _private: std::marker::PhantomData,
-        })
+        Self::new_with_io()
     }
     /// Retrieves a new instance of AsyncRouteListener.
     pub fn listener() -> io::Result<AsyncRouteListener> {
         AsyncRouteListener::new()
     }
+}
+impl<Io: AsyncRouteIo> AsyncRouteManager<Io> {
+    /// (Unix only) Creates a new AsyncRouteManager whose sockets and timers are driven by the
+    /// reactor `Io`.
+    pub fn new_with_io() -> io::Result<Self> {
+        Ok(AsyncRouteManager {
+            driver: Driver::new()?,
+            list_lens: Default::default(),
+            timeout: None,
+            normalize_mapped: false,
+            retry: None,
+        })
+    }
+    /// (Unix only) Makes operations that do not complete within `timeout` fail with
+    /// `TimedOut`, e.g. when a reply of the kernel never arrives. Their sockets are closed.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+    /// Sets whether routes are converted with [`Route::to_canonical`] before they are
+    /// installed or deleted, see
+    /// [`RouteManagerBuilder::with_normalize_mapped`](crate::RouteManagerBuilder::with_normalize_mapped).
+    pub fn with_normalize_mapped(mut self, normalize: bool) -> Self {
+        self.normalize_mapped = normalize;
+        self
+    }
+    /// Retries operations that fail with a transient error under `policy`, see
+    /// [`RetryPolicy`].
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
 
     /// Asynchronously lists routes for a specific address family.
     async fn list_family(
-        socket: &mut AsyncRoute<RouteSocket>,
+        socket: &mut AsyncSocket<Io>,
         family: AddressFamily,
-    ) -> io::Result<Vec<RouteChange>> {
-        let mut buf = vec![0; 4096];
-        let mut list = Vec::new();
-        let req = list_route_req(family);
-        socket.write_with(|s| s.send(&req)).await?;
-        loop {
-            let len = socket.read_with(|s| s.recv(&mut buf)).await?;
-            let rs = deserialize_res(
-                |route| {
-                    list.push(route);
-                },
-                &buf[..len],
-            )?;
-            if rs {
-                break;
+        capacity: usize,
+    ) -> io::Result<Vec<Route>> {
+        let mut buf = vec![0; RECV_BUF_LEN];
+        let mut routes = Vec::with_capacity(capacity);
+        let req = list_route_req(family, None, None);
+        for _ in 0..DUMP_ATTEMPTS {
+            routes.clear();
+            let mut interrupted = false;
+            send(socket, &req).await?;
+            loop {
+                let len = recv(socket, &mut buf).await?;
+                interrupted |= is_dump_interrupted(&buf[..len]);
+                if deserialize_routes(&mut routes, &buf[..len])? {
+                    break;
+                }
             }
+            if !interrupted {
+                return Ok(routes);
+            }
+            debug_event!(?family, "route dump interrupted, dumping again");
         }
-        Ok(list)
+        Err(dump_interrupted())
     }
 
     /// Asynchronously lists all current routes.
-    pub async fn list(&mut self) -> io::Result<Vec<Route>> {
-        let mut socket = AsyncRoute::new(RouteSocket::new()?)?;
-
-        // Query IPv4 routes
-        let v4_result = Self::list_family(&mut socket, AddressFamily::Inet).await;
-
-        // Query IPv6 routes
-        let v6_result = Self::list_family(&mut socket, AddressFamily::Inet6).await;
+    ///
+    /// The IPv4 and IPv6 routes are dumped concurrently, on separate sockets.
+    ///
+    /// A dump the kernel marks as interrupted by concurrent changes is repeated; if it keeps
+    /// being interrupted, an error of kind [`Interrupted`](io::ErrorKind::Interrupted) is
+    /// returned rather than an inconsistent list.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), err(level = "debug"))
+    )]
+    pub async fn list(&self) -> io::Result<Vec<Route>> {
+        let [v4_lens, v6_lens] = &*self.list_lens;
+        let lens = (
+            v4_lens.load(Ordering::Relaxed),
+            v6_lens.load(Ordering::Relaxed),
+        );
+        let list = crate::retry_async(self.retry, Io::sleep, || self.list_once(lens));
+        let (routes, v4_len) = crate::timeout_with(self.timeout.map(Io::sleep), list).await?;
+        v4_lens.store(v4_len, Ordering::Relaxed);
+        v6_lens.store(routes.len() - v4_len, Ordering::Relaxed);
+        Ok(routes)
+    }
+    /// Lists the routes, returning them with the number of IPv4 routes, which come first.
+    async fn list_once(&self, lens: (usize, usize)) -> io::Result<(Vec<Route>, usize)> {
+        // A netlink socket runs one dump at a time
+        let mut v4_socket = self.driver.socket(RouteSocket::new()?)?;
+        let mut v6_socket = self.driver.socket(RouteSocket::new()?)?;
+        let (v4_result, v6_result) = join(
+            // The IPv6 routes are appended to the IPv4 list, which has room for both
+            Self::list_family(&mut v4_socket, AddressFamily::Inet, lens.0 + lens.1),
+            Self::list_family(&mut v6_socket, AddressFamily::Inet6, lens.1),
+        )
+        .await;
 
         // Only fail if both queries failed. If at least one succeeded, return partial results.
-        let list = match (v4_result, v6_result) {
-            (Ok(v4), Ok(v6)) => [v4, v6].concat(),
-            (Ok(v4), Err(_)) => v4,            // IPv4 succeeded
-            (Err(_), Ok(v6)) => v6,            // IPv6 succeeded
-            (Err(e), Err(_)) => return Err(e), // Both failed, return first error
+        let (mut routes, v6) = match (v4_result, v6_result) {
+            // A family that could not be dumped consistently is not a partial result
+            (Err(e), _) | (_, Err(e)) if e.kind() == io::ErrorKind::Interrupted => return Err(e),
+            (Ok(v4), Ok(v6)) => (v4, v6),
+            (Ok(v4), Err(_)) => (v4, Vec::new()), // IPv4 succeeded
+            (Err(_), Ok(v6)) => (Vec::new(), v6), // IPv6 succeeded
+            (Err(e), Err(_)) => return Err(e),    // Both failed, return first error
         };
-        Ok(convert_add_route(list))
+        let v4_len = routes.len();
+        routes.extend(v6);
+        debug_event!(count = routes.len(), "listed routes");
+        Ok((routes, v4_len))
     }
     /// Asynchronously adds a new route.
-    pub async fn add(&mut self, route: &Route) -> io::Result<()> {
-        let req = add_route_req(route)?;
-        let mut socket = AsyncRoute::new(RouteSocket::new()?)?;
-        socket.write_with(|s| s.send(&req)).await?;
-        let mut buf = vec![0; 4096];
-        let len = socket.read_with(|s| s.recv(&mut buf)).await?;
-        deserialize_res(|_| {}, &buf[..len]).map(|_| ())
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub async fn add(&self, route: &Route) -> io::Result<()> {
+        let route = &*route.normalized(self.normalize_mapped);
+        let result = match add_route_req(route) {
+            Ok(req) => {
+                crate::timeout_with(
+                    self.timeout.map(Io::sleep),
+                    send_request(&self.driver, self.retry, &req),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        result.map_err(crate::context("add", route))
     }
     /// Asynchronously deletes an existing route.
-    pub async fn delete(&mut self, route: &Route) -> io::Result<()> {
-        let req = delete_route_req(route)?;
-        let mut socket = AsyncRoute::new(RouteSocket::new()?)?;
-        socket.write_with(|s| s.send(&req)).await?;
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub async fn delete(&self, route: &Route) -> io::Result<()> {
+        let route = &*route.normalized(self.normalize_mapped);
+        let result = match delete_route_req(route) {
+            Ok(req) => {
+                crate::timeout_with(
+                    self.timeout.map(Io::sleep),
+                    send_request(&self.driver, self.retry, &req),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        result.map_err(crate::context("delete", route))
+    }
+    /// (Linux only) Asynchronously deletes the single route matching `route`. See
+    /// `RouteManager::delete_strict`.
+    pub async fn delete_strict(&self, route: &Route) -> io::Result<()> {
+        let found = strict_match(route, self.list().await?)?;
+        self.delete(&found).await
+    }
+    /// Asynchronously modifies an existing route in place. See `RouteManager::update`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub async fn update(&self, route: &Route) -> io::Result<()> {
+        let route = &*route.normalized(self.normalize_mapped);
+        let result = match update_route_req(route) {
+            Ok(req) => {
+                crate::timeout_with(
+                    self.timeout.map(Io::sleep),
+                    send_request(&self.driver, self.retry, &req),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        result.map_err(crate::context("update", route))
+    }
+}
+
+/// Runs `a` and `b` concurrently and returns both outputs.
+async fn join<A: Future, B: Future>(a: A, b: B) -> (A::Output, B::Output) {
+    let mut a = pin!(a);
+    let mut b = pin!(b);
+    let mut a_out = None;
+    let mut b_out = None;
+    poll_fn(|cx| {
+        if a_out.is_none() {
+            if let Poll::Ready(out) = a.as_mut().poll(cx) {
+                a_out = Some(out);
+            }
+        }
+        if b_out.is_none() {
+            if let Poll::Ready(out) = b.as_mut().poll(cx) {
+                b_out = Some(out);
+            }
+        }
+        match (a_out.take(), b_out.take()) {
+            (Some(a), Some(b)) => Poll::Ready((a, b)),
+            (a, b) => {
+                a_out = a;
+                b_out = b;
+                Poll::Pending
+            }
+        }
+    })
+    .await
+}
+
+/// Sends a request that changes a route and waits for its acknowledgement.
+async fn send_request<Io: AsyncRouteIo>(
+    driver: &Driver<Io>,
+    retry: Option<RetryPolicy>,
+    req: &[u8],
+) -> io::Result<()> {
+    crate::retry_async(retry, Io::sleep, || async move {
+        debug_event!(len = req.len(), "sending netlink request");
+        let mut socket = driver.socket(RouteSocket::new()?)?;
+        send(&mut socket, req).await?;
         let mut buf = vec![0; 4096];
-        let len = socket.read_with(|s| s.recv(&mut buf)).await?;
+        let len = recv(&mut socket, &mut buf).await?;
         deserialize_res(|_| {}, &buf[..len]).map(|_| ())
+    })
+    .await
+}
+
+#[cfg(not(feature = "io_uring"))]
+type AsyncSocket<Io> = AsyncRoute<RouteSocket, Io>;
+#[cfg(feature = "io_uring")]
+type AsyncSocket<Io> = UringSocket<Io>;
+
+/// Opens the sockets of an async listener or manager: polled for readiness on the reactor, or
+/// with the `io_uring` feature, receiving on an io_uring they share.
+struct Driver<Io> {
+    #[cfg(feature = "io_uring")]
+    uring: Arc<Uring<Io>>,
+    #[cfg(not(feature = "io_uring"))]
+    io: PhantomData<fn() -> Io>,
+}
+
+impl<Io> Clone for Driver<Io> {
+    fn clone(&self) -> Self {
+        Self {
+            #[cfg(feature = "io_uring")]
+            uring: self.uring.clone(),
+            #[cfg(not(feature = "io_uring"))]
+            io: PhantomData,
+        }
+    }
+}
+
+impl<Io: AsyncRouteIo> Driver<Io> {
+    fn new() -> io::Result<Self> {
+        Ok(Self {
+            #[cfg(feature = "io_uring")]
+            uring: Arc::new(Uring::new()?),
+            #[cfg(not(feature = "io_uring"))]
+            io: PhantomData,
+        })
+    }
+    #[cfg(not(feature = "io_uring"))]
+    fn socket(&self, socket: RouteSocket) -> io::Result<AsyncSocket<Io>> {
+        AsyncRoute::new(socket)
     }
+    #[cfg(feature = "io_uring")]
+    fn socket(&self, socket: RouteSocket) -> io::Result<AsyncSocket<Io>> {
+        Ok(UringSocket::new(socket, self.uring.clone()))
+    }
+}
+
+#[cfg(not(feature = "io_uring"))]
+async fn send<Io: AsyncRouteIo>(socket: &mut AsyncSocket<Io>, req: &[u8]) -> io::Result<usize> {
+    socket.write_with(|s| s.send(req)).await
+}
+#[cfg(feature = "io_uring")]
+async fn send<Io: AsyncRouteIo>(socket: &mut AsyncSocket<Io>, req: &[u8]) -> io::Result<usize> {
+    socket.send(req)
+}
+#[cfg(not(feature = "io_uring"))]
+async fn recv<Io: AsyncRouteIo>(
+    socket: &mut AsyncSocket<Io>,
+    buf: &mut Vec<u8>,
+) -> io::Result<usize> {
+    socket.read_with(|s| s.recv(buf)).await
+}
+#[cfg(feature = "io_uring")]
+async fn recv<Io: AsyncRouteIo>(
+    socket: &mut AsyncSocket<Io>,
+    buf: &mut Vec<u8>,
+) -> io::Result<usize> {
+    socket.recv(buf).await
 }
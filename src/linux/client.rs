@@ -0,0 +1,132 @@
+use crate::linux::{RouteSocket, RECV_BUF_LEN};
+use netlink_packet_core::NLA_ALIGNTO;
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Requests a client keeps in flight at once; the acks of more could overflow the receive
+/// buffer of the socket.
+const MAX_IN_FLIGHT: usize = 64;
+
+/// Sequence numbers of requests, unique in the process so that replies left over on a reused
+/// socket are never taken for those of a later request.
+static SEQ: AtomicU32 = AtomicU32::new(1);
+
+/// A netlink client that numbers its requests and matches the replies of the kernel to them
+/// by sequence number, so that several requests can be in flight on one socket.
+///
+/// It handles requests answered by a single message: an ack or error, or the reply of a
+/// lookup. Dumps are read by their callers.
+pub(crate) struct NetlinkClient<'a> {
+    socket: &'a RouteSocket,
+    buf: Vec<u8>,
+    // Requests sent and not yet taken, with their reply once received
+    pending: HashMap<u32, Option<Vec<u8>>>,
+    // Set once receiving failed; the replies still pending may have been lost
+    failed: Option<io::ErrorKind>,
+}
+
+impl<'a> NetlinkClient<'a> {
+    pub(crate) fn new(socket: &'a RouteSocket) -> Self {
+        Self {
+            socket,
+            buf: vec![0; RECV_BUF_LEN],
+            pending: HashMap::new(),
+            failed: None,
+        }
+    }
+    /// Sends `req` under a new sequence number, which is returned.
+    pub(crate) fn send(&mut self, req: &mut [u8]) -> io::Result<u32> {
+        let seq = SEQ.fetch_add(1, Ordering::Relaxed);
+        let Some(header_seq) = req.get_mut(8..12) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "netlink request shorter than its header",
+            ));
+        };
+        header_seq.copy_from_slice(&seq.to_ne_bytes());
+        self.socket.send(req)?;
+        self.pending.insert(seq, None);
+        Ok(seq)
+    }
+    /// Waits for the reply to the request `seq`, keeping the replies to other requests
+    /// received meanwhile until they are asked for.
+    pub(crate) fn reply(&mut self, seq: u32) -> io::Result<Vec<u8>> {
+        loop {
+            match self.pending.get(&seq) {
+                Some(Some(_)) => {
+                    return Ok(self.pending.remove(&seq).flatten().unwrap_or_default())
+                }
+                Some(None) => {}
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("no netlink request {seq} pending"),
+                    ))
+                }
+            }
+            if let Some(kind) = self.failed {
+                self.pending.remove(&seq);
+                return Err(io::Error::new(
+                    kind,
+                    "reply lost after a failed netlink receive",
+                ));
+            }
+            if let Err(e) = self.receive() {
+                self.failed = Some(e.kind());
+                self.pending.remove(&seq);
+                return Err(e);
+            }
+        }
+    }
+    /// Sends `req` and waits for its reply.
+    pub(crate) fn request(&mut self, mut req: Vec<u8>) -> io::Result<Vec<u8>> {
+        let seq = self.send(&mut req)?;
+        self.reply(seq)
+    }
+    /// Sends the requests without waiting for each reply before the next request, and returns
+    /// their replies in order. A request that could not be built fails on its own.
+    pub(crate) fn request_all(
+        &mut self,
+        reqs: impl IntoIterator<Item = io::Result<Vec<u8>>>,
+    ) -> Vec<io::Result<Vec<u8>>> {
+        let mut replies = Vec::new();
+        let mut in_flight = VecDeque::new();
+        for req in reqs {
+            if in_flight.len() == MAX_IN_FLIGHT {
+                if let Some((index, seq)) = in_flight.pop_front() {
+                    replies[index] = self.reply(seq);
+                }
+            }
+            match req.and_then(|mut req| self.send(&mut req)) {
+                Ok(seq) => {
+                    in_flight.push_back((replies.len(), seq));
+                    replies.push(Ok(Vec::new()));
+                }
+                Err(e) => replies.push(Err(e)),
+            }
+        }
+        for (index, seq) in in_flight {
+            replies[index] = self.reply(seq);
+        }
+        replies
+    }
+    /// Receives one datagram and files the messages in it under their requests.
+    fn receive(&mut self) -> io::Result<()> {
+        let header_len = std::mem::size_of::<libc::nlmsghdr>();
+        let len = self.socket.recv(&mut self.buf)?;
+        let mut msgs = &self.buf[..len];
+        while msgs.len() >= header_len {
+            let msg_len = u32::from_ne_bytes([msgs[0], msgs[1], msgs[2], msgs[3]]) as usize;
+            if msg_len < header_len || msg_len > msgs.len() {
+                break;
+            }
+            let seq = u32::from_ne_bytes([msgs[8], msgs[9], msgs[10], msgs[11]]);
+            if let Some(reply @ None) = self.pending.get_mut(&seq) {
+                *reply = Some(msgs[..msg_len].to_vec());
+            }
+            msgs = &msgs[msg_len.next_multiple_of(NLA_ALIGNTO).min(msgs.len())..];
+        }
+        Ok(())
+    }
+}
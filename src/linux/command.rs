@@ -0,0 +1,458 @@
+use crate::common::unsupported;
+use crate::{CommandRouteManager, Route};
+use netlink_packet_route::route::RouteFlags;
+use std::io;
+use std::net::IpAddr;
+
+impl CommandRouteManager {
+    /// Lists all current routes, parsed from `ip route show table all`.
+    pub fn list(&mut self) -> io::Result<Vec<Route>> {
+        let mut routes = Vec::new();
+        for family in ["-4", "-6"] {
+            let args = ["-N", family, "route", "show", "table", "all"].map(String::from);
+            let output = self.run("ip", &args)?;
+            routes.extend(
+                output
+                    .lines()
+                    .filter_map(|v| parse_route(v, family == "-6")),
+            );
+        }
+        debug_event!(count = routes.len(), "listed routes");
+        Ok(routes)
+    }
+    /// The `ip` command of [`add`](Self::add).
+    pub(crate) fn add_command(route: &Route) -> io::Result<(&'static str, Vec<String>)> {
+        Ok(("ip", route_args("add", route, true)?))
+    }
+    /// The `ip` command of [`delete`](Self::delete).
+    pub(crate) fn delete_command(route: &Route) -> io::Result<(&'static str, Vec<String>)> {
+        Ok(("ip", route_args("del", route, false)?))
+    }
+    /// The `ip` command of [`update`](Self::update).
+    pub(crate) fn update_command(route: &Route) -> io::Result<(&'static str, Vec<String>)> {
+        Ok(("ip", route_args("change", route, true)?))
+    }
+}
+
+/// Builds the arguments of `ip route <cmd>`; like the native requests, routes are added with
+/// the static protocol unless one is set.
+fn route_args(cmd: &str, route: &Route, static_proto: bool) -> io::Result<Vec<String>> {
+    let route = &*route.input_form();
+    route.check()?;
+    if !route.raw_attrs.is_empty() {
+        return Err(unsupported("raw attributes"));
+    }
+    let family = if route.destination.is_ipv4() {
+        "-4"
+    } else {
+        "-6"
+    };
+    let mut args: Vec<String> = vec![family.into(), "route".into(), cmd.into()];
+    args.push(format!("{}/{}", route.destination, route.prefix));
+    if let Some(gateway) = route.gateway {
+        args.push("via".into());
+        if gateway.is_ipv4() != route.destination.is_ipv4() {
+            args.push(if gateway.is_ipv4() { "inet" } else { "inet6" }.into());
+        }
+        args.push(gateway.to_string());
+    }
+    let if_name = match route.get_name() {
+        Some(name) => Some(name),
+        None => route
+            .gateway_scope_id
+            .map(crate::cached_if_index_to_name)
+            .transpose()?,
+    };
+    if let Some(if_name) = if_name {
+        args.extend(["dev".into(), if_name.to_string()]);
+    }
+    args.extend(["table".into(), route.table.to_string()]);
+    match route.protocol {
+        Some(protocol) => args.extend(["proto".into(), protocol.to_string()]),
+        None if static_proto => args.extend(["proto".into(), "static".into()]),
+        None => {}
+    }
+    if let Some(metric) = route.metric {
+        args.extend(["metric".into(), metric.to_string()]);
+    }
+    if let Some(pref_source) = route.pref_source {
+        args.extend(["src".into(), pref_source.to_string()]);
+    }
+    if let Some(source) = route.source {
+        args.extend(["from".into(), format!("{source}/{}", route.source_prefix)]);
+    }
+    Ok(args)
+}
+
+/// Parses a line of `ip -N route show`, skipping continuation lines (multipath nexthops) and
+/// routes the crate cannot represent.
+fn parse_route(line: &str, ipv6: bool) -> Option<Route> {
+    if line.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mut tokens = line.split_whitespace().peekable();
+    // Routes of a type other than unicast start with the type, numeric with `-N` since
+    // iproute2 6.0 and a word before
+    let kind = match parse_type(tokens.peek()?) {
+        Some(kind) => {
+            tokens.next();
+            kind
+        }
+        None => libc::RTN_UNICAST,
+    };
+    let (destination, prefix) = parse_prefix(tokens.next()?, ipv6)?;
+    let mut route = Route::new(destination, prefix)
+        .with_table(libc::RT_TABLE_MAIN)
+        .with_protocol(libc::RTPROT_BOOT);
+    let mut flags = RouteFlags::empty();
+    let mut scope = libc::RT_SCOPE_UNIVERSE;
+    while let Some(key) = tokens.next() {
+        match key {
+            "via" => {
+                let mut value = tokens.next()?;
+                if value == "inet" || value == "inet6" {
+                    value = tokens.next()?;
+                }
+                route.gateway = Some(value.parse().ok()?);
+            }
+            "dev" => {
+                let name = tokens.next()?;
+                route.if_index = crate::cached_if_name_to_index(name).ok();
+                route.if_name = Some(name.into());
+            }
+            // Tables above 255 are not representable, as in the native listing
+            "table" => route.table = parse_table(tokens.next()?)?,
+            "proto" => route.protocol = Some(parse_protocol(tokens.next()?)?),
+            "metric" => route.metric = Some(tokens.next()?.parse().ok()?),
+            "src" => route.pref_source = Some(tokens.next()?.parse().ok()?),
+            "from" => {
+                let (source, source_prefix) = parse_prefix(tokens.next()?, ipv6)?;
+                route.source = Some(source);
+                route.source_prefix = source_prefix;
+            }
+            "scope" => scope = parse_scope(tokens.next()?)?,
+            "dead" => flags |= RouteFlags::Dead,
+            "pervasive" => flags |= RouteFlags::Pervasive,
+            "onlink" => flags |= RouteFlags::Onlink,
+            "offload" => flags |= RouteFlags::Offload,
+            "linkdown" => flags |= RouteFlags::Linkdown,
+            "trap" => flags |= RouteFlags::Trap,
+            "notify" => flags |= RouteFlags::Notify,
+            "rt_offload" => flags |= RouteFlags::RtOffload,
+            "rt_trap" => flags |= RouteFlags::RtTrap,
+            "rt_offload_failed" => flags |= RouteFlags::OffloadFailed,
+            // Other attributes take a value, metrics optionally preceded by `lock`
+            _ => {
+                if tokens.next() == Some("lock") {
+                    tokens.next();
+                }
+            }
+        }
+    }
+    if let Some(IpAddr::V6(v6)) = route.gateway {
+        // Linux scopes link-local gateways by the output interface
+        if v6.is_unicast_link_local() {
+            route.gateway_scope_id = route.if_index;
+        }
+    }
+    route.reported.kind = Some(kind);
+    route.reported.scope = Some(scope);
+    route.reported.flags = Some(flags.bits());
+    Some(route)
+}
+
+/// Parses the type of a route, numeric or as named by `ip`; `None` if `value` is not one.
+fn parse_type(value: &str) -> Option<u8> {
+    if value.bytes().all(|v| v.is_ascii_digit()) {
+        return value.parse().ok();
+    }
+    let kind = match value {
+        "unicast" => libc::RTN_UNICAST,
+        "local" => libc::RTN_LOCAL,
+        "broadcast" => libc::RTN_BROADCAST,
+        "anycast" => libc::RTN_ANYCAST,
+        "multicast" => libc::RTN_MULTICAST,
+        "blackhole" => libc::RTN_BLACKHOLE,
+        "unreachable" => libc::RTN_UNREACHABLE,
+        "prohibit" => libc::RTN_PROHIBIT,
+        "throw" => libc::RTN_THROW,
+        "nat" => libc::RTN_NAT,
+        "xresolve" => libc::RTN_XRESOLVE,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// Parses the scope of a route, numeric or as named by `ip`.
+fn parse_scope(value: &str) -> Option<u8> {
+    match value {
+        "global" => Some(libc::RT_SCOPE_UNIVERSE),
+        "site" => Some(libc::RT_SCOPE_SITE),
+        "link" => Some(libc::RT_SCOPE_LINK),
+        "host" => Some(libc::RT_SCOPE_HOST),
+        "nowhere" => Some(libc::RT_SCOPE_NOWHERE),
+        _ => value.parse().ok(),
+    }
+}
+
+/// Parses the table of a route, numeric or as named by `ip` without `/etc/iproute2`.
+fn parse_table(value: &str) -> Option<u8> {
+    match value {
+        "unspec" => Some(libc::RT_TABLE_UNSPEC),
+        "default" => Some(libc::RT_TABLE_DEFAULT),
+        "main" => Some(libc::RT_TABLE_MAIN),
+        "local" => Some(libc::RT_TABLE_LOCAL),
+        _ => value.parse().ok(),
+    }
+}
+
+/// Parses the protocol of a route, numeric or as named by `ip` up to `static`.
+fn parse_protocol(value: &str) -> Option<u8> {
+    match value {
+        "unspec" => Some(libc::RTPROT_UNSPEC),
+        "redirect" => Some(libc::RTPROT_REDIRECT),
+        "kernel" => Some(libc::RTPROT_KERNEL),
+        "boot" => Some(libc::RTPROT_BOOT),
+        "static" => Some(libc::RTPROT_STATIC),
+        _ => value.parse().ok(),
+    }
+}
+
+/// Parses `default`, an address or `address/prefix`; a bare address is a host route.
+fn parse_prefix(value: &str, ipv6: bool) -> Option<(IpAddr, u8)> {
+    if value == "default" {
+        let unspecified = if ipv6 {
+            IpAddr::V6(std::net::Ipv6Addr::UNSPECIFIED)
+        } else {
+            IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED)
+        };
+        return Some((unspecified, 0));
+    }
+    match value.split_once('/') {
+        Some((addr, prefix)) => Some((addr.parse().ok()?, prefix.parse().ok()?)),
+        None => {
+            let addr: IpAddr = value.parse().ok()?;
+            Some((addr, if addr.is_ipv4() { 32 } else { 128 }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A line of `ip route show table all` and the route it describes: destination, prefix,
+    /// gateway, table, protocol, metric, type and scope.
+    type Case = (
+        &'static str,
+        &'static str,
+        u8,
+        Option<&'static str>,
+        u8,
+        u8,
+        Option<u32>,
+        u8,
+        u8,
+    );
+
+    fn check(cases: &[Case], ipv6: bool) {
+        for &(line, destination, prefix, gateway, table, protocol, metric, kind, scope) in cases {
+            let route = parse_route(line, ipv6).unwrap_or_else(|| panic!("not parsed: {line}"));
+            assert_eq!(
+                route.destination,
+                destination.parse::<IpAddr>().unwrap(),
+                "{line}"
+            );
+            assert_eq!(route.prefix, prefix, "{line}");
+            assert_eq!(route.gateway, gateway.map(|v| v.parse().unwrap()), "{line}");
+            assert_eq!(route.table, table, "{line}");
+            assert_eq!(route.protocol, Some(protocol), "{line}");
+            assert_eq!(route.metric, metric, "{line}");
+            assert_eq!(route.reported.kind, Some(kind), "{line}");
+            assert_eq!(route.reported.scope, Some(scope), "{line}");
+        }
+    }
+
+    #[test]
+    fn parse_ipv4_routes() {
+        check(
+            &[
+                (
+                    "default via 192.168.1.1 dev eth0 proto 16 src 192.168.1.20 metric 100",
+                    "0.0.0.0",
+                    0,
+                    Some("192.168.1.1"),
+                    libc::RT_TABLE_MAIN,
+                    16,
+                    Some(100),
+                    libc::RTN_UNICAST,
+                    libc::RT_SCOPE_UNIVERSE,
+                ),
+                (
+                    "192.168.1.0/24 dev eth0 proto kernel scope link src 192.168.1.20",
+                    "192.168.1.0",
+                    24,
+                    None,
+                    libc::RT_TABLE_MAIN,
+                    libc::RTPROT_KERNEL,
+                    None,
+                    libc::RTN_UNICAST,
+                    libc::RT_SCOPE_LINK,
+                ),
+                (
+                    "10.8.0.0/16 via 10.0.0.1 dev eth1 table 100 proto static metric 5 mtu lock 1400",
+                    "10.8.0.0",
+                    16,
+                    Some("10.0.0.1"),
+                    100,
+                    libc::RTPROT_STATIC,
+                    Some(5),
+                    libc::RTN_UNICAST,
+                    libc::RT_SCOPE_UNIVERSE,
+                ),
+                (
+                    "10.9.0.0/16 via 10.0.0.1 dev eth1 mtu 1400 advmss 1360 proto 4",
+                    "10.9.0.0",
+                    16,
+                    Some("10.0.0.1"),
+                    libc::RT_TABLE_MAIN,
+                    libc::RTPROT_STATIC,
+                    None,
+                    libc::RTN_UNICAST,
+                    libc::RT_SCOPE_UNIVERSE,
+                ),
+                (
+                    "local 127.0.0.1 dev lo table local proto kernel scope host src 127.0.0.1",
+                    "127.0.0.1",
+                    32,
+                    None,
+                    libc::RT_TABLE_LOCAL,
+                    libc::RTPROT_KERNEL,
+                    None,
+                    libc::RTN_LOCAL,
+                    libc::RT_SCOPE_HOST,
+                ),
+                (
+                    "2 127.0.0.0/8 dev lo table 255 proto 2 scope 254 src 127.0.0.1",
+                    "127.0.0.0",
+                    8,
+                    None,
+                    libc::RT_TABLE_LOCAL,
+                    libc::RTPROT_KERNEL,
+                    None,
+                    libc::RTN_LOCAL,
+                    libc::RT_SCOPE_HOST,
+                ),
+                (
+                    "broadcast 192.168.1.255 dev eth0 table local proto kernel scope link src 192.168.1.20",
+                    "192.168.1.255",
+                    32,
+                    None,
+                    libc::RT_TABLE_LOCAL,
+                    libc::RTPROT_KERNEL,
+                    None,
+                    libc::RTN_BROADCAST,
+                    libc::RT_SCOPE_LINK,
+                ),
+                (
+                    "unreachable 10.97.2.0/24 proto boot",
+                    "10.97.2.0",
+                    24,
+                    None,
+                    libc::RT_TABLE_MAIN,
+                    libc::RTPROT_BOOT,
+                    None,
+                    libc::RTN_UNREACHABLE,
+                    libc::RT_SCOPE_UNIVERSE,
+                ),
+                (
+                    "blackhole 10.97.3.0/24 table 100",
+                    "10.97.3.0",
+                    24,
+                    None,
+                    100,
+                    libc::RTPROT_BOOT,
+                    None,
+                    libc::RTN_BLACKHOLE,
+                    libc::RT_SCOPE_UNIVERSE,
+                ),
+            ],
+            false,
+        );
+    }
+
+    #[test]
+    fn parse_ipv6_routes() {
+        check(
+            &[
+                (
+                    "2001:db8::/64 dev eth0 proto 9 metric 100 expires 1789sec pref medium",
+                    "2001:db8::",
+                    64,
+                    None,
+                    libc::RT_TABLE_MAIN,
+                    9,
+                    Some(100),
+                    libc::RTN_UNICAST,
+                    libc::RT_SCOPE_UNIVERSE,
+                ),
+                (
+                    "fe80::/64 dev eth0 proto kernel metric 256 pref medium",
+                    "fe80::",
+                    64,
+                    None,
+                    libc::RT_TABLE_MAIN,
+                    libc::RTPROT_KERNEL,
+                    Some(256),
+                    libc::RTN_UNICAST,
+                    libc::RT_SCOPE_UNIVERSE,
+                ),
+                (
+                    "default via fe80::1 dev eth0 proto 9 metric 1024 expires 1795sec mtu 1480 hoplimit 64 pref high",
+                    "::",
+                    0,
+                    Some("fe80::1"),
+                    libc::RT_TABLE_MAIN,
+                    9,
+                    Some(1024),
+                    libc::RTN_UNICAST,
+                    libc::RT_SCOPE_UNIVERSE,
+                ),
+                (
+                    "local ::1 dev lo table local proto kernel metric 0 pref medium",
+                    "::1",
+                    128,
+                    None,
+                    libc::RT_TABLE_LOCAL,
+                    libc::RTPROT_KERNEL,
+                    Some(0),
+                    libc::RTN_LOCAL,
+                    libc::RT_SCOPE_UNIVERSE,
+                ),
+                (
+                    "multicast ff00::/8 dev eth0 table local proto kernel metric 256 pref medium",
+                    "ff00::",
+                    8,
+                    None,
+                    libc::RT_TABLE_LOCAL,
+                    libc::RTPROT_KERNEL,
+                    Some(256),
+                    libc::RTN_MULTICAST,
+                    libc::RT_SCOPE_UNIVERSE,
+                ),
+            ],
+            true,
+        );
+    }
+
+    #[test]
+    fn skip_unrepresentable_lines() {
+        for line in [
+            "\tnexthop via 10.0.0.1 dev eth0 weight 1",
+            "10.10.0.0/16 dev eth0 table 1000 proto kernel",
+            "10.11.0.0/16 dev eth0 table vpn",
+        ] {
+            assert!(parse_route(line, false).is_none(), "{line}");
+        }
+    }
+}
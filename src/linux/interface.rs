@@ -0,0 +1,137 @@
+use crate::linux::{deserialize_msg, RouteSocket};
+use crate::Interface;
+use netlink_packet_core::{
+    NetlinkHeader, NetlinkMessage, NetlinkPayload, NLM_F_ACK, NLM_F_DUMP, NLM_F_REQUEST,
+};
+use netlink_packet_route::link::{LinkAttribute, LinkFlags, LinkMessage};
+use netlink_packet_route::RouteNetlinkMessage;
+use std::io;
+
+/// Lists all network interfaces.
+pub fn list_interfaces() -> io::Result<Vec<Interface>> {
+    let socket = RouteSocket::new()?;
+    socket.send(&list_link_req())?;
+    // Link messages carry statistics and are considerably larger than route messages.
+    let mut buf = vec![0; 32 * 1024];
+    let mut list = Vec::new();
+    loop {
+        let len = socket.recv(&mut buf)?;
+        let rs = deserialize_msg(
+            |msg| {
+                if let RouteNetlinkMessage::NewLink(msg) = msg {
+                    list.push(msg.into());
+                }
+                Ok(())
+            },
+            &buf[..len],
+        )?;
+        if rs {
+            break;
+        }
+    }
+    Ok(list)
+}
+
+/// LinkManager is used for configuring network interfaces (up/down, MTU).
+pub struct LinkManager {
+    _private: std::marker::PhantomData<()>,
+}
+impl LinkManager {
+    /// Creates a new LinkManager.
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            _private: std::marker::PhantomData,
+        })
+    }
+    /// Lists all network interfaces.
+    pub fn list(&mut self) -> io::Result<Vec<Interface>> {
+        list_interfaces()
+    }
+    /// Sets the interface administratively up.
+    pub fn set_up(&mut self, if_index: u32) -> io::Result<()> {
+        let mut msg = LinkMessage::default();
+        msg.header.index = if_index;
+        msg.header.flags = LinkFlags::Up;
+        msg.header.change_mask = LinkFlags::Up;
+        set_link(msg)
+    }
+    /// Sets the interface administratively down.
+    pub fn set_down(&mut self, if_index: u32) -> io::Result<()> {
+        let mut msg = LinkMessage::default();
+        msg.header.index = if_index;
+        msg.header.change_mask = LinkFlags::Up;
+        set_link(msg)
+    }
+    /// Sets the MTU of the interface.
+    pub fn set_mtu(&mut self, if_index: u32, mtu: u32) -> io::Result<()> {
+        let mut msg = LinkMessage::default();
+        msg.header.index = if_index;
+        msg.attributes.push(LinkAttribute::Mtu(mtu));
+        set_link(msg)
+    }
+}
+
+fn set_link(msg: LinkMessage) -> io::Result<()> {
+    let req = set_link_req(msg);
+    let socket = RouteSocket::new()?;
+    socket.send(&req)?;
+    let mut buf = vec![0; 4096];
+    let len = socket.recv(&mut buf)?;
+    deserialize_msg(|_| Ok(()), &buf[..len]).map(|_| ())
+}
+
+impl From<LinkMessage> for Interface {
+    fn from(msg: LinkMessage) -> Self {
+        let mut name = String::new();
+        let mut mac = None;
+        let mut mtu = 0;
+        for x in msg.attributes {
+            match x {
+                LinkAttribute::IfName(v) => name = v,
+                LinkAttribute::Address(v) => mac = <[u8; 6]>::try_from(v.as_slice()).ok(),
+                LinkAttribute::Mtu(v) => mtu = v,
+                _ => {}
+            }
+        }
+        Interface {
+            index: msg.header.index,
+            name,
+            mac,
+            mtu,
+            flags: msg.header.flags.bits(),
+            is_up: msg.header.flags.contains(LinkFlags::Up),
+        }
+    }
+}
+
+pub(crate) fn list_link_req() -> Vec<u8> {
+    let mut nl_hdr = NetlinkHeader::default();
+    nl_hdr.flags = NLM_F_REQUEST | NLM_F_DUMP;
+
+    let mut packet = NetlinkMessage::new(
+        nl_hdr,
+        NetlinkPayload::from(RouteNetlinkMessage::GetLink(LinkMessage::default())),
+    );
+
+    packet.finalize();
+
+    let mut buf = vec![0; packet.header.length as usize];
+    packet.serialize(&mut buf[..]);
+    buf
+}
+
+pub(crate) fn set_link_req(msg: LinkMessage) -> Vec<u8> {
+    let mut nl_hdr = NetlinkHeader::default();
+    nl_hdr.flags = NLM_F_REQUEST | NLM_F_ACK;
+
+    let mut packet = NetlinkMessage::new(
+        nl_hdr,
+        NetlinkPayload::from(RouteNetlinkMessage::SetLink(msg)),
+    );
+
+    packet.finalize();
+
+    let mut buf = vec![0; packet.header.length as usize];
+    packet.serialize(&mut buf[..]);
+    buf
+}
@@ -1,28 +1,46 @@
 use libc::RTM_DELROUTE;
 use netlink_packet_core::{
-    NetlinkHeader, NetlinkMessage, NetlinkPayload, NLM_F_ACK, NLM_F_CREATE, NLM_F_DUMP, NLM_F_EXCL,
-    NLM_F_REQUEST,
+    DefaultNla, ErrorMessage, NetlinkBuffer, NetlinkHeader, NetlinkMessage, NetlinkPayload, Nla,
+    NlasIterator, NLA_ALIGNTO, NLA_TYPE_MASK, NLMSG_ERROR, NLM_F_ACK, NLM_F_ACK_TLVS, NLM_F_CAPPED,
+    NLM_F_CREATE, NLM_F_DUMP, NLM_F_DUMP_INTR, NLM_F_EXCL, NLM_F_REPLACE, NLM_F_REQUEST,
 };
 use netlink_packet_route::route::{
-    RouteAddress, RouteAttribute, RouteMessage, RouteProtocol, RouteScope, RouteType,
+    RouteAddress, RouteAttribute, RouteFlags, RouteMessage, RouteMetric, RouteProtocol, RouteScope,
+    RouteType, RouteVia,
 };
 use netlink_packet_route::{AddressFamily, RouteNetlinkMessage};
 use netlink_sys::{protocols::NETLINK_ROUTE, Socket, SocketAddr};
-use std::collections::VecDeque;
+use std::collections::HashMap;
 use std::io;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
-use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::ops::{Deref, DerefMut};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::{Duration, Instant};
 
-use crate::{Route, RouteChange};
+use crate::linux::client::NetlinkClient;
+use crate::{
+    network_address, EventOrigin, EventQueue, ListenerConfig, RawAttribute, Reported, Route,
+    RouteChange,
+};
 #[cfg(any(feature = "async", feature = "async_io"))]
 pub(crate) mod async_route;
 #[cfg(any(feature = "async", feature = "async_io"))]
 pub use async_route::*;
+mod client;
+#[cfg(feature = "command")]
+mod command;
+mod interface;
+#[cfg(all(feature = "io_uring", any(feature = "async", feature = "async_io")))]
+mod uring;
+pub use interface::*;
 
 /// RouteListener for receiving route change events.
 pub struct RouteListener {
-    list: VecDeque<RouteChange>,
+    pub(crate) list: EventQueue,
     route_socket: RouteSocket,
+    buf: Vec<u8>,
     #[cfg(feature = "shutdown")]
     pub(crate) shutdown_handle: crate::RouteListenerShutdown,
 }
@@ -31,75 +49,202 @@ impl AsRawFd for RouteListener {
         self.route_socket.as_raw_fd()
     }
 }
+impl AsFd for RouteListener {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.route_socket.as_fd()
+    }
+}
+impl IntoRawFd for RouteListener {
+    /// Returns the netlink socket, which keeps its multicast subscriptions. Events buffered
+    /// by the listener are lost.
+    fn into_raw_fd(self) -> RawFd {
+        self.route_socket.into_raw_fd()
+    }
+}
+impl FromRawFd for RouteListener {
+    /// Creates a RouteListener on the netlink socket `fd`, e.g. one returned by
+    /// [`into_raw_fd`](IntoRawFd::into_raw_fd) before an `exec`, with the default queue
+    /// configuration. The socket is used with the multicast subscriptions it has; see
+    /// [`RouteListener::from_socket`] to subscribe a new socket.
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        let route_socket = RouteSocket::adopt_raw(fd);
+        #[cfg(feature = "shutdown")]
+        let _ = route_socket.0.set_non_blocking(true);
+        Self {
+            list: EventQueue::new_or_empty(&ListenerConfig::default()),
+            route_socket,
+            buf: vec![0; RECV_BUF_LEN],
+            #[cfg(feature = "shutdown")]
+            shutdown_handle: crate::RouteListenerShutdown::new(),
+        }
+    }
+}
 
 impl RouteListener {
     /// Creates a new RouteListener.
     pub fn new() -> io::Result<Self> {
-        let mut route_socket = RouteSocket::new()?;
-        route_socket.add_membership()?;
+        Self::with_config(ListenerConfig::default())
+    }
+    /// Creates a new RouteListener with the given queue configuration.
+    pub fn with_config(config: ListenerConfig) -> io::Result<Self> {
+        Self::with_socket(RouteSocket::new()?, config)
+    }
+    /// (Unix only) Creates a RouteListener that receives the route change events on
+    /// `socket`, a `NETLINK_ROUTE` socket opened elsewhere, e.g. by a privileged parent of a
+    /// sandboxed process. It subscribes the socket to the multicast groups of `config`.
+    ///
+    /// Fails with `InvalidInput` if `socket` is not a `NETLINK_ROUTE` socket.
+    pub fn from_socket(socket: OwnedFd, config: ListenerConfig) -> io::Result<Self> {
+        Self::with_socket(RouteSocket::from_owned(socket)?, config)
+    }
+    fn with_socket(mut route_socket: RouteSocket, config: ListenerConfig) -> io::Result<Self> {
+        route_socket.add_membership(&config)?;
+        crate::set_recv_buffer(&route_socket, &config)?;
         #[cfg(feature = "shutdown")]
         route_socket.0.set_non_blocking(true)?;
         Ok(Self {
-            list: Default::default(),
+            list: EventQueue::new(&config)?,
             route_socket,
+            buf: vec![0; RECV_BUF_LEN],
             #[cfg(feature = "shutdown")]
-            shutdown_handle: crate::RouteListenerShutdown::new()?,
+            shutdown_handle: crate::RouteListenerShutdown::new(),
         })
     }
-    /// Listens for a route change event and returns a RouteChange.
+    /// Listens for a route change event and returns it together with its sender.
     #[cfg(not(feature = "shutdown"))]
-    pub fn listen(&mut self) -> io::Result<RouteChange> {
-        if let Some(route) = self.list.pop_front() {
-            return Ok(route);
+    pub fn listen_with_origin(&mut self) -> io::Result<(RouteChange, EventOrigin)> {
+        if let Some(event) = self.list.pop_with_origin()? {
+            return Ok(event);
         }
-        let mut buf = vec![0; 4096];
         loop {
-            let len = self.route_socket.recv(&mut buf)?;
-            deserialize_res(
-                |route| {
-                    self.list.push_back(route);
+            let len = self.route_socket.recv(&mut self.buf)?;
+            deserialize_res_with_origin(
+                |route, origin| {
+                    self.list.push(route, origin);
                 },
-                &buf[..len],
+                &self.buf[..len],
             )?;
-            if let Some(route) = self.list.pop_front() {
-                return Ok(route);
+            if let Some(event) = self.list.pop_with_origin()? {
+                return Ok(event);
             }
         }
     }
 }
 impl RouteListener {
-    /// Listens for a route change event and returns a RouteChange.
+    /// Listens for a route change event and returns it together with its sender.
     #[cfg(feature = "shutdown")]
-    pub fn listen(&mut self) -> io::Result<RouteChange> {
-        if let Some(route) = self.list.pop_front() {
-            return Ok(route);
+    pub fn listen_with_origin(&mut self) -> io::Result<(RouteChange, EventOrigin)> {
+        if let Some(event) = self.list.pop_with_origin()? {
+            return Ok(event);
         }
-        let mut buf = vec![0; 4096];
         loop {
             self.wait()?;
-            let len = match self.route_socket.recv(&mut buf) {
+            let len = match self.route_socket.recv(&mut self.buf) {
                 Ok(list) => list,
                 Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => continue,
                 Err(e) => return Err(e),
             };
-            deserialize_res(
-                |route| {
-                    self.list.push_back(route);
+            deserialize_res_with_origin(
+                |route, origin| {
+                    self.list.push(route, origin);
                 },
-                &buf[..len],
+                &self.buf[..len],
             )?;
-            if let Some(route) = self.list.pop_front() {
-                return Ok(route);
+            if let Some(event) = self.list.pop_with_origin()? {
+                return Ok(event);
+            }
+        }
+    }
+}
+impl RouteListener {
+    pub(crate) fn take_snapshot(&mut self) -> Vec<crate::Route> {
+        self.list.take_snapshot()
+    }
+    /// Receives one message and buffers the route changes it carries.
+    pub(crate) fn recv_once(&mut self) -> io::Result<()> {
+        let len = match self.route_socket.recv(&mut self.buf) {
+            Ok(len) => len,
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        deserialize_res_with_origin(
+            |route, origin| {
+                self.list.push(route, origin);
+            },
+            &self.buf[..len],
+        )?;
+        Ok(())
+    }
+}
+impl RouteListener {
+    /// Waits for route change events and passes them to `f` as views of the receive buffer,
+    /// without building a [`Route`] for each. Returns the number of events passed, at least
+    /// one.
+    ///
+    /// The event queue is bypassed: events buffered for [`listen`](Self::listen) are not
+    /// passed, and the snapshot, deduplication and filters of the [`ListenerConfig`] do not
+    /// apply.
+    pub fn listen_ref<F: FnMut(crate::RouteRef<'_>)>(&mut self, mut f: F) -> io::Result<usize> {
+        loop {
+            #[cfg(feature = "shutdown")]
+            let len = {
+                self.wait()?;
+                match self.route_socket.recv(&mut self.buf) {
+                    Ok(len) => len,
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                    Err(e) => return Err(e),
+                }
+            };
+            #[cfg(not(feature = "shutdown"))]
+            let len = self.route_socket.recv(&mut self.buf)?;
+            let count = deserialize_route_refs(&mut f, &self.buf[..len])?;
+            if count > 0 {
+                return Ok(count);
             }
         }
     }
 }
 /// RouteManager is used for managing routes (adding, deleting, and listing).
+///
+/// Clones are cheap and share the manager; it can be used from several threads at once.
+#[derive(Clone)]
 pub struct RouteManager {
-    _private: std::marker::PhantomData<()>,
+    defaults: crate::RouteManagerBuilder,
+    // Injected by from_socket and used by one request at a time; otherwise each request
+    // opens its own socket
+    socket: Option<Arc<Mutex<RouteSocket>>>,
+    // Reused by the dumps of list and stats
+    bufs: Arc<crate::BufPool>,
+    // Number of routes of the last list, preallocated for the next
+    list_len: Arc<AtomicUsize>,
+}
+
+/// The socket of a request: the injected one, locked until the request is done, or a new one.
+enum ManagerSocket<'a> {
+    Shared(MutexGuard<'a, RouteSocket>),
+    Owned(RouteSocket),
+}
+impl Deref for ManagerSocket<'_> {
+    type Target = RouteSocket;
+
+    fn deref(&self) -> &RouteSocket {
+        match self {
+            ManagerSocket::Shared(socket) => socket,
+            ManagerSocket::Owned(socket) => socket,
+        }
+    }
+}
+impl DerefMut for ManagerSocket<'_> {
+    fn deref_mut(&mut self) -> &mut RouteSocket {
+        match self {
+            ManagerSocket::Shared(socket) => socket,
+            ManagerSocket::Owned(socket) => socket,
+        }
+    }
 }
 
-pub(crate) struct RouteSocket(Socket);
+// The receive timeout set by a manager, after which a blocked receive fails with TimedOut
+pub(crate) struct RouteSocket(Socket, Option<Duration>);
 impl AsRawFd for RouteSocket {
     fn as_raw_fd(&self) -> RawFd {
         self.0.as_raw_fd()
@@ -110,19 +255,106 @@ impl AsFd for RouteSocket {
         self.0.as_fd()
     }
 }
+impl Drop for RouteSocket {
+    fn drop(&mut self) {
+        if let Some(port_number) = self.port_number() {
+            unregister_port(port_number);
+        }
+    }
+}
 impl RouteSocket {
     pub(crate) fn new() -> io::Result<Self> {
-        Ok(Self(route_socket()?))
+        Ok(Self::with_port(route_socket()?))
+    }
+    fn from_owned(socket: OwnedFd) -> io::Result<Self> {
+        Ok(Self::with_port(adopt_socket(socket)?))
+    }
+    unsafe fn adopt_raw(fd: RawFd) -> Self {
+        Self::with_port(adopt_raw_socket(fd))
+    }
+    /// Records the port of `socket` as one of this process until the socket is dropped.
+    fn with_port(socket: Socket) -> Self {
+        let socket = Self(socket, None);
+        if let Some(port_number) = socket.port_number() {
+            register_port(port_number);
+        }
+        socket
+    }
+    fn port_number(&self) -> Option<u32> {
+        let mut addr = SocketAddr::new(0, 0);
+        self.0.get_address(&mut addr).ok()?;
+        Some(addr.port_number()).filter(|v| *v != 0)
+    }
+    fn into_raw_fd(self) -> RawFd {
+        if let Some(port_number) = self.port_number() {
+            unregister_port(port_number);
+        }
+        let socket = std::mem::ManuallyDrop::new(self);
+        socket.0.as_raw_fd()
+    }
+    /// Discards replies left over from an earlier, failed request on a reused socket.
+    fn drain(&self) {
+        let mut buf = vec![0; 4096];
+        while self.0.recv(&mut &mut buf[..], libc::MSG_DONTWAIT).is_ok() {}
     }
     pub(crate) fn send(&self, buf: &[u8]) -> io::Result<usize> {
         self.0.send(buf, 0)
     }
-    pub(crate) fn recv(&self, mut buf: &mut [u8]) -> io::Result<usize> {
-        self.0.recv(&mut buf, 0)
+    /// Receives one message into `buf`, which is first grown to [`RECV_BUF_LEN`] bytes.
+    pub(crate) fn recv(&self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        if buf.len() < RECV_BUF_LEN {
+            buf.resize(RECV_BUF_LEN, 0);
+        }
+        let len = self
+            .0
+            .recv(&mut &mut buf[..], libc::MSG_TRUNC)
+            .map_err(|e| self.timed_out(e))?;
+        check_truncated(buf, len)
     }
-    pub(crate) fn add_membership(&mut self) -> io::Result<()> {
-        self.0.add_membership(libc::RTNLGRP_IPV4_ROUTE)?;
-        self.0.add_membership(libc::RTNLGRP_IPV6_ROUTE)?;
+    /// Makes receives that wait longer than `timeout` fail with `TimedOut`.
+    fn set_recv_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        if self.1 == Some(timeout) {
+            return Ok(());
+        }
+        let tv = libc::timeval {
+            tv_sec: timeout.as_secs().try_into().unwrap_or(libc::time_t::MAX),
+            tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+        };
+        let rs = unsafe {
+            libc::setsockopt(
+                self.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                (&tv as *const libc::timeval).cast(),
+                std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if rs < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        self.1 = Some(timeout);
+        Ok(())
+    }
+    fn timed_out(&self, e: io::Error) -> io::Error {
+        match self.1 {
+            Some(timeout) if e.kind() == io::ErrorKind::WouldBlock => io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("no netlink reply within {timeout:?}"),
+            ),
+            _ => e,
+        }
+    }
+    pub(crate) fn add_membership(&mut self, config: &ListenerConfig) -> io::Result<()> {
+        config.check_families()?;
+        if config.ipv4 {
+            self.0.add_membership(libc::RTNLGRP_IPV4_ROUTE)?;
+        }
+        if config.ipv6 {
+            self.0.add_membership(libc::RTNLGRP_IPV6_ROUTE)?;
+        }
+        if crate::InterfaceCache::is_enabled() {
+            self.0.add_membership(libc::RTNLGRP_LINK)?;
+        }
         Ok(())
     }
 }
@@ -130,8 +362,64 @@ impl RouteSocket {
 impl RouteManager {
     /// Creates a new RouteManager.
     pub fn new() -> io::Result<Self> {
-        Ok(Self {
-            _private: std::marker::PhantomData,
+        Self::with_defaults(Default::default())
+    }
+    pub(crate) fn with_defaults(defaults: crate::RouteManagerBuilder) -> io::Result<Self> {
+        Ok(Self::with_parts(defaults, None))
+    }
+    /// (Unix only) Creates a manager that sends all requests through `socket`, a
+    /// `NETLINK_ROUTE` socket, instead of opening sockets of its own.
+    ///
+    /// This allows privilege separation: Linux checks `CAP_NET_ADMIN` against the process
+    /// that opened the socket, so a privileged parent can open it and pass it to a sandboxed
+    /// child. Fails with `InvalidInput` if `socket` is not a `NETLINK_ROUTE` socket.
+    pub fn from_socket(socket: OwnedFd) -> io::Result<Self> {
+        let socket = RouteSocket::from_owned(socket)?;
+        Ok(Self::with_parts(Default::default(), Some(socket)))
+    }
+    fn with_parts(defaults: crate::RouteManagerBuilder, socket: Option<RouteSocket>) -> Self {
+        Self {
+            defaults,
+            socket: socket.map(|v| Arc::new(Mutex::new(v))),
+            bufs: Arc::new(crate::BufPool::new(RECV_BUF_LEN)),
+            list_len: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+    /// Returns the injected socket, or a new one, with the timeout of the manager.
+    fn socket(&self) -> io::Result<ManagerSocket<'_>> {
+        let mut socket = match &self.socket {
+            Some(socket) => {
+                let socket = socket.lock().unwrap();
+                socket.drain();
+                ManagerSocket::Shared(socket)
+            }
+            None => ManagerSocket::Owned(RouteSocket::new()?),
+        };
+        if let Some(timeout) = self.defaults.timeout {
+            socket.set_recv_timeout(timeout)?;
+        }
+        Ok(socket)
+    }
+    pub(crate) fn defaults(&self) -> &crate::RouteManagerBuilder {
+        &self.defaults
+    }
+    /// Builds the netlink request of a dry run, see [`RouteRequest`](crate::RouteRequest).
+    pub(crate) fn request(
+        &self,
+        kind: crate::RequestKind,
+        route: &Route,
+    ) -> io::Result<crate::RouteRequest> {
+        let route = &*self.defaults.apply(route);
+        let bytes = match kind {
+            crate::RequestKind::Add => add_route_req(route)?,
+            crate::RequestKind::Delete => delete_route_req(route)?,
+            crate::RequestKind::Update => update_route_req(route)?,
+        };
+        let decoded = <NetlinkMessage<RouteNetlinkMessage>>::deserialize(&bytes)
+            .map_err(|e| io::Error::other(format!("{e:?}")))?;
+        Ok(crate::RouteRequest {
+            decoded: format!("{decoded:?}"),
+            bytes,
         })
     }
     /// Returns a new instance of RouteListener.
@@ -139,86 +427,659 @@ impl RouteManager {
         RouteListener::new()
     }
 
-    /// Lists routes for a specific address family.
-    fn list_family(socket: &RouteSocket, family: AddressFamily) -> io::Result<Vec<RouteChange>> {
-        let mut buf = vec![0; 4096];
-        let mut list = Vec::new();
-        let req = list_route_req(family);
-        socket.send(&req)?;
+    /// Appends the routes of a specific address family to `routes`, which is left as it was
+    /// on failure.
+    fn list_family(
+        socket: &RouteSocket,
+        family: AddressFamily,
+        oif: Option<u32>,
+        buf: &mut Vec<u8>,
+        routes: &mut Vec<Route>,
+    ) -> io::Result<()> {
+        let start = routes.len();
+        let req = list_route_req(family, oif, None);
+        for _ in 0..DUMP_ATTEMPTS {
+            match Self::dump_routes(socket, &req, buf, routes) {
+                Ok(false) => return Ok(()),
+                Ok(true) => {
+                    debug_event!(?family, "route dump interrupted, dumping again");
+                }
+                Err(e) => {
+                    routes.truncate(start);
+                    return Err(e);
+                }
+            }
+            routes.truncate(start);
+        }
+        Err(dump_interrupted())
+    }
+    /// Sends the dump request `req` and parses the reply into `routes`, returning whether the
+    /// kernel marked the dump as interrupted.
+    fn dump_routes(
+        socket: &RouteSocket,
+        req: &[u8],
+        buf: &mut Vec<u8>,
+        routes: &mut Vec<Route>,
+    ) -> io::Result<bool> {
+        let mut interrupted = false;
+        socket.send(req)?;
         loop {
-            let len = socket.recv(&mut buf)?;
-            let rs = deserialize_res(
-                |route| {
-                    list.push(route);
-                },
-                &buf[..len],
-            )?;
-            if rs {
-                break;
+            let len = socket.recv(buf)?;
+            interrupted |= is_dump_interrupted(&buf[..len]);
+            if deserialize_routes(routes, &buf[..len])? {
+                return Ok(interrupted);
             }
         }
-        Ok(list)
     }
 
     /// Lists all current routes.
-    pub fn list(&mut self) -> io::Result<Vec<Route>> {
-        let socket = RouteSocket::new()?;
-
-        // Query IPv4 routes
-        let v4_result = Self::list_family(&socket, AddressFamily::Inet);
+    ///
+    /// A dump the kernel marks as interrupted by concurrent changes is repeated; if it keeps
+    /// being interrupted, an error of kind [`Interrupted`](io::ErrorKind::Interrupted) is
+    /// returned rather than an inconsistent list.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), err(level = "debug"))
+    )]
+    pub fn list(&self) -> io::Result<Vec<Route>> {
+        crate::retry(self.defaults.retry, || self.list_filtered(None))
+    }
+    /// Dumps the routes through `if_index`, letting the kernel filter them where supported.
+    pub(crate) fn list_oif(&self, if_index: u32) -> io::Result<Vec<Route>> {
+        crate::retry(self.defaults.retry, || self.list_filtered(Some(if_index)))
+    }
+    fn list_filtered(&self, oif: Option<u32>) -> io::Result<Vec<Route>> {
+        let socket = self.socket()?;
+        // Dump filters are only honored with strict checking (Linux 4.20+); older kernels
+        // reject the option, and the caller filters the full dump instead
+        let oif = oif.filter(|_| socket.0.set_netlink_get_strict_chk(true).is_ok());
+        let _strict = StrictCheck(&socket, oif.is_some());
 
-        // Query IPv6 routes
-        let v6_result = Self::list_family(&socket, AddressFamily::Inet6);
+        // Both families are parsed straight into one list, sized after the last one
+        let mut routes = Vec::with_capacity(self.list_len.load(Ordering::Relaxed));
+        let buf = &mut *self.bufs.take();
+        let v4_result = Self::list_family(&socket, AddressFamily::Inet, oif, buf, &mut routes);
+        let v6_result = Self::list_family(&socket, AddressFamily::Inet6, oif, buf, &mut routes);
 
         // Only fail if both queries failed. If at least one succeeded, return partial results.
-        let list = match (v4_result, v6_result) {
-            (Ok(v4), Ok(v6)) => [v4, v6].concat(),
-            (Ok(v4), Err(_)) => v4,            // IPv4 succeeded
-            (Err(_), Ok(v6)) => v6,            // IPv6 succeeded
+        match (v4_result, v6_result) {
+            // A family that could not be dumped consistently is not a partial result
+            (Err(e), _) | (_, Err(e)) if e.kind() == io::ErrorKind::Interrupted => return Err(e),
             (Err(e), Err(_)) => return Err(e), // Both failed, return first error
+            _ => {}
+        }
+        if oif.is_none() {
+            self.list_len.store(routes.len(), Ordering::Relaxed);
+        }
+        debug_event!(count = routes.len(), "listed routes");
+        Ok(routes)
+    }
+    /// Returns the number of routes per family, table, protocol and interface.
+    ///
+    /// The routes are counted as the dump is received, without building the list.
+    pub fn stats(&self) -> io::Result<crate::RouteStats> {
+        crate::retry(self.defaults.retry, || self.stats_filtered(None))
+    }
+    /// (Linux only) Returns the statistics of the routes in `table`, letting the kernel filter
+    /// the dump where supported.
+    pub fn table_stats(&self, table: u32) -> io::Result<crate::RouteStats> {
+        crate::retry(self.defaults.retry, || self.stats_filtered(Some(table)))
+    }
+    fn stats_filtered(&self, table: Option<u32>) -> io::Result<crate::RouteStats> {
+        let socket = self.socket()?;
+        // Like the interface filter of list_oif, the table filter needs strict checking
+        let strict = table.is_some() && socket.0.set_netlink_get_strict_chk(true).is_ok();
+        let _strict = StrictCheck(&socket, strict);
+        let buf = &mut *self.bufs.take();
+        let v4_result = Self::stats_family(&socket, AddressFamily::Inet, table, strict, buf);
+        let v6_result = Self::stats_family(&socket, AddressFamily::Inet6, table, strict, buf);
+        match (v4_result, v6_result) {
+            (Err(e), _) | (_, Err(e)) if e.kind() == io::ErrorKind::Interrupted => Err(e),
+            (Ok(mut v4), Ok(v6)) => {
+                v4.merge(v6);
+                Ok(v4)
+            }
+            (Ok(v4), Err(_)) => Ok(v4),
+            (Err(_), Ok(v6)) => Ok(v6),
+            (Err(e), Err(_)) => Err(e),
+        }
+    }
+    /// Counts the routes of a specific address family.
+    fn stats_family(
+        socket: &RouteSocket,
+        family: AddressFamily,
+        table: Option<u32>,
+        strict: bool,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<crate::RouteStats> {
+        let req = list_route_req(family, None, table.filter(|_| strict));
+        for _ in 0..DUMP_ATTEMPTS {
+            let mut stats = crate::RouteStats::default();
+            let mut interrupted = false;
+            socket.send(&req)?;
+            loop {
+                let len = socket.recv(buf)?;
+                interrupted |= is_dump_interrupted(&buf[..len]);
+                let rs = deserialize_msg(
+                    |msg| {
+                        if let RouteNetlinkMessage::NewRoute(msg) = msg {
+                            count_route(&mut stats, &msg, table);
+                        }
+                        Ok(())
+                    },
+                    &buf[..len],
+                )?;
+                if rs {
+                    break;
+                }
+            }
+            if !interrupted {
+                return Ok(stats);
+            }
+            debug_event!(?family, "route dump interrupted, dumping again");
+        }
+        Err(dump_interrupted())
+    }
+    /// Returns the source address the kernel would choose for packets to `dest`, or `None`
+    /// if `dest` is unreachable.
+    ///
+    /// The route is resolved by the kernel (`ip route get`), so policy rules apply.
+    pub fn find_source(&self, dest: &IpAddr) -> io::Result<Option<IpAddr>> {
+        match get_route_reply(&*self.socket()?, host_route_msg(dest.to_canonical())) {
+            Ok(route) => Ok(route.and_then(|v| v.pref_source)),
+            Err(e) if is_unreachable(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+    /// (Linux only) Returns the route the kernel selects for packets to `dest` sent by the
+    /// user `uid`, or `None` if `dest` is unreachable for it.
+    ///
+    /// Unlike [`find_route`](Self::find_route), the lookup is made by the kernel (`ip route
+    /// get <dest> uid <uid> fibmatch`), so policy rules apply, including the `uidrange` rules
+    /// of per-user VPN routing (e.g. on Android); the table of the returned route tells which
+    /// rule matched. Requires Linux 4.13 or later.
+    pub fn find_route_for_uid(&self, dest: &IpAddr, uid: u32) -> io::Result<Option<Route>> {
+        let mut route_msg = host_route_msg(dest.to_canonical());
+        // Without `LookupTable` the reply names the table of the FIB entry, not the one used
+        route_msg.header.flags = RouteFlags::FibMatch | RouteFlags::LookupTable;
+        route_msg.attributes.push(RouteAttribute::Uid(uid));
+        match get_route_reply(&*self.socket()?, route_msg) {
+            Ok(route) => Ok(route),
+            Err(e) if is_unreachable(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+    /// Returns the MTU of the path to `dest`, or `None` if `dest` is unreachable.
+    ///
+    /// This is the MTU the kernel resolves for `dest`: a path MTU learned from ICMP and
+    /// cached for the destination, else the MTU set on the route, else the MTU of the output
+    /// interface.
+    pub fn path_mtu(&self, dest: &IpAddr) -> io::Result<Option<u32>> {
+        let msg = match get_route_msg(&*self.socket()?, host_route_msg(dest.to_canonical())) {
+            Ok(Some(msg)) => msg,
+            Ok(None) => return Ok(None),
+            Err(e) if is_unreachable(&e) => return Ok(None),
+            Err(e) => return Err(e),
         };
-        Ok(convert_add_route(list))
+        let mut oif = None;
+        for attr in &msg.attributes {
+            match attr {
+                RouteAttribute::Metrics(metrics) => {
+                    for metric in metrics {
+                        if let RouteMetric::Mtu(mtu) = metric {
+                            return Ok(Some(*mtu));
+                        }
+                    }
+                }
+                RouteAttribute::Oif(index) => oif = Some(*index),
+                _ => {}
+            }
+        }
+        let Some(oif) = oif else {
+            return Ok(None);
+        };
+        Ok(crate::list_interfaces()?
+            .into_iter()
+            .find(|v| v.index == oif)
+            .map(|v| v.mtu))
+    }
+    /// Returns the installed route to exactly `destination/prefix` in the main table, or the
+    /// default table of the manager, or `None` if there is none. Among several metrics the
+    /// preferred route is returned.
+    pub fn get_route(&self, destination: IpAddr, prefix: u8) -> io::Result<Option<Route>> {
+        let table = self.defaults.table.unwrap_or(libc::RT_TABLE_MAIN);
+        self.get_route_in_table(destination, prefix, table)
+    }
+    /// (Linux only) Returns the installed route to exactly `destination/prefix` in `table`.
+    pub fn get_route_in_table(
+        &self,
+        destination: IpAddr,
+        prefix: u8,
+        table: u8,
+    ) -> io::Result<Option<Route>> {
+        let wanted = Route::new(network_address(destination, prefix), prefix).with_table(table);
+        wanted.check()?;
+        // The kernel answers with the FIB entry used for the network address, which is the
+        // wanted route unless a more specific one shadows it; only then is a dump needed.
+        // The lookup follows the policy rules, so an entry of another table proves nothing
+        if let Ok(found) = fib_match(&*self.socket()?, &wanted) {
+            match found {
+                Some(route) if !route.same_table(&wanted) => {}
+                Some(route) if route.same_destination(&wanted) => return Ok(Some(route)),
+                Some(route) if route.prefix > wanted.prefix => {}
+                _ => return Ok(None),
+            }
+        }
+        let mut list: Vec<Route> = self
+            .list()?
+            .into_iter()
+            .filter(|v| v.same_destination(&wanted) && v.same_table(&wanted))
+            .collect();
+        list.sort_by_key(|v| v.metric.unwrap_or(0));
+        Ok(list.into_iter().next())
     }
     /// Adds a new route.
-    pub fn add(&mut self, route: &Route) -> io::Result<()> {
-        let req = add_route_req(route)?;
-        let socket = RouteSocket::new()?;
-        socket.send(&req)?;
-        let mut buf = vec![0; 4096];
-        let len = socket.recv(&mut buf)?;
-        deserialize_res(|_| {}, &buf[..len]).map(|_| ())
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub fn add(&self, route: &Route) -> io::Result<()> {
+        let route = &*self.defaults.apply(route);
+        add_route_req(route)
+            .and_then(|req| self.send_request(&req))
+            .map_err(crate::context("add", route))
     }
     /// Deletes an existing route.
-    pub fn delete(&mut self, route: &Route) -> io::Result<()> {
-        let req = delete_route_req(route)?;
-        let socket = RouteSocket::new()?;
-        socket.send(&req)?;
-        let mut buf = vec![0; 4096];
-        let len = socket.recv(&mut buf)?;
-        deserialize_res(|_| {}, &buf[..len]).map(|_| ())
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub fn delete(&self, route: &Route) -> io::Result<()> {
+        let route = &*self.defaults.apply(route);
+        delete_route_req(route)
+            .and_then(|req| self.send_request(&req))
+            .map_err(crate::context("delete", route))
+    }
+    /// (Linux only) Deletes the single route matching `route`, failing with `InvalidInput`
+    /// instead of removing an arbitrary one if several match.
+    ///
+    /// Routes match on destination and table, and on metric, gateway, interface and protocol
+    /// where those are set. Fails with `ESRCH` if none matches.
+    pub fn delete_strict(&self, route: &Route) -> io::Result<()> {
+        let route = self.defaults.apply(route);
+        let found = strict_match(&route, self.list()?)?;
+        self.delete(&found)
+    }
+    /// Modifies an existing route in place, without a window in which it is missing.
+    ///
+    /// The route is identified by destination, table and metric on Linux, by destination,
+    /// interface and gateway on Windows, and by destination on BSD; the remaining attributes
+    /// (e.g. the gateway on Linux and BSD, the metric on Windows) are replaced. Fails if no
+    /// such route exists.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub fn update(&self, route: &Route) -> io::Result<()> {
+        let route = &*self.defaults.apply(route);
+        update_route_req(route)
+            .and_then(|req| self.send_request(&req))
+            .map_err(crate::context("update", route))
+    }
+    /// Sends a request that changes a route and waits for its acknowledgement.
+    fn send_request(&self, req: &[u8]) -> io::Result<()> {
+        crate::retry(self.defaults.retry, || self.send_once(req))
+    }
+    fn send_once(&self, req: &[u8]) -> io::Result<()> {
+        debug_event!(len = req.len(), "sending netlink request");
+        let socket = self.socket()?;
+        let reply = NetlinkClient::new(&socket).request(req.to_vec())?;
+        deserialize_res(|_| {}, &reply).map(|_| ())
+    }
+    /// Adds `routes`, with their requests pipelined on one socket, and returns the result for
+    /// each in order. A request that failed is sent again on its own under the retry policy.
+    pub(crate) fn add_all(&self, routes: &[Route]) -> Vec<io::Result<()>> {
+        let routes: Vec<_> = routes.iter().map(|v| self.defaults.apply(v)).collect();
+        let reqs = routes.iter().map(|route| add_route_req(route));
+        let replies = match self.socket() {
+            Ok(socket) => NetlinkClient::new(&socket).request_all(reqs),
+            Err(e) => routes.iter().map(|_| Err(crate::copy_error(&e))).collect(),
+        };
+        replies
+            .into_iter()
+            .zip(&routes)
+            .map(|(reply, route)| {
+                let result = reply.and_then(|reply| deserialize_res(|_| {}, &reply).map(|_| ()));
+                crate::retry_failed(self.defaults.retry, result, || {
+                    add_route_req(route).and_then(|req| self.send_once(&req))
+                })
+                .map_err(crate::context("add", route))
+            })
+            .collect()
+    }
+}
+impl FromRawFd for RouteManager {
+    /// Creates a manager that sends all requests through the `NETLINK_ROUTE` socket `fd`, see
+    /// [`RouteManager::from_socket`].
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        Self::with_parts(Default::default(), Some(RouteSocket::adopt_raw(fd)))
+    }
+}
+/// Turns strict checking off again when a dump on a reused socket is done, as it would also
+/// apply to the lookups sent later.
+struct StrictCheck<'a>(&'a RouteSocket, bool);
+impl Drop for StrictCheck<'_> {
+    fn drop(&mut self) {
+        if self.1 {
+            let _ = self.0 .0.set_netlink_get_strict_chk(false);
+        }
+    }
+}
+/// Finds the only route in `list` matched by `route`, see [`RouteManager::delete_strict`].
+pub(crate) fn strict_match(route: &Route, list: Vec<Route>) -> io::Result<Route> {
+    let route = route.input_form();
+    let if_index = route.get_index();
+    let mut found: Vec<Route> = list
+        .into_iter()
+        .filter(|v| {
+            v.same_destination(&route)
+                && v.same_table(&route)
+                && route.metric.is_none_or(|m| v.metric == Some(m))
+                && route.gateway.is_none_or(|g| v.gateway == Some(g))
+                && if_index.is_none_or(|i| v.if_index == Some(i))
+                && route.protocol.is_none_or(|p| v.protocol == Some(p))
+        })
+        .collect();
+    match found.len() {
+        0 => Err(io::Error::from_raw_os_error(libc::ESRCH)),
+        1 => Ok(found.remove(0)),
+        n => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{n} routes match {route}"),
+        )),
+    }
+}
+/// Size of the buffers netlink messages are received into, enough for the largest datagrams
+/// of netlink dumps; only notifications of huge routes can exceed it.
+pub(crate) const RECV_BUF_LEN: usize = 32 * 1024;
+
+/// Checks that a message of `len` bytes fit into `buf`. Netlink discards the excess of a
+/// larger message, so the buffer is grown for the next one and `InvalidData` returned.
+pub(crate) fn check_truncated(buf: &mut Vec<u8>, len: usize) -> io::Result<usize> {
+    if len <= buf.len() {
+        return Ok(len);
+    }
+    let truncated = buf.len();
+    debug_event!(len, "growing netlink receive buffer");
+    buf.resize(len, 0);
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("netlink message of {len} bytes truncated to {truncated} bytes"),
+    ))
+}
+/// Netlink port ids of the sockets of this process.
+static OWN_PORTS: RwLock<Option<OwnPorts>> = RwLock::new(None);
+/// How long the port of a closed socket is still taken as its own, for the notifications of
+/// its last requests that listeners have yet to read.
+const CLOSED_PORT_GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct OwnPorts {
+    // Number of open sockets on each port
+    open: HashMap<u32, usize>,
+    closed: HashMap<u32, Instant>,
+}
+
+impl OwnPorts {
+    fn contains(&self, port_number: u32) -> bool {
+        self.open.contains_key(&port_number)
+            || self
+                .closed
+                .get(&port_number)
+                .is_some_and(|v| v.elapsed() < CLOSED_PORT_GRACE)
     }
 }
+
+fn register_port(port_number: u32) {
+    let mut ports = OWN_PORTS.write().unwrap();
+    let ports = ports.get_or_insert_with(OwnPorts::default);
+    ports.closed.remove(&port_number);
+    *ports.open.entry(port_number).or_insert(0) += 1;
+}
+
+fn unregister_port(port_number: u32) {
+    let mut ports = OWN_PORTS.write().unwrap();
+    let Some(ports) = ports.as_mut() else {
+        return;
+    };
+    let Some(count) = ports.open.get_mut(&port_number) else {
+        return;
+    };
+    *count -= 1;
+    if *count == 0 {
+        ports.open.remove(&port_number);
+        ports.closed.retain(|_, v| v.elapsed() < CLOSED_PORT_GRACE);
+        ports.closed.insert(port_number, Instant::now());
+    }
+}
+
 pub(crate) fn route_socket() -> io::Result<Socket> {
     let mut socket = Socket::new(NETLINK_ROUTE)?;
-    let _port_number = socket.bind_auto()?.port_number();
+    // Kernels before 4.12 do not explain errors; their errors stay plain
+    let _ = socket.set_ext_ack(true);
+    socket.bind_auto()?;
     socket.connect(&SocketAddr::new(0, 0))?;
     Ok(socket)
 }
-pub(crate) fn convert_add_route(list: Vec<RouteChange>) -> Vec<Route> {
-    list.into_iter()
-        .filter_map(|v| {
-            if let RouteChange::Add(route) = v {
-                Some(route)
-            } else {
-                None
+
+/// Takes over a netlink socket opened elsewhere, e.g. by a privileged parent process.
+pub(crate) fn adopt_socket(socket: OwnedFd) -> io::Result<Socket> {
+    let mut protocol: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let rs = unsafe {
+        libc::getsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PROTOCOL,
+            (&mut protocol as *mut libc::c_int).cast(),
+            &mut len,
+        )
+    };
+    if rs < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if protocol != NETLINK_ROUTE as libc::c_int {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a NETLINK_ROUTE socket",
+        ));
+    }
+    Ok(unsafe { adopt_raw_socket(socket.into_raw_fd()) })
+}
+
+/// Takes over `fd` without checking it.
+pub(crate) unsafe fn adopt_raw_socket(fd: RawFd) -> Socket {
+    let mut socket = Socket::from_raw_fd(fd);
+    let _ = socket.set_ext_ack(true);
+    let mut addr = SocketAddr::new(0, 0);
+    // An unbound socket would be bound by its first send, to a port not recorded here
+    if socket.get_address(&mut addr).is_ok() && addr.port_number() == 0 {
+        let _ = socket.bind_auto();
+    }
+    socket
+}
+
+/// Whether an event was caused by a request sent from this process.
+pub(crate) fn is_own_origin(origin: &EventOrigin) -> bool {
+    match origin.pid {
+        Some(0) | None => false,
+        Some(pid) => {
+            pid == std::process::id()
+                || OWN_PORTS
+                    .read()
+                    .unwrap()
+                    .as_ref()
+                    .is_some_and(|v| v.contains(pid))
+        }
+    }
+}
+/// Parses the routes of a dump reply into `routes`, returning whether the dump is done.
+pub(crate) fn deserialize_routes(
+    routes: &mut Vec<Route>,
+    receive_buffer: &[u8],
+) -> io::Result<bool> {
+    deserialize_msg(
+        |msg| {
+            if let RouteNetlinkMessage::NewRoute(msg) = msg {
+                routes.push(msg.try_into()?);
             }
-        })
-        .collect()
+            Ok(())
+        },
+        receive_buffer,
+    )
 }
 
 pub(crate) fn deserialize_res<F: FnMut(RouteChange)>(
     mut add_fn: F,
     receive_buffer: &[u8],
+) -> io::Result<bool> {
+    deserialize_res_with_origin(|route, _| add_fn(route), receive_buffer)
+}
+
+pub(crate) fn deserialize_res_with_origin<F: FnMut(RouteChange, EventOrigin)>(
+    mut add_fn: F,
+    receive_buffer: &[u8],
+) -> io::Result<bool> {
+    deserialize_msg_with_header(
+        |header, msg| {
+            let mut origin = EventOrigin {
+                pid: Some(header.port_number),
+                seq: Some(header.sequence_number),
+                system_route: false,
+            };
+            match msg {
+                RouteNetlinkMessage::NewRoute(msg) => {
+                    origin.system_route = is_system_protocol(msg.header.protocol);
+                    let route = msg.try_into()?;
+                    if header.flags & NLM_F_REPLACE != 0 {
+                        add_fn(
+                            RouteChange::Change {
+                                old: None,
+                                new: route,
+                            },
+                            origin,
+                        )
+                    } else {
+                        add_fn(RouteChange::Add(route), origin)
+                    }
+                }
+                RouteNetlinkMessage::DelRoute(msg) => {
+                    origin.system_route = is_system_protocol(msg.header.protocol);
+                    add_fn(RouteChange::Delete(msg.try_into()?), origin)
+                }
+                RouteNetlinkMessage::NewLink(msg) | RouteNetlinkMessage::DelLink(msg) => {
+                    crate::InterfaceCache::invalidate_index(msg.header.index)
+                }
+                _ => {}
+            }
+            Ok(())
+        },
+        receive_buffer,
+    )
+}
+
+/// Adds a dumped route to `stats` unless it is outside `table`.
+fn count_route(stats: &mut crate::RouteStats, msg: &RouteMessage, table: Option<u32>) {
+    let ipv4 = match msg.header.address_family {
+        AddressFamily::Inet => true,
+        AddressFamily::Inet6 => false,
+        _ => return,
+    };
+    let mut route_table = msg.header.table as u32;
+    let mut oif = None;
+    for attr in &msg.attributes {
+        match attr {
+            RouteAttribute::Table(v) => route_table = *v,
+            RouteAttribute::Oif(v) => oif = Some(*v),
+            _ => {}
+        }
+    }
+    if table.is_some_and(|v| v != route_table) {
+        return;
+    }
+    stats.count(
+        ipv4,
+        Some(route_table),
+        Some(msg.header.protocol.into()),
+        oif,
+    );
+}
+
+/// Protocols of routes the kernel installs on its own or on behalf of autoconfiguration.
+fn is_system_protocol(protocol: RouteProtocol) -> bool {
+    matches!(
+        protocol,
+        RouteProtocol::Kernel
+            | RouteProtocol::IcmpRedirect
+            | RouteProtocol::Ra
+            | RouteProtocol::Dhcp
+    )
+}
+
+/// Normalized flags from the netlink message `route` was read from.
+pub(crate) fn reported_flags(route: &Route) -> crate::RouteFlags {
+    let mut flags = crate::RouteFlags::empty();
+    let (Some(kind), Some(bits)) = (route.reported.kind, route.reported.flags) else {
+        return flags;
+    };
+    let rtm_flags = RouteFlags::from_bits_retain(bits);
+    let protocol = RouteProtocol::from(route.protocol.unwrap_or(0));
+    flags.set(
+        crate::RouteFlags::UP,
+        !rtm_flags.intersects(RouteFlags::Dead | RouteFlags::Linkdown),
+    );
+    flags.set(crate::RouteFlags::STATIC, !is_system_protocol(protocol));
+    flags.set(
+        crate::RouteFlags::DYNAMIC,
+        protocol == RouteProtocol::IcmpRedirect,
+    );
+    flags.set(
+        crate::RouteFlags::CLONED,
+        rtm_flags.contains(RouteFlags::Cloned),
+    );
+    match RouteType::from(kind) {
+        RouteType::Unreachable | RouteType::Prohibit => flags.insert(crate::RouteFlags::REJECT),
+        RouteType::BlackHole => flags.insert(crate::RouteFlags::BLACKHOLE),
+        _ => {}
+    }
+    flags
+}
+
+pub(crate) fn deserialize_msg<F: FnMut(RouteNetlinkMessage) -> io::Result<()>>(
+    mut msg_fn: F,
+    receive_buffer: &[u8],
+) -> io::Result<bool> {
+    deserialize_msg_with_header(|_, msg| msg_fn(msg), receive_buffer)
+}
+
+fn deserialize_msg_with_header<F: FnMut(&NetlinkHeader, RouteNetlinkMessage) -> io::Result<()>>(
+    mut msg_fn: F,
+    receive_buffer: &[u8],
 ) -> io::Result<bool> {
     let mut offset = 0;
     loop {
@@ -234,15 +1095,12 @@ pub(crate) fn deserialize_res<F: FnMut(RouteChange)>(
                 if e.code.is_none() {
                     return Ok(true);
                 }
-                return Err(e.to_io());
+                debug_event!(code = ?e.code, "netlink error reply");
+                return Err(netlink_error(rx_packet.header.flags, &e));
             }
             NetlinkPayload::Noop => {}
             NetlinkPayload::Overrun(_) => {}
-            NetlinkPayload::InnerMessage(msg) => match msg {
-                RouteNetlinkMessage::NewRoute(msg) => add_fn(RouteChange::Add(msg.try_into()?)),
-                RouteNetlinkMessage::DelRoute(msg) => add_fn(RouteChange::Delete(msg.try_into()?)),
-                _ => {}
-            },
+            NetlinkPayload::InnerMessage(msg) => msg_fn(&rx_packet.header, msg)?,
             _ => {}
         }
 
@@ -252,6 +1110,185 @@ pub(crate) fn deserialize_res<F: FnMut(RouteChange)>(
         }
     }
 }
+/// Passes the route messages in `receive_buffer` to `ref_fn` without deserializing them and
+/// returns their number.
+fn deserialize_route_refs<F: FnMut(crate::RouteRef<'_>)>(
+    mut ref_fn: F,
+    receive_buffer: &[u8],
+) -> io::Result<usize> {
+    let header_len = std::mem::size_of::<libc::nlmsghdr>();
+    let mut count = 0;
+    let mut offset = 0;
+    while offset + header_len <= receive_buffer.len() {
+        let bytes = &receive_buffer[offset..];
+        let len = u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+        if len < header_len || len > bytes.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("netlink message of {len} bytes, {} available", bytes.len()),
+            ));
+        }
+        let msg = &bytes[..len];
+        offset += (len + NLA_ALIGNTO - 1) & !(NLA_ALIGNTO - 1);
+        let kind = u16::from_ne_bytes([msg[4], msg[5]]);
+        let flags = u16::from_ne_bytes([msg[6], msg[7]]);
+        match kind {
+            libc::RTM_NEWROUTE | libc::RTM_DELROUTE => {
+                if let Some(route) = route_ref(msg, kind, flags) {
+                    ref_fn(route);
+                    count += 1;
+                }
+            }
+            libc::RTM_NEWLINK | libc::RTM_DELLINK => {
+                // ifi_index follows ifi_family, a pad byte and ifi_type
+                if let Some(index) = msg.get(header_len + 4..header_len + 8) {
+                    let index = i32::from_ne_bytes(index.try_into().unwrap());
+                    crate::InterfaceCache::invalidate_index(index as u32);
+                }
+            }
+            NLMSG_ERROR => {
+                deserialize_msg(|_| Ok(()), msg)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(count)
+}
+
+/// Size of `struct rtmsg`, the header of route messages.
+const RTMSG_LEN: usize = 12;
+/// Nexthop in another address family; missing from the musl bindings of libc.
+const RTA_VIA: u16 = 18;
+
+/// Reads the fields of a `RTM_NEWROUTE`/`RTM_DELROUTE` message that [`crate::RouteRef`]
+/// exposes, or `None` if it is not an IPv4 or IPv6 route.
+fn route_ref(msg: &[u8], kind: u16, flags: u16) -> Option<crate::RouteRef<'_>> {
+    let header_len = std::mem::size_of::<libc::nlmsghdr>();
+    let rtmsg = msg.get(header_len..header_len + RTMSG_LEN)?;
+    let family = rtmsg[0] as i32;
+    let ip = |bytes: &[u8]| match (family, bytes.len()) {
+        (libc::AF_INET, 4) => Some(IpAddr::from(<[u8; 4]>::try_from(bytes).ok()?)),
+        (libc::AF_INET6, 16) => Some(IpAddr::from(<[u8; 16]>::try_from(bytes).ok()?)),
+        _ => None,
+    };
+    let mut destination = match family {
+        libc::AF_INET => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        libc::AF_INET6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        _ => return None,
+    };
+    let mut gateway = None;
+    let mut if_index = None;
+    let mut table = rtmsg[4] as u32;
+    let attrs = &msg[header_len + rtmsg.len()..];
+    for nla in NlasIterator::new(attrs) {
+        let Ok(nla) = nla else {
+            break;
+        };
+        let value = nla.value();
+        match nla.kind() & NLA_TYPE_MASK {
+            libc::RTA_DST => destination = ip(value)?,
+            libc::RTA_GATEWAY => gateway = ip(value),
+            libc::RTA_OIF => if_index = value.try_into().ok().map(u32::from_ne_bytes),
+            libc::RTA_TABLE => {
+                table = value
+                    .try_into()
+                    .ok()
+                    .map(u32::from_ne_bytes)
+                    .unwrap_or(table)
+            }
+            // rtvia: a family and an address of that family
+            RTA_VIA if value.len() > 2 => {
+                let addr = &value[2..];
+                gateway = match (u16::from_ne_bytes([value[0], value[1]]) as i32, addr.len()) {
+                    (libc::AF_INET, 4) => <[u8; 4]>::try_from(addr).ok().map(IpAddr::from),
+                    (libc::AF_INET6, 16) => <[u8; 16]>::try_from(addr).ok().map(IpAddr::from),
+                    _ => gateway,
+                };
+            }
+            _ => {}
+        }
+    }
+    let kind = match kind {
+        libc::RTM_DELROUTE => crate::RouteChangeKind::Delete,
+        _ if flags & NLM_F_REPLACE != 0 => crate::RouteChangeKind::Change,
+        _ => crate::RouteChangeKind::Add,
+    };
+    Some(crate::RouteRef {
+        kind,
+        destination,
+        prefix: rtmsg[1],
+        gateway,
+        if_index,
+        table,
+        msg,
+    })
+}
+
+/// Parses a single route message, see [`crate::RouteRef::to_owned`].
+pub(crate) fn parse_route_msg(msg: &[u8]) -> io::Result<Route> {
+    let mut found = None;
+    deserialize_res(|change| found = Some(change), msg)?;
+    match found {
+        Some(RouteChange::Add(route) | RouteChange::Delete(route)) => Ok(route),
+        Some(RouteChange::Change { new, .. }) => Ok(new),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a route message",
+        )),
+    }
+}
+
+/// Converts an error reply to an error, attaching the extended acknowledgement if the kernel
+/// sent one.
+fn netlink_error(flags: u16, e: &ErrorMessage) -> io::Error {
+    const NLMSGERR_ATTR_MSG: u16 = 1;
+    const NLMSGERR_ATTR_OFFS: u16 = 2;
+    if flags & NLM_F_ACK_TLVS == 0 {
+        return e.to_io();
+    }
+    // The payload repeats the request, only its header if capped, followed by the TLVs
+    let request_len = if flags & NLM_F_CAPPED != 0 {
+        std::mem::size_of::<libc::nlmsghdr>()
+    } else {
+        e.header
+            .get(..4)
+            .map_or(0, |v| u32::from_ne_bytes([v[0], v[1], v[2], v[3]]) as usize)
+    };
+    let tlvs = e
+        .header
+        .get(request_len.next_multiple_of(NLA_ALIGNTO)..)
+        .unwrap_or_default();
+    let mut ext_ack = crate::ExtAckError {
+        code: e.raw_code().abs(),
+        message: None,
+        offset: None,
+    };
+    for nla in NlasIterator::new(tlvs).flatten() {
+        let value = nla.value();
+        match nla.kind() & NLA_TYPE_MASK {
+            NLMSGERR_ATTR_MSG => {
+                let message = value.split(|v| *v == 0).next().unwrap_or_default();
+                ext_ack.message = Some(String::from_utf8_lossy(message).into_owned());
+            }
+            NLMSGERR_ATTR_OFFS => {
+                ext_ack.offset = <[u8; 4]>::try_from(value).ok().map(u32::from_ne_bytes);
+            }
+            _ => {}
+        }
+    }
+    if ext_ack.message.is_none() && ext_ack.offset.is_none() {
+        return e.to_io();
+    }
+    debug_event!(error = %ext_ack, "netlink extended acknowledgement");
+    ext_ack.into_io()
+}
+
+/// Converts a `clock_t` value of the kernel, in `USER_HZ` ticks, to a duration.
+fn clock_t_to_duration(ticks: u32) -> std::time::Duration {
+    let hz = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    let hz = if hz > 0 { hz as u64 } else { 100 };
+    std::time::Duration::from_millis(ticks as u64 * 1000 / hz)
+}
 
 impl TryFrom<RouteMessage> for Route {
     type Error = io::Error;
@@ -266,12 +1303,31 @@ impl TryFrom<RouteMessage> for Route {
         let mut if_index = None;
         let mut metric = None;
         let mut pref_source = None;
+        let mut raw_attrs = Vec::new();
+        let mut cache_info: Option<Box<crate::RouteCacheInfo>> = None;
         for x in msg.attributes {
             match x {
                 RouteAttribute::Metrics(_) => {}
-                RouteAttribute::MfcStats(_) => {}
+                RouteAttribute::MfcStats(stats) => {
+                    let info = cache_info.get_or_insert_with(Default::default);
+                    info.packets = Some(stats.packets);
+                    info.bytes = Some(stats.bytes);
+                    info.wrong_if = Some(stats.wrong_if);
+                }
                 RouteAttribute::MultiPath(_) => {}
-                RouteAttribute::CacheInfo(_) => {}
+                RouteAttribute::CacheInfo(ci) => {
+                    let info = cache_info.get_or_insert_with(Default::default);
+                    info.users = ci.clntref;
+                    info.last_use = clock_t_to_duration(ci.last_use);
+                    info.expires = Some(ci.expires)
+                        .filter(|v| *v != 0)
+                        .map(clock_t_to_duration);
+                    info.error = ci.error;
+                    info.used = ci.used;
+                    info.id = ci.id;
+                    info.ts = ci.ts;
+                    info.ts_age = ci.ts_age;
+                }
                 RouteAttribute::Destination(addr) => {
                     destination = route_address_to_ip(addr);
                 }
@@ -284,7 +1340,10 @@ impl TryFrom<RouteMessage> for Route {
                 RouteAttribute::PrefSource(addr) => {
                     pref_source = route_address_to_ip(addr);
                 }
-                RouteAttribute::Via(_) => {}
+                // Nexthop in another address family (RFC 5549), e.g. an IPv6 gateway of an
+                // IPv4 route
+                RouteAttribute::Via(RouteVia::Inet(addr)) => gateway = Some(IpAddr::V4(addr)),
+                RouteAttribute::Via(RouteVia::Inet6(addr)) => gateway = Some(IpAddr::V6(addr)),
                 RouteAttribute::NewDestination(_) => {}
                 RouteAttribute::Preference(_) => {}
                 RouteAttribute::EncapType(_) => {}
@@ -301,7 +1360,11 @@ impl TryFrom<RouteMessage> for Route {
                 RouteAttribute::Realm(_) => {}
                 RouteAttribute::Table(_) => {}
                 RouteAttribute::Mark(_) => {}
-                RouteAttribute::Other(_) => {}
+                RouteAttribute::Other(nla) => {
+                    let mut value = vec![0; nla.value_len()];
+                    nla.emit_value(&mut value);
+                    raw_attrs.push(RawAttribute::new(nla.kind(), value));
+                }
                 _ => {}
             }
         }
@@ -319,16 +1382,24 @@ impl TryFrom<RouteMessage> for Route {
                 }
             }
         };
-        let mut route = Route::new(destination, prefix).with_table(table);
+        let mut route = Route::new(destination, prefix)
+            .with_table(table)
+            .with_protocol(msg.header.protocol.into());
         if let Some(source) = source {
             route = route.with_source(source, source_prefix);
         }
         if let Some(if_index) = if_index {
             route = route.with_if_index(if_index);
-            route.if_name = crate::unix::if_index_to_name(if_index).ok();
+            route.if_name = crate::route_if_name(if_index);
         }
         if let Some(gateway) = gateway {
             route = route.with_gateway(gateway);
+            // Linux scopes link-local gateways by the output interface
+            if let IpAddr::V6(v6) = gateway {
+                if v6.is_unicast_link_local() {
+                    route.gateway_scope_id = if_index;
+                }
+            }
         }
         if let Some(metric) = metric {
             route = route.with_metric(metric);
@@ -336,12 +1407,20 @@ impl TryFrom<RouteMessage> for Route {
         if let Some(pref_source) = pref_source {
             route = route.with_pref_source(pref_source);
         }
+        route.raw_attrs = raw_attrs;
+        route.reported = Reported {
+            scope: Some(msg.header.scope.into()),
+            kind: Some(msg.header.kind.into()),
+            flags: Some(msg.header.flags.bits()),
+            cache_info,
+        };
         Ok(route)
     }
 }
 impl TryFrom<&Route> for RouteMessage {
     type Error = io::Error;
     fn try_from(route: &Route) -> Result<Self, Self::Error> {
+        let route = &*route.input_form();
         route.check()?;
         let mut route_msg = RouteMessage::default();
         route_msg.header.address_family = if route.destination.is_ipv4() {
@@ -350,7 +1429,9 @@ impl TryFrom<&Route> for RouteMessage {
             AddressFamily::Inet6
         };
         route_msg.header.destination_prefix_length = route.prefix;
-        route_msg.header.protocol = RouteProtocol::Static;
+        route_msg.header.protocol = route
+            .protocol
+            .map_or(RouteProtocol::Static, RouteProtocol::from);
         route_msg.header.scope = RouteScope::Universe;
         route_msg.header.kind = RouteType::Unicast;
         route_msg.header.table = route.table;
@@ -358,11 +1439,14 @@ impl TryFrom<&Route> for RouteMessage {
             .attributes
             .push(RouteAttribute::Destination(route.destination.into()));
         if let Some(gateway) = route.gateway {
-            route_msg
-                .attributes
-                .push(RouteAttribute::Gateway(gateway.into()));
+            let attr = if gateway.is_ipv4() == route.destination.is_ipv4() {
+                RouteAttribute::Gateway(gateway.into())
+            } else {
+                RouteAttribute::Via(gateway.into())
+            };
+            route_msg.attributes.push(attr);
         }
-        if let Some(if_index) = route.get_index() {
+        if let Some(if_index) = route.get_index().or(route.gateway_scope_id) {
             route_msg.attributes.push(RouteAttribute::Oif(if_index));
         }
         if let Some(metric) = route.metric {
@@ -379,17 +1463,131 @@ impl TryFrom<&Route> for RouteMessage {
                 .attributes
                 .push(RouteAttribute::PrefSource(pref_source.into()));
         }
+        for attr in &route.raw_attrs {
+            route_msg
+                .attributes
+                .push(RouteAttribute::Other(DefaultNla::new(
+                    attr.kind(),
+                    attr.value().to_vec(),
+                )));
+        }
 
         Ok(route_msg)
     }
 }
 
-pub(crate) fn list_route_req(family: AddressFamily) -> Vec<u8> {
+/// Looks up the FIB entry that `route`'s table uses for its network address.
+fn fib_match(socket: &RouteSocket, route: &Route) -> io::Result<Option<Route>> {
+    let mut route_msg = host_route_msg(route.destination);
+    route_msg.header.table = route.table;
+    route_msg.header.flags = RouteFlags::FibMatch;
+    route_msg
+        .attributes
+        .push(RouteAttribute::Table(route.table as u32));
+    get_route_reply(socket, route_msg)
+}
+
+/// A `RTM_GETROUTE` request for the host address `dest`.
+fn host_route_msg(dest: IpAddr) -> RouteMessage {
+    let mut route_msg = RouteMessage::default();
+    if dest.is_ipv4() {
+        route_msg.header.address_family = AddressFamily::Inet;
+        route_msg.header.destination_prefix_length = 32;
+    } else {
+        route_msg.header.address_family = AddressFamily::Inet6;
+        route_msg.header.destination_prefix_length = 128;
+    }
+    route_msg
+        .attributes
+        .push(RouteAttribute::Destination(dest.into()));
+    route_msg
+}
+
+/// Sends a `RTM_GETROUTE` request and returns the route of the reply.
+fn get_route_reply(socket: &RouteSocket, route_msg: RouteMessage) -> io::Result<Option<Route>> {
+    get_route_msg(socket, route_msg)?
+        .map(Route::try_from)
+        .transpose()
+}
+
+/// Sends a `RTM_GETROUTE` request and returns the reply.
+fn get_route_msg(
+    socket: &RouteSocket,
+    route_msg: RouteMessage,
+) -> io::Result<Option<RouteMessage>> {
+    let mut nl_hdr = NetlinkHeader::default();
+    nl_hdr.flags = NLM_F_REQUEST;
+    let mut packet = NetlinkMessage::new(
+        nl_hdr,
+        NetlinkPayload::from(RouteNetlinkMessage::GetRoute(route_msg)),
+    );
+    packet.finalize();
+    let mut req = vec![0; packet.header.length as usize];
+    packet.serialize(&mut req[..]);
+
+    let reply = NetlinkClient::new(socket).request(req)?;
+    let mut found = None;
+    deserialize_msg_with_header(
+        |_, msg| {
+            if let RouteNetlinkMessage::NewRoute(msg) = msg {
+                found = Some(msg);
+            }
+            Ok(())
+        },
+        &reply,
+    )?;
+    Ok(found)
+}
+
+fn is_unreachable(e: &io::Error) -> bool {
+    matches!(
+        crate::os_error(e),
+        Some(libc::ENETUNREACH | libc::EHOSTUNREACH)
+    )
+}
+
+/// Attempts of a dump before giving up on a table that keeps changing.
+pub(crate) const DUMP_ATTEMPTS: u32 = 5;
+
+/// Whether the kernel marked any message in `receive_buffer` with `NLM_F_DUMP_INTR`, i.e. the
+/// table changed while it was being dumped and the dump may be inconsistent.
+pub(crate) fn is_dump_interrupted(receive_buffer: &[u8]) -> bool {
+    let mut offset = 0;
+    while let Ok(packet) = NetlinkBuffer::new_checked(&receive_buffer[offset..]) {
+        if packet.flags() & NLM_F_DUMP_INTR != 0 {
+            return true;
+        }
+        if packet.length() == 0 {
+            break;
+        }
+        offset += packet.length() as usize;
+    }
+    false
+}
+
+pub(crate) fn dump_interrupted() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Interrupted,
+        "route dump kept being interrupted by concurrent changes",
+    )
+}
+
+pub(crate) fn list_route_req(
+    family: AddressFamily,
+    oif: Option<u32>,
+    table: Option<u32>,
+) -> Vec<u8> {
     let mut nl_hdr = NetlinkHeader::default();
     nl_hdr.flags = NLM_F_REQUEST | NLM_F_DUMP;
 
     let mut route_msg = RouteMessage::default();
     route_msg.header.address_family = family;
+    if let Some(oif) = oif {
+        route_msg.attributes.push(RouteAttribute::Oif(oif));
+    }
+    if let Some(table) = table {
+        route_msg.attributes.push(RouteAttribute::Table(table));
+    }
 
     let mut packet = NetlinkMessage::new(
         nl_hdr,
@@ -419,14 +1617,39 @@ pub(crate) fn add_route_req(route: &Route) -> io::Result<Vec<u8>> {
     Ok(buf)
 }
 
+pub(crate) fn update_route_req(route: &Route) -> io::Result<Vec<u8>> {
+    let mut nl_hdr = NetlinkHeader::default();
+    // REPLACE without CREATE fails with ENOENT instead of adding a missing route
+    nl_hdr.flags = NLM_F_REQUEST | NLM_F_REPLACE | NLM_F_ACK;
+
+    let mut packet = NetlinkMessage::new(
+        nl_hdr,
+        NetlinkPayload::from(RouteNetlinkMessage::NewRoute(route.try_into()?)),
+    );
+
+    packet.finalize();
+
+    let mut buf = vec![0; packet.header.length as usize];
+    packet.serialize(&mut buf[..]);
+    Ok(buf)
+}
+
 pub(crate) fn delete_route_req(route: &Route) -> io::Result<Vec<u8>> {
     let mut nl_hdr = NetlinkHeader::default();
     nl_hdr.message_type = RTM_DELROUTE;
     nl_hdr.flags = NLM_F_REQUEST | NLM_F_ACK;
 
+    // Unset fields are wildcards: any protocol, scope and type, like `ip route del`.
+    // Metric, table, oif and gateway narrow the match when set.
+    let mut route_msg: RouteMessage = route.try_into()?;
+    route_msg.header.protocol = route
+        .protocol
+        .map_or(RouteProtocol::Unspec, RouteProtocol::from);
+    route_msg.header.scope = RouteScope::NoWhere;
+    route_msg.header.kind = RouteType::Unspec;
     let mut packet = NetlinkMessage::new(
         nl_hdr,
-        NetlinkPayload::from(RouteNetlinkMessage::DelRoute(route.try_into()?)),
+        NetlinkPayload::from(RouteNetlinkMessage::DelRoute(route_msg)),
     );
 
     packet.finalize();
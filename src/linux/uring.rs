@@ -0,0 +1,485 @@
+use crate::linux::{check_truncated, RouteSocket, RECV_BUF_LEN};
+use crate::{AsyncRoute, AsyncRouteIo};
+use std::collections::HashMap;
+use std::future::{poll_fn, Future};
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::pin::pin;
+use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// Entries of the submission queue; the completion queue has twice as many.
+const ENTRIES: u32 = 64;
+
+const IORING_OFF_SQ_RING: libc::off_t = 0;
+const IORING_OFF_CQ_RING: libc::off_t = 0x8000000;
+const IORING_OFF_SQES: libc::off_t = 0x10000000;
+const IORING_OP_ASYNC_CANCEL: u8 = 14;
+const IORING_OP_RECV: u8 = 27;
+const IORING_ENTER_GETEVENTS: libc::c_uint = 1;
+const IORING_REGISTER_EVENTFD: libc::c_uint = 4;
+/// Set in the user data of cancellations, whose completions are not waited for.
+const CANCEL: u64 = 1 << 63;
+
+#[repr(C)]
+#[derive(Default)]
+struct SqRingOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    flags: u32,
+    dropped: u32,
+    array: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct CqRingOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    overflow: u32,
+    cqes: u32,
+    flags: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct Params {
+    sq_entries: u32,
+    cq_entries: u32,
+    flags: u32,
+    sq_thread_cpu: u32,
+    sq_thread_idle: u32,
+    features: u32,
+    wq_fd: u32,
+    resv: [u32; 3],
+    sq_off: SqRingOffsets,
+    cq_off: CqRingOffsets,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct Sqe {
+    opcode: u8,
+    flags: u8,
+    ioprio: u16,
+    fd: i32,
+    off: u64,
+    addr: u64,
+    len: u32,
+    op_flags: u32,
+    user_data: u64,
+    buf_index: u16,
+    personality: u16,
+    splice_fd_in: i32,
+    addr3: u64,
+    pad: u64,
+}
+
+#[repr(C)]
+struct Cqe {
+    user_data: u64,
+    res: i32,
+    flags: u32,
+}
+
+/// A region of the ring mapped into the process.
+struct Mmap {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+impl Mmap {
+    fn new(fd: RawFd, len: usize, offset: libc::off_t) -> io::Result<Self> {
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_POPULATE,
+                fd,
+                offset,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { ptr, len })
+    }
+    fn at<T>(&self, offset: u32) -> *mut T {
+        unsafe { self.ptr.cast::<u8>().add(offset as usize).cast() }
+    }
+    fn atomic(&self, offset: u32) -> &AtomicU32 {
+        unsafe { &*self.at::<AtomicU32>(offset) }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr, self.len) };
+    }
+}
+
+/// The queues of an io_uring, used under the lock of its [`Uring`].
+struct Ring {
+    params: Params,
+    sq: Mmap,
+    cq: Mmap,
+    sqes: Mmap,
+    fd: OwnedFd,
+}
+
+unsafe impl Send for Ring {}
+
+impl Ring {
+    fn new() -> io::Result<Self> {
+        let mut params = Params::default();
+        let fd = unsafe { libc::syscall(libc::SYS_io_uring_setup, ENTRIES, &mut params) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd as RawFd) };
+        let sq_len = params.sq_off.array as usize + params.sq_entries as usize * 4;
+        let cq_len =
+            params.cq_off.cqes as usize + params.cq_entries as usize * std::mem::size_of::<Cqe>();
+        let sqes_len = params.sq_entries as usize * std::mem::size_of::<Sqe>();
+        Ok(Self {
+            sq: Mmap::new(fd.as_raw_fd(), sq_len, IORING_OFF_SQ_RING)?,
+            cq: Mmap::new(fd.as_raw_fd(), cq_len, IORING_OFF_CQ_RING)?,
+            sqes: Mmap::new(fd.as_raw_fd(), sqes_len, IORING_OFF_SQES)?,
+            params,
+            fd,
+        })
+    }
+    fn enter(&self, to_submit: u32, min_complete: u32, flags: libc::c_uint) -> io::Result<()> {
+        loop {
+            let res = unsafe {
+                libc::syscall(
+                    libc::SYS_io_uring_enter,
+                    self.fd.as_raw_fd(),
+                    to_submit,
+                    min_complete,
+                    flags,
+                    ptr::null::<libc::sigset_t>(),
+                    0usize,
+                )
+            };
+            if res >= 0 {
+                return Ok(());
+            }
+            let e = io::Error::last_os_error();
+            if e.kind() != io::ErrorKind::Interrupted {
+                return Err(e);
+            }
+        }
+    }
+    /// Queues `sqe` and submits it. If that fails the entry is taken back, so that the kernel
+    /// never uses the memory it refers to.
+    fn submit(&mut self, sqe: Sqe) -> io::Result<()> {
+        let off = &self.params.sq_off;
+        let head = self.sq.atomic(off.head).load(Ordering::Acquire);
+        let tail = self.sq.atomic(off.tail).load(Ordering::Relaxed);
+        if tail.wrapping_sub(head) == self.params.sq_entries {
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "io_uring submission queue full",
+            ));
+        }
+        let index = tail & unsafe { *self.sq.at::<u32>(off.ring_mask) };
+        unsafe {
+            self.sqes.at::<Sqe>(0).add(index as usize).write(sqe);
+            self.sq
+                .at::<u32>(off.array)
+                .add(index as usize)
+                .write(index);
+        }
+        let next = tail.wrapping_add(1);
+        self.sq.atomic(off.tail).store(next, Ordering::Release);
+        let res = self.enter(next.wrapping_sub(head), 0, 0);
+        // The kernel only fails a submission that consumed no entry
+        if res.is_err() && self.sq.atomic(off.head).load(Ordering::Acquire) != next {
+            self.sq.atomic(off.tail).store(tail, Ordering::Release);
+        }
+        res
+    }
+    /// Takes the next completion, if any.
+    fn pop(&mut self) -> Option<Cqe> {
+        let off = &self.params.cq_off;
+        let head = self.cq.atomic(off.head).load(Ordering::Relaxed);
+        if head == self.cq.atomic(off.tail).load(Ordering::Acquire) {
+            return None;
+        }
+        let index = head & unsafe { *self.cq.at::<u32>(off.ring_mask) };
+        let cqe = unsafe { self.cq.at::<Cqe>(off.cqes).add(index as usize).read() };
+        self.cq
+            .atomic(off.head)
+            .store(head.wrapping_add(1), Ordering::Release);
+        Some(cqe)
+    }
+}
+
+/// An operation submitted to the ring and not yet taken by its caller.
+struct Op {
+    // The kernel may write into it until the operation completes
+    buf: Vec<u8>,
+    result: Option<i32>,
+    waker: Option<Waker>,
+    abandoned: bool,
+}
+
+struct State {
+    ring: Ring,
+    ops: HashMap<u64, Op>,
+    next_id: u64,
+}
+
+impl State {
+    /// Files the completions under their operations and wakes the tasks waiting for them.
+    fn reap(&mut self) {
+        while let Some(cqe) = self.ring.pop() {
+            if cqe.user_data & CANCEL != 0 {
+                continue;
+            }
+            let Some(op) = self.ops.get_mut(&cqe.user_data) else {
+                continue;
+            };
+            if op.abandoned {
+                self.ops.remove(&cqe.user_data);
+                continue;
+            }
+            op.result = Some(cqe.res);
+            if let Some(waker) = op.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+    fn cancel(&mut self, id: u64) -> io::Result<()> {
+        self.ring.submit(Sqe {
+            opcode: IORING_OP_ASYNC_CANCEL,
+            fd: -1,
+            addr: id,
+            user_data: id | CANCEL,
+            ..Default::default()
+        })
+    }
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        // The buffers must outlive the receives writing into them
+        let pending: Vec<u64> = self
+            .ops
+            .iter()
+            .filter(|(_, op)| op.result.is_none())
+            .map(|(id, _)| *id)
+            .collect();
+        for id in pending {
+            let _ = self.cancel(id);
+        }
+        while self.ops.values().any(|op| op.result.is_none()) {
+            if self.ring.enter(0, 1, IORING_ENTER_GETEVENTS).is_err() {
+                std::mem::forget(std::mem::take(&mut self.ops));
+                return;
+            }
+            while let Some(cqe) = self.ring.pop() {
+                if let Some(op) = self.ops.get_mut(&cqe.user_data) {
+                    op.result = Some(cqe.res);
+                }
+            }
+        }
+    }
+}
+
+/// An io_uring on which netlink receives complete, shared by the sockets of an async listener
+/// or manager.
+///
+/// Completions signal an eventfd, which is polled for readiness on the runtime; one wakeup
+/// covers all completions that arrived meanwhile, however many sockets they are for.
+pub(crate) struct Uring<Io> {
+    state: Mutex<State>,
+    event: AsyncRoute<OwnedFd, Io>,
+}
+
+impl<Io: AsyncRouteIo> Uring<Io> {
+    pub(crate) fn new() -> io::Result<Self> {
+        let ring = Ring::new()?;
+        let event = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+        if event < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let event = unsafe { OwnedFd::from_raw_fd(event) };
+        let event_fd = event.as_raw_fd();
+        let res = unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_register,
+                ring.fd.as_raw_fd(),
+                IORING_REGISTER_EVENTFD,
+                &event_fd,
+                1,
+            )
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            state: Mutex::new(State {
+                ring,
+                ops: HashMap::new(),
+                next_id: 0,
+            }),
+            event: AsyncRoute::new(event)?,
+        })
+    }
+    /// Starts receiving from `fd` into `buf`, with the flags of `recv(2)`, and returns the id
+    /// of the receive, which then owns the buffer. On failure `buf` is left untouched.
+    fn recv(&self, fd: RawFd, buf: &mut Vec<u8>, flags: libc::c_int) -> io::Result<u64> {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        let sqe = Sqe {
+            opcode: IORING_OP_RECV,
+            fd,
+            addr: buf.as_mut_ptr() as u64,
+            len: buf.len() as u32,
+            op_flags: flags as u32,
+            user_data: id,
+            ..Default::default()
+        };
+        state.ring.submit(sqe)?;
+        let op = Op {
+            buf: std::mem::take(buf),
+            result: None,
+            waker: None,
+            abandoned: false,
+        };
+        state.ops.insert(id, op);
+        Ok(id)
+    }
+    /// Waits for the operation `id` to complete and returns its result and buffer.
+    async fn complete(&self, id: u64) -> io::Result<(i32, Vec<u8>)> {
+        loop {
+            // Completes once the eventfd signalled completions, after clearing it
+            let mut signalled = pin!(self.event.read_with_ref(|event| {
+                let mut count = 0u64;
+                let res = unsafe {
+                    libc::read(
+                        event.as_raw_fd(),
+                        (&mut count as *mut u64).cast(),
+                        std::mem::size_of::<u64>(),
+                    )
+                };
+                if res < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            }));
+            let done = poll_fn(|cx| match self.take(id, cx) {
+                Ok(None) => signalled.as_mut().poll(cx).map_ok(|()| None),
+                rs => Poll::Ready(rs),
+            })
+            .await?;
+            if let Some(done) = done {
+                return Ok(done);
+            }
+        }
+    }
+    /// Takes the result and buffer of the operation `id` if it completed, or else wakes the
+    /// current task when it does.
+    fn take(&self, id: u64, cx: &mut Context<'_>) -> io::Result<Option<(i32, Vec<u8>)>> {
+        let mut state = self.state.lock().unwrap();
+        state.reap();
+        let Some(op) = state.ops.get_mut(&id) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("no io_uring operation {id} pending"),
+            ));
+        };
+        let Some(result) = op.result else {
+            op.waker = Some(cx.waker().clone());
+            return Ok(None);
+        };
+        let buf = std::mem::take(&mut op.buf);
+        state.ops.remove(&id);
+        Ok(Some((result, buf)))
+    }
+}
+
+impl<Io> Uring<Io> {
+    /// Cancels the operation `id`, whose result is then dropped when it completes.
+    fn abandon(&self, id: u64) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(op) = state.ops.get_mut(&id) {
+            op.waker = None;
+            if op.result.is_some() {
+                state.ops.remove(&id);
+            } else {
+                op.abandoned = true;
+                let _ = state.cancel(id);
+            }
+        }
+    }
+}
+
+/// A netlink socket whose receives complete on a [`Uring`] instead of being retried on
+/// readiness.
+pub(crate) struct UringSocket<Io> {
+    socket: RouteSocket,
+    uring: Arc<Uring<Io>>,
+    // A receive left in flight by a cancelled call, taken over by the next
+    pending: Option<u64>,
+}
+
+impl<Io: AsyncRouteIo> UringSocket<Io> {
+    pub(crate) fn new(socket: RouteSocket, uring: Arc<Uring<Io>>) -> Self {
+        Self {
+            socket,
+            uring,
+            pending: None,
+        }
+    }
+    pub(crate) fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        // Netlink requests are handled by the kernel as they are sent and never block
+        self.socket.send(buf)
+    }
+    /// Receives one message into `buf`. If the call is cancelled, the next one takes over its
+    /// receive, so that no message is lost.
+    pub(crate) async fn recv(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let id = match self.pending {
+            Some(id) => id,
+            None => {
+                if buf.len() < RECV_BUF_LEN {
+                    buf.resize(RECV_BUF_LEN, 0);
+                }
+                let id = self
+                    .uring
+                    .recv(self.socket.as_raw_fd(), buf, libc::MSG_TRUNC)?;
+                self.pending = Some(id);
+                id
+            }
+        };
+        let (res, received) = self.uring.complete(id).await?;
+        self.pending = None;
+        *buf = received;
+        if res < 0 {
+            return Err(io::Error::from_raw_os_error(-res));
+        }
+        check_truncated(buf, res as usize)
+    }
+}
+
+impl<Io> Drop for UringSocket<Io> {
+    fn drop(&mut self) {
+        if let Some(id) = self.pending {
+            self.uring.abandon(id);
+        }
+    }
+}
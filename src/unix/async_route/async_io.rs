@@ -1,23 +1,33 @@
-use async_io::Async;
+use crate::AsyncRouteIo;
+use async_io::{Async, Timer};
+use std::future::Future;
 use std::io;
-use std::os::fd::{AsFd, AsRawFd};
+use std::os::fd::{BorrowedFd, RawFd};
+use std::time::Duration;
 
-pub struct AsyncRoute<T> {
-    fd: Async<T>,
+/// (Unix only) The [`AsyncRouteIo`] of async-io, used with the `async_io` feature.
+pub struct AsyncIoRouteIo {
+    fd: Async<BorrowedFd<'static>>,
 }
-impl<T: AsRawFd + AsFd> AsyncRoute<T> {
-    pub fn new(fd: T) -> io::Result<Self> {
-        Ok(Self {
-            fd: Async::new(fd)?,
+impl AsyncRouteIo for AsyncIoRouteIo {
+    fn new(fd: RawFd) -> io::Result<Self> {
+        Ok(AsyncIoRouteIo {
+            fd: Async::new(unsafe { BorrowedFd::borrow_raw(fd) })?,
         })
     }
-    pub async fn read_with<R>(&mut self, op: impl FnMut(&mut T) -> io::Result<R>) -> io::Result<R> {
-        unsafe { self.fd.read_with_mut(op).await }
+    fn read_with<R>(
+        &self,
+        mut op: impl FnMut() -> io::Result<R>,
+    ) -> impl Future<Output = io::Result<R>> {
+        self.fd.read_with(move |_| op())
     }
-    pub async fn write_with<R>(
-        &mut self,
-        op: impl FnMut(&mut T) -> io::Result<R>,
-    ) -> io::Result<R> {
-        unsafe { self.fd.write_with_mut(op).await }
+    fn write_with<R>(
+        &self,
+        mut op: impl FnMut() -> io::Result<R>,
+    ) -> impl Future<Output = io::Result<R>> {
+        self.fd.write_with(move |_| op())
+    }
+    async fn sleep(duration: Duration) {
+        Timer::after(duration).await;
     }
 }
@@ -1,8 +1,92 @@
 #[cfg(feature = "async")]
 mod tokio;
 #[cfg(feature = "async")]
-pub(crate) use tokio::*;
-#[cfg(all(feature = "async_io", not(feature = "async")))]
+pub use tokio::*;
+#[cfg(feature = "async_io")]
 mod async_io;
+#[cfg(feature = "async_io")]
+pub use async_io::*;
+use std::future::Future;
+use std::io;
+use std::os::fd::{AsRawFd, RawFd};
+use std::time::Duration;
+
+/// (Unix only) The reactor of an async runtime, on which [`AsyncRouteListener`] and
+/// [`AsyncRouteManager`] wait for their sockets and timers.
+///
+/// It is implemented for tokio by [`TokioRouteIo`] with the `async` feature and for async-io
+/// by [`AsyncIoRouteIo`] with the `async_io` feature. Implement it to run the listener and
+/// manager on another runtime, and pass it as their type parameter, e.g.
+/// `AsyncRouteManager::<MyIo>::new_with_io()`.
+///
+/// [`AsyncRouteListener`]: crate::AsyncRouteListener
+/// [`AsyncRouteManager`]: crate::AsyncRouteManager
+pub trait AsyncRouteIo: Sized {
+    /// Registers the socket `fd`, already non-blocking, with the reactor. The socket stays
+    /// open until the returned value is dropped.
+    fn new(fd: RawFd) -> io::Result<Self>;
+    /// Runs `op` whenever the socket is readable, until it returns anything but a
+    /// `WouldBlock` error. Several tasks may wait on one socket at once.
+    fn read_with<R>(
+        &self,
+        op: impl FnMut() -> io::Result<R>,
+    ) -> impl Future<Output = io::Result<R>>;
+    /// Runs `op` whenever the socket is writable, until it returns anything but a
+    /// `WouldBlock` error.
+    fn write_with<R>(
+        &self,
+        op: impl FnMut() -> io::Result<R>,
+    ) -> impl Future<Output = io::Result<R>>;
+    /// Waits for `duration`, for timeouts and the delays between retries.
+    fn sleep(duration: Duration) -> impl Future<Output = ()>;
+}
+
+/// (Unix only) The [`AsyncRouteIo`] of the runtime selected by the features: tokio with the
+/// `async` feature, otherwise async-io.
+#[cfg(feature = "async")]
+pub type DefaultRouteIo = TokioRouteIo;
+/// (Unix only) The [`AsyncRouteIo`] of the runtime selected by the features: tokio with the
+/// `async` feature, otherwise async-io.
 #[cfg(all(feature = "async_io", not(feature = "async")))]
-pub(crate) use async_io::*;
+pub type DefaultRouteIo = AsyncIoRouteIo;
+
+/// A socket registered with the reactor of `Io`.
+pub(crate) struct AsyncRoute<T, Io = DefaultRouteIo> {
+    // Deregistered before the socket is closed
+    io: Io,
+    inner: T,
+}
+impl<T: AsRawFd, Io: AsyncRouteIo> AsyncRoute<T, Io> {
+    pub fn new(inner: T) -> io::Result<Self> {
+        let mut nonblocking = true as libc::c_int;
+        if unsafe { libc::ioctl(inner.as_raw_fd(), libc::FIONBIO, &mut nonblocking) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(AsyncRoute {
+            io: Io::new(inner.as_raw_fd())?,
+            inner,
+        })
+    }
+    // Linux sockets are driven by the ring with the `io_uring` feature
+    #[cfg_attr(all(target_os = "linux", feature = "io_uring"), allow(dead_code))]
+    pub async fn read_with<R>(
+        &mut self,
+        mut op: impl FnMut(&mut T) -> io::Result<R>,
+    ) -> io::Result<R> {
+        let inner = &mut self.inner;
+        self.io.read_with(|| op(inner)).await
+    }
+    #[cfg_attr(all(target_os = "linux", feature = "io_uring"), allow(dead_code))]
+    pub async fn write_with<R>(
+        &mut self,
+        mut op: impl FnMut(&mut T) -> io::Result<R>,
+    ) -> io::Result<R> {
+        let inner = &mut self.inner;
+        self.io.write_with(|| op(inner)).await
+    }
+    /// Like [`read_with`](Self::read_with), for an operation that needs no exclusive access.
+    #[cfg(all(target_os = "linux", feature = "io_uring"))]
+    pub async fn read_with_ref<R>(&self, mut op: impl FnMut(&T) -> io::Result<R>) -> io::Result<R> {
+        self.io.read_with(|| op(&self.inner)).await
+    }
+}
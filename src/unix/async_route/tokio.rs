@@ -1,33 +1,35 @@
+use crate::AsyncRouteIo;
+use std::future::Future;
 use std::io;
-use std::os::fd::AsRawFd;
+use std::os::fd::RawFd;
+use std::time::Duration;
 use tokio::io::unix::AsyncFd;
 use tokio::io::Interest;
 
-pub struct AsyncRoute<T: AsRawFd> {
-    fd: AsyncFd<T>,
+/// (Unix only) The [`AsyncRouteIo`] of tokio, used with the `async` feature.
+pub struct TokioRouteIo {
+    fd: AsyncFd<RawFd>,
 }
-impl<T: AsRawFd> AsyncRoute<T> {
-    pub fn new(fd: T) -> io::Result<Self> {
-        let mut nonblocking = true as libc::c_int;
-        if unsafe { libc::ioctl(fd.as_raw_fd(), libc::FIONBIO, &mut nonblocking) } != 0 {
-            return Err(io::Error::last_os_error());
-        }
-        Ok(AsyncRoute {
+impl AsyncRouteIo for TokioRouteIo {
+    fn new(fd: RawFd) -> io::Result<Self> {
+        Ok(TokioRouteIo {
             fd: AsyncFd::new(fd)?,
         })
     }
-    pub async fn read_with<R>(
-        &mut self,
-        mut op: impl FnMut(&mut T) -> io::Result<R>,
-    ) -> io::Result<R> {
+    fn read_with<R>(
+        &self,
+        mut op: impl FnMut() -> io::Result<R>,
+    ) -> impl Future<Output = io::Result<R>> {
         self.fd
-            .async_io_mut(Interest::READABLE.add(Interest::ERROR), |fd| op(fd))
-            .await
+            .async_io(Interest::READABLE.add(Interest::ERROR), move |_| op())
     }
-    pub async fn write_with<R>(
-        &mut self,
-        mut op: impl FnMut(&mut T) -> io::Result<R>,
-    ) -> io::Result<R> {
-        self.fd.async_io_mut(Interest::WRITABLE, |fd| op(fd)).await
+    fn write_with<R>(
+        &self,
+        mut op: impl FnMut() -> io::Result<R>,
+    ) -> impl Future<Output = io::Result<R>> {
+        self.fd.async_io(Interest::WRITABLE, move |_| op())
+    }
+    fn sleep(duration: Duration) -> impl Future<Output = ()> {
+        tokio::time::sleep(duration)
     }
 }
@@ -0,0 +1,128 @@
+use crate::{RouteChange, RouteListener};
+use std::io;
+use std::time::{Duration, Instant};
+
+impl RouteListener {
+    /// Listens for a route change event and returns a RouteChange.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self), ret(Display), err(level = "debug"))
+    )]
+    pub fn listen(&mut self) -> io::Result<RouteChange> {
+        self.listen_with_origin().map(|(change, _)| change)
+    }
+    /// Listens for a route change event, waiting at most `timeout`.
+    ///
+    /// Fails with `TimedOut` if no event arrives in time.
+    pub fn listen_timeout(&mut self, timeout: Duration) -> io::Result<RouteChange> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(route) = self.list.pop()? {
+                return Ok(route);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if self.wait_readable(remaining)? {
+                self.recv_once()?;
+            } else if Instant::now() >= deadline {
+                return Err(io::Error::from(io::ErrorKind::TimedOut));
+            }
+        }
+    }
+    /// Returns a pending route change event without blocking, or `None` if there is none.
+    pub fn try_listen(&mut self) -> io::Result<Option<RouteChange>> {
+        loop {
+            if let Some(route) = self.list.pop()? {
+                return Ok(Some(route));
+            }
+            if !self.wait_readable(Duration::ZERO)? {
+                return Ok(None);
+            }
+            self.recv_once()?;
+        }
+    }
+    /// Drains all route change events that can be read without blocking.
+    ///
+    /// Intended for external event loops (e.g. `mio` or `polling`, via `AsFd`): call it
+    /// whenever the listener is reported readable. It leaves the socket drained, as
+    /// edge-triggered registrations require.
+    pub fn read_events(&mut self) -> io::Result<Vec<RouteChange>> {
+        self.try_drain()
+    }
+    /// Returns all route change events that can be read without blocking, possibly none.
+    pub fn try_drain(&mut self) -> io::Result<Vec<RouteChange>> {
+        let mut events = Vec::new();
+        self.drain_into(&mut events, 0, usize::MAX)?;
+        Ok(events)
+    }
+    /// Waits for a route change event and returns it together with the events that can be
+    /// read without blocking, at most `max` (but at least one) in total.
+    ///
+    /// Events beyond `max` stay buffered for the next call.
+    pub fn listen_batch(&mut self, max: usize) -> io::Result<Vec<RouteChange>> {
+        let mut events = vec![self.listen()?];
+        self.drain_into(&mut events, 0, max)?;
+        Ok(events)
+    }
+    /// Waits for a route change event and appends it to `events` together with the events
+    /// that can be read without blocking. Returns the number of events appended.
+    ///
+    /// Reusing `events` (after clearing it) avoids allocating in a processing loop.
+    pub fn listen_into(&mut self, events: &mut Vec<RouteChange>) -> io::Result<usize> {
+        let start = events.len();
+        events.push(self.listen()?);
+        self.drain_into(events, start, usize::MAX)?;
+        Ok(events.len() - start)
+    }
+    /// Appends the events that can be read without blocking to `events` until it holds `max`
+    /// events after `start`.
+    ///
+    /// An error after some events were collected is reported by the next call instead.
+    fn drain_into(
+        &mut self,
+        events: &mut Vec<RouteChange>,
+        start: usize,
+        max: usize,
+    ) -> io::Result<()> {
+        while events.len() - start < max {
+            match self.try_listen() {
+                Ok(Some(event)) => events.push(event),
+                Ok(None) => break,
+                Err(e) if events.len() == start => return Err(e),
+                Err(e) => {
+                    self.list.defer_error(e);
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+    fn wait_readable(&self, timeout: Duration) -> io::Result<bool> {
+        #[cfg(feature = "shutdown")]
+        return self.wait_timeout(Some(timeout));
+        #[cfg(not(feature = "shutdown"))]
+        return poll_readable(std::os::fd::AsRawFd::as_raw_fd(self), timeout);
+    }
+}
+
+#[cfg(not(feature = "shutdown"))]
+fn poll_readable(fd: libc::c_int, timeout: Duration) -> io::Result<bool> {
+    let mut pfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // Round up so that a short remaining timeout does not turn into a busy loop
+    let timeout_ms = timeout
+        .as_micros()
+        .div_ceil(1000)
+        .min(libc::c_int::MAX as u128);
+    let result = unsafe { libc::poll(&mut pfd, 1, timeout_ms as libc::c_int) };
+    if result == -1 {
+        let err = io::Error::last_os_error();
+        if err.kind() == io::ErrorKind::Interrupted {
+            return Ok(false);
+        }
+        return Err(err);
+    }
+    Ok(result != 0)
+}
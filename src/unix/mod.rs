@@ -1,15 +1,21 @@
+mod listen;
+mod route_ref;
 #[cfg(feature = "shutdown")]
 mod shutdown;
+#[cfg(feature = "mio")]
+mod source;
 
 #[cfg(any(feature = "async", feature = "async_io"))]
 mod async_route;
 #[cfg(any(feature = "async", feature = "async_io"))]
-pub(crate) use async_route::*;
+pub use async_route::*;
 use libc::c_char;
+pub use route_ref::*;
 #[cfg(feature = "shutdown")]
 pub use shutdown::*;
 use std::ffi::{CStr, CString};
 use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 pub(crate) fn if_name_to_index(name: &str) -> io::Result<u32> {
     let name = CString::new(name)?;
@@ -34,3 +40,54 @@ pub(crate) fn if_index_to_name(index: u32) -> io::Result<String> {
         }
     }
 }
+
+/// Lists the addresses configured on all interfaces.
+pub(crate) fn interface_addresses() -> io::Result<Vec<crate::InterfaceAddress>> {
+    let mut ifap: *mut libc::ifaddrs = std::ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut ifap) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut list = Vec::new();
+    let mut cur = ifap;
+    while !cur.is_null() {
+        let ifa = unsafe { &*cur };
+        cur = ifa.ifa_next;
+        if ifa.ifa_addr.is_null() || ifa.ifa_netmask.is_null() {
+            continue;
+        }
+        let (Some(addr), Some(mask)) = (unsafe { sockaddr_to_ip(ifa.ifa_addr) }, unsafe {
+            sockaddr_to_ip(ifa.ifa_netmask)
+        }) else {
+            continue;
+        };
+        let prefix = match mask {
+            IpAddr::V4(mask) => u32::from(mask).count_ones(),
+            IpAddr::V6(mask) => u128::from(mask).count_ones(),
+        } as u8;
+        let index = unsafe { libc::if_nametoindex(ifa.ifa_name) };
+        if index == 0 {
+            continue;
+        }
+        list.push(crate::InterfaceAddress {
+            index,
+            addr,
+            prefix,
+        });
+    }
+    unsafe { libc::freeifaddrs(ifap) };
+    Ok(list)
+}
+
+unsafe fn sockaddr_to_ip(sa: *const libc::sockaddr) -> Option<IpAddr> {
+    match (*sa).sa_family as i32 {
+        libc::AF_INET => {
+            let sa = &*(sa as *const libc::sockaddr_in);
+            Some(IpAddr::V4(Ipv4Addr::from(u32::from_be(sa.sin_addr.s_addr))))
+        }
+        libc::AF_INET6 => {
+            let sa = &*(sa as *const libc::sockaddr_in6);
+            Some(IpAddr::V6(Ipv6Addr::from(sa.sin6_addr.s6_addr)))
+        }
+        _ => None,
+    }
+}
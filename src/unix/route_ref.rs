@@ -0,0 +1,72 @@
+use crate::Route;
+use std::io;
+use std::net::IpAddr;
+
+/// The kind of a route change event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RouteChangeKind {
+    Add,
+    Delete,
+    /// A route was modified in place.
+    Change,
+}
+
+/// A route change event borrowed from the receive buffer of a [`RouteListener`], passed by
+/// [`RouteListener::listen_ref`].
+///
+/// The accessors read the fields parsed without allocating; [`to_owned`](Self::to_owned)
+/// parses the whole route, e.g. for the events that pass a filter on the destination.
+///
+/// [`RouteListener`]: crate::RouteListener
+/// [`RouteListener::listen_ref`]: crate::RouteListener::listen_ref
+#[derive(Clone, Copy, Debug)]
+pub struct RouteRef<'a> {
+    pub(crate) kind: RouteChangeKind,
+    pub(crate) destination: IpAddr,
+    pub(crate) prefix: u8,
+    pub(crate) gateway: Option<IpAddr>,
+    pub(crate) if_index: Option<u32>,
+    #[cfg(target_os = "linux")]
+    pub(crate) table: u32,
+    pub(crate) msg: &'a [u8],
+}
+
+impl<'a> RouteRef<'a> {
+    /// Returns whether the route was added, deleted or changed.
+    pub fn kind(&self) -> RouteChangeKind {
+        self.kind
+    }
+    pub fn destination(&self) -> IpAddr {
+        self.destination
+    }
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+    pub fn gateway(&self) -> Option<IpAddr> {
+        self.gateway
+    }
+    pub fn if_index(&self) -> Option<u32> {
+        self.if_index
+    }
+    /// (Linux only) Returns the routing table, including tables above 255.
+    #[cfg(target_os = "linux")]
+    pub fn table(&self) -> u32 {
+        self.table
+    }
+    /// Returns whether the destination of the route contains `addr`.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        addr.is_ipv4() == self.destination.is_ipv4()
+            && crate::network_address(addr, self.prefix)
+                == crate::network_address(self.destination, self.prefix)
+    }
+    /// Returns the raw message: the netlink message on Linux, the routing socket message
+    /// (`rt_msghdr` and its sockaddrs) on BSD.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.msg
+    }
+    /// Parses the whole route, as [`RouteListener::listen`](crate::RouteListener::listen)
+    /// would return it.
+    pub fn to_owned(&self) -> io::Result<Route> {
+        crate::parse_route_msg(self.msg)
+    }
+}
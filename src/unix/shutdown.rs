@@ -2,7 +2,8 @@ use crate::RouteListener;
 use std::io;
 use std::os::fd::AsRawFd;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
 struct EventFd(libc::c_int, libc::c_int);
 impl EventFd {
@@ -38,21 +39,41 @@ impl Drop for EventFd {
 }
 impl RouteListener {
     pub(crate) fn wait(&self) -> io::Result<()> {
+        if self.wait_timeout(None)? {
+            Ok(())
+        } else {
+            Err(io::Error::from(io::ErrorKind::TimedOut))
+        }
+    }
+    /// Waits until the socket is readable. Returns false if `timeout` elapsed first.
+    pub(crate) fn wait_timeout(&self, timeout: Option<Duration>) -> io::Result<bool> {
         let fd = self.as_raw_fd() as libc::c_int;
 
-        let event_fd = self.shutdown_handle.event_fd.as_event_fd();
+        // Without a handle nothing can shut the listener down while it waits
+        let event_fd = self
+            .shutdown_handle
+            .event_fd
+            .get()
+            .map(EventFd::as_event_fd);
         let mut readfds: libc::fd_set = unsafe { std::mem::zeroed() };
         unsafe {
             libc::FD_SET(fd, &mut readfds);
-            libc::FD_SET(event_fd, &mut readfds);
+            if let Some(event_fd) = event_fd {
+                libc::FD_SET(event_fd, &mut readfds);
+            }
         }
+        let mut tv = timeout.map(|v| libc::timeval {
+            tv_sec: v.as_secs() as _,
+            tv_usec: v.subsec_micros() as _,
+        });
         let result = unsafe {
             libc::select(
-                fd.max(event_fd) + 1,
+                fd.max(event_fd.unwrap_or(fd)) + 1,
                 &mut readfds,
                 std::ptr::null_mut(),
                 std::ptr::null_mut(),
-                std::ptr::null_mut(),
+                tv.as_mut()
+                    .map_or(std::ptr::null_mut(), |v| v as *mut libc::timeval),
             )
         };
         if self.shutdown_handle.is_shutdown.load(Ordering::Relaxed) {
@@ -61,13 +82,15 @@ impl RouteListener {
         if result == -1 {
             return Err(io::Error::last_os_error());
         }
-        if result == 0 {
-            return Err(io::Error::from(io::ErrorKind::TimedOut));
-        }
-        Ok(())
+        Ok(result != 0)
     }
     /// Retrieves a shutdown handle for the RouteListener.
+    ///
+    /// The pipe that wakes the listener is created with the first handle.
     pub fn shutdown_handle(&self) -> io::Result<RouteListenerShutdown> {
+        if self.shutdown_handle.event_fd.get().is_none() {
+            let _ = self.shutdown_handle.event_fd.set(EventFd::new()?);
+        }
         Ok(self.shutdown_handle.clone())
     }
 }
@@ -76,18 +99,21 @@ impl RouteListener {
 #[derive(Clone)]
 pub struct RouteListenerShutdown {
     is_shutdown: Arc<AtomicBool>,
-    event_fd: Arc<EventFd>,
+    event_fd: Arc<OnceLock<EventFd>>,
 }
 impl RouteListenerShutdown {
-    pub(crate) fn new() -> io::Result<Self> {
-        Ok(Self {
+    pub(crate) fn new() -> Self {
+        Self {
             is_shutdown: Arc::new(Default::default()),
-            event_fd: Arc::new(EventFd::new()?),
-        })
+            event_fd: Arc::new(OnceLock::new()),
+        }
     }
     /// Shuts down the RouteListener.
     pub fn shutdown(&self) -> io::Result<()> {
         self.is_shutdown.store(true, Ordering::Relaxed);
-        self.event_fd.wake()
+        match self.event_fd.get() {
+            Some(event_fd) => event_fd.wake(),
+            None => Ok(()),
+        }
     }
 }
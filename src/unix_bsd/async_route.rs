@@ -1,102 +1,239 @@
 use crate::unix_bsd::bind::*;
 use crate::unix_bsd::{
     add_or_del_route_req, create_route_socket, deserialize_res, deserialize_res_change,
-    list_routes, m_rtmsg,
+    list_routes, listener_family, recv_buf_len,
 };
-use crate::Route;
-use crate::{AsyncRoute, RouteChange};
-use std::collections::VecDeque;
+use crate::{AsyncRoute, AsyncRouteIo, DefaultRouteIo};
+use crate::{BufPool, RetryPolicy, Route};
+use crate::{EventOrigin, EventQueue, ListenerConfig, RouteChange};
 use std::io;
 use std::io::{Read, Write};
+use std::marker::PhantomData;
 use std::os::unix::net::UnixStream;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// AsyncRouteListener for asynchronously receiving route change events.
-pub struct AsyncRouteListener {
-    list: VecDeque<RouteChange>,
-    route_socket: AsyncRoute<UnixStream>,
+pub struct AsyncRouteListener<Io = DefaultRouteIo> {
+    list: EventQueue,
+    route_socket: AsyncRoute<UnixStream, Io>,
+    buf: Vec<u8>,
 }
 impl AsyncRouteListener {
     /// Creates a new AsyncRouteListener.
     pub fn new() -> io::Result<Self> {
-        let route_socket = create_route_socket()?;
+        Self::with_config(ListenerConfig::default())
+    }
+    /// Creates a new AsyncRouteListener with the given queue configuration.
+    pub fn with_config(config: ListenerConfig) -> io::Result<Self> {
+        Self::with_config_and_io(config)
+    }
+}
+impl<Io: AsyncRouteIo> AsyncRouteListener<Io> {
+    /// (Unix only) Creates a new AsyncRouteListener whose socket is driven by the reactor `Io`.
+    pub fn new_with_io() -> io::Result<Self> {
+        Self::with_config_and_io(ListenerConfig::default())
+    }
+    /// (Unix only) Creates a new AsyncRouteListener with the given queue configuration, whose
+    /// socket is driven by the reactor `Io`.
+    pub fn with_config_and_io(config: ListenerConfig) -> io::Result<Self> {
+        let route_socket = create_route_socket(listener_family(&config)?)?;
+        crate::set_recv_buffer(&route_socket, &config)?;
+        let buf = vec![0; recv_buf_len(&route_socket)];
         let route_socket = AsyncRoute::new(route_socket)?;
         Ok(AsyncRouteListener {
-            list: Default::default(),
+            list: EventQueue::new(&config)?,
             route_socket,
+            buf,
         })
     }
+    pub(crate) fn take_snapshot(&mut self) -> Vec<crate::Route> {
+        self.list.take_snapshot()
+    }
     /// Asynchronously listens for a route change event and returns a RouteChange.
+    ///
+    /// This method is cancel safe: if the future is dropped (e.g. in `select!`), no event is
+    /// lost and the next call picks up where this one left off.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self), ret(Display), err(level = "debug"))
+    )]
     pub async fn listen(&mut self) -> io::Result<RouteChange> {
-        if let Some(route) = self.list.pop_front() {
-            return Ok(route);
+        self.listen_with_origin().await.map(|(change, _)| change)
+    }
+    /// Asynchronously listens for a route change event and returns it together with its
+    /// sender. This method is cancel safe.
+    pub async fn listen_with_origin(&mut self) -> io::Result<(RouteChange, EventOrigin)> {
+        if let Some(event) = self.list.pop_with_origin()? {
+            return Ok(event);
         }
-        let mut buf = [0u8; 2048];
-        let route_socket = &mut self.route_socket;
         loop {
-            let read = route_socket.read_with(|s| s.read(&mut buf)).await?;
+            let buf = &mut self.buf;
+            let read = self.route_socket.read_with(|s| s.read(buf)).await?;
 
             deserialize_res_change(
-                |route| {
-                    self.list.push_back(route);
+                |route, origin| {
+                    self.list.push(route, origin);
                 },
-                &buf[..read],
+                &self.buf[..read],
             )?;
-            if let Some(route) = self.list.pop_front() {
-                return Ok(route);
+            if let Some(event) = self.list.pop_with_origin()? {
+                return Ok(event);
             }
         }
     }
 }
 /// AsyncRouteManager for asynchronously managing routes (adding, deleting, and listing).
-pub struct AsyncRouteManager {
-    _private: std::marker::PhantomData<()>,
+///
+/// Clones are cheap and share the manager; it can be used from several tasks at once.
+pub struct AsyncRouteManager<Io = DefaultRouteIo> {
+    // Reused by the sysctl dumps of list
+    bufs: Arc<BufPool>,
+    timeout: Option<Duration>,
+    normalize_mapped: bool,
+    retry: Option<RetryPolicy>,
+    io: PhantomData<fn() -> Io>,
+}
+
+impl<Io> Clone for AsyncRouteManager<Io> {
+    fn clone(&self) -> Self {
+        Self {
+            bufs: self.bufs.clone(),
+            timeout: self.timeout,
+            normalize_mapped: self.normalize_mapped,
+            retry: self.retry,
+            io: PhantomData,
+        }
+    }
 }
 
 impl AsyncRouteManager {
     /// Creates a new AsyncRouteManager.
     pub fn new() -> io::Result<AsyncRouteManager> {
-        Ok(AsyncRouteManager {
-            _private: std::marker::PhantomData,
-        })
+        Self::new_with_io()
     }
     /// Retrieves a new instance of AsyncRouteListener.
     pub fn listener() -> io::Result<AsyncRouteListener> {
         AsyncRouteListener::new()
     }
+}
+
+impl<Io: AsyncRouteIo> AsyncRouteManager<Io> {
+    /// (Unix only) Creates a new AsyncRouteManager whose sockets and timers are driven by the
+    /// reactor `Io`.
+    pub fn new_with_io() -> io::Result<Self> {
+        Ok(AsyncRouteManager {
+            bufs: Arc::new(BufPool::new(0)),
+            timeout: None,
+            normalize_mapped: false,
+            retry: None,
+            io: PhantomData,
+        })
+    }
+    /// (Unix only) Makes operations that do not complete within `timeout` fail with
+    /// `TimedOut`, e.g. when a reply of the kernel never arrives. Their sockets are closed.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+    /// Sets whether routes are converted with [`Route::to_canonical`] before they are
+    /// installed or deleted, see
+    /// [`RouteManagerBuilder::with_normalize_mapped`](crate::RouteManagerBuilder::with_normalize_mapped).
+    pub fn with_normalize_mapped(mut self, normalize: bool) -> Self {
+        self.normalize_mapped = normalize;
+        self
+    }
+    /// Retries operations that fail with a transient error under `policy`, see
+    /// [`RetryPolicy`].
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
 
     /// Asynchronously lists all current routes.
     /// **Note: On macOS and FreeBSD, this is not truly asynchronous.**
-    pub async fn list(&mut self) -> io::Result<Vec<Route>> {
-        list_routes()
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), err(level = "debug"))
+    )]
+    pub async fn list(&self) -> io::Result<Vec<Route>> {
+        let routes = crate::retry(self.retry, || list_routes(&mut self.bufs.take()))?;
+        debug_event!(count = routes.len(), "listed routes");
+        Ok(routes)
     }
     /// Asynchronously adds a new route.
-    pub async fn add(&mut self, route: &Route) -> io::Result<()> {
-        add_route(route).await
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub async fn add(&self, route: &Route) -> io::Result<()> {
+        let route = &*route.normalized(self.normalize_mapped);
+        let add = crate::retry_async(self.retry, Io::sleep, || add_route::<Io>(route));
+        crate::timeout_with(self.timeout.map(Io::sleep), add)
+            .await
+            .map_err(crate::context("add", route))
     }
     /// Asynchronously deletes an existing route.
-    pub async fn delete(&mut self, route: &Route) -> io::Result<()> {
-        delete_route(route).await
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub async fn delete(&self, route: &Route) -> io::Result<()> {
+        let route = &*route.normalized(self.normalize_mapped);
+        let delete = crate::retry_async(self.retry, Io::sleep, || delete_route::<Io>(route));
+        crate::timeout_with(self.timeout.map(Io::sleep), delete)
+            .await
+            .map_err(crate::context("delete", route))
+    }
+    /// Asynchronously modifies an existing route in place. See `RouteManager::update`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub async fn update(&self, route: &Route) -> io::Result<()> {
+        let route = &*route.normalized(self.normalize_mapped);
+        let update = crate::retry_async(self.retry, Io::sleep, || {
+            add_or_del_route::<Io>(route, RTM_CHANGE as u8)
+        });
+        crate::timeout_with(self.timeout.map(Io::sleep), update)
+            .await
+            .map_err(crate::context("update", route))
     }
 }
 
-async fn add_route(route: &Route) -> io::Result<()> {
-    add_or_del_route(route, RTM_ADD as u8).await
+async fn add_route<Io: AsyncRouteIo>(route: &Route) -> io::Result<()> {
+    add_or_del_route::<Io>(route, RTM_ADD as u8).await
 }
-async fn delete_route(route: &Route) -> io::Result<()> {
-    add_or_del_route(route, RTM_DELETE as u8).await
+async fn delete_route<Io: AsyncRouteIo>(route: &Route) -> io::Result<()> {
+    add_or_del_route::<Io>(route, RTM_DELETE as u8).await
 }
 
-async fn add_or_del_route(route: &Route, rtm_type: u8) -> io::Result<()> {
+async fn add_or_del_route<Io: AsyncRouteIo>(route: &Route, rtm_type: u8) -> io::Result<()> {
     let rtmsg = add_or_del_route_req(route, rtm_type)?;
-    let route_socket = create_route_socket()?;
+    let route_socket = create_route_socket(AF_UNSPEC as i32)?;
+    let mut buf = vec![0u8; recv_buf_len(&route_socket)];
 
-    let mut route_socket = AsyncRoute::new(route_socket)?;
+    let mut route_socket = AsyncRoute::<_, Io>::new(route_socket)?;
 
     route_socket
         .write_with(|s| s.write_all(rtmsg.slice()))
         .await?;
 
-    let mut buf = [0u8; std::mem::size_of::<m_rtmsg>()];
     let len = route_socket.read_with(|s| s.read(&mut buf)).await?;
     deserialize_res(|_, _| {}, &buf[..len])?;
 
@@ -14,3 +14,6 @@ include!("macos_bindings.rs");
 
 #[cfg(all(target_os = "netbsd", any(docsrs, not(feature = "bindgen"))))]
 include!("netbsd_bindings.rs");
+
+#[cfg(all(target_os = "dragonfly", any(docsrs, not(feature = "bindgen"))))]
+include!("dragonfly_bindings.rs");
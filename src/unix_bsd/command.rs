@@ -0,0 +1,185 @@
+use crate::common::unsupported;
+use crate::{CommandRouteManager, Route};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+impl CommandRouteManager {
+    /// Lists all current routes, parsed from `netstat -rn`.
+    pub fn list(&mut self) -> io::Result<Vec<Route>> {
+        let mut routes = Vec::new();
+        for family in ["inet", "inet6"] {
+            let args = ["-rn", "-f", family].map(String::from);
+            let output = self.run("netstat", &args)?;
+            routes.extend(parse_routes(&output, family == "inet6"));
+        }
+        debug_event!(count = routes.len(), "listed routes");
+        Ok(routes)
+    }
+    /// The `route` command of [`add`](Self::add).
+    pub(crate) fn add_command(route: &Route) -> io::Result<(&'static str, Vec<String>)> {
+        Ok(("route", route_args("add", route)?))
+    }
+    /// The `route` command of [`delete`](Self::delete).
+    pub(crate) fn delete_command(route: &Route) -> io::Result<(&'static str, Vec<String>)> {
+        Ok(("route", route_args("delete", route)?))
+    }
+    /// The `route` command of [`update`](Self::update).
+    pub(crate) fn update_command(route: &Route) -> io::Result<(&'static str, Vec<String>)> {
+        Ok(("route", route_args("change", route)?))
+    }
+}
+
+/// Builds the arguments of `route <cmd>`.
+fn route_args(cmd: &str, route: &Route) -> io::Result<Vec<String>> {
+    let route = &*route.input_form();
+    route.check()?;
+    if !route.raw_attrs.is_empty() {
+        return Err(unsupported("raw attributes"));
+    }
+    if route.raw_flags != 0 {
+        return Err(unsupported("raw flags"));
+    }
+    if route.expire.is_some() {
+        return Err(unsupported("the expiry"));
+    }
+    let mut args: Vec<String> = vec!["-n".into(), cmd.into()];
+    let host = if route.destination.is_ipv4() {
+        route.prefix == 32
+    } else {
+        args.push("-inet6".into());
+        route.prefix == 128
+    };
+    if host {
+        args.extend(["-host".into(), route.destination.to_string()]);
+    } else {
+        args.extend([
+            "-net".into(),
+            format!("{}/{}", route.destination, route.prefix),
+        ]);
+    }
+    let if_name = route.get_name();
+    match (route.gateway, &if_name) {
+        (Some(gateway), _) => {
+            let scope = route
+                .gateway_scope_id
+                .map(crate::cached_if_index_to_name)
+                .transpose()?;
+            match scope {
+                Some(scope) => args.push(format!("{gateway}%{scope}")),
+                None => args.push(gateway.to_string()),
+            }
+            if let Some(if_name) = if_name {
+                args.extend(["-ifp".into(), if_name.to_string()]);
+            }
+        }
+        (None, Some(if_name)) if cmd != "delete" => {
+            args.extend(["-interface".into(), if_name.to_string()]);
+        }
+        _ => {}
+    }
+    if let Some(pref_source) = route.pref_source {
+        args.extend(["-ifa".into(), pref_source.to_string()]);
+    }
+    if let Some(mtu) = route.mtu {
+        args.extend(["-mtu".into(), mtu.to_string()]);
+    }
+    #[cfg(target_os = "openbsd")]
+    if route.priority != 0 {
+        args.extend(["-priority".into(), route.priority.to_string()]);
+    }
+    Ok(args)
+}
+
+/// Parses the table printed by `netstat -rn -f <family>`, locating the columns by the
+/// header, whose layout differs between the BSDs.
+fn parse_routes(output: &str, ipv6: bool) -> Vec<Route> {
+    let mut routes = Vec::new();
+    let mut columns: Option<Vec<&str>> = None;
+    for line in output.lines() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.first() == Some(&"Destination") {
+            columns = Some(tokens);
+            continue;
+        }
+        let Some(columns) = &columns else {
+            continue;
+        };
+        // A blank line or a section title ends the table
+        if tokens.len() < 3 {
+            continue;
+        }
+        let column = |names: &[&str]| {
+            let idx = columns.iter().position(|v| names.contains(v))?;
+            tokens.get(idx).copied()
+        };
+        let flags = column(&["Flags"]).unwrap_or_default();
+        let Some((destination, prefix)) = parse_destination(tokens[0], ipv6, flags.contains('H'))
+        else {
+            continue;
+        };
+        let mut route = Route::new(destination, prefix);
+        let if_name = column(&["Netif", "Iface", "Interface"]);
+        if let Some(if_name) = if_name {
+            route.if_index = crate::cached_if_name_to_index(if_name).ok();
+            route.if_name = Some(if_name.into());
+        }
+        // Directly connected routes name the link (`link#1`) or a MAC address instead
+        if let Some((gateway, scope)) = tokens.get(1).and_then(|v| parse_gateway(v)) {
+            route.gateway = Some(gateway);
+            route.gateway_scope_id = scope.and_then(|v| crate::cached_if_name_to_index(v).ok());
+        }
+        route.mtu = column(&["Mtu", "MTU"]).and_then(|v| v.parse().ok().filter(|v| *v != 0));
+        #[cfg(target_os = "openbsd")]
+        if let Some(priority) = column(&["Prio"]).and_then(|v| v.parse().ok()) {
+            route.priority = priority;
+        }
+        routes.push(route);
+    }
+    routes
+}
+
+/// Parses a destination column: `default`, `addr/prefix`, a host address, or on macOS an
+/// IPv4 network abbreviated to its significant octets (`10`, `169.254`, `10/24`).
+fn parse_destination(value: &str, ipv6: bool, host: bool) -> Option<(IpAddr, u8)> {
+    if value == "default" {
+        let unspecified = if ipv6 {
+            IpAddr::V6(Ipv6Addr::UNSPECIFIED)
+        } else {
+            IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+        };
+        return Some((unspecified, 0));
+    }
+    let (addr, prefix) = match value.split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix.parse().ok()?)),
+        None => (value, None),
+    };
+    // Link-local destinations carry their zone, e.g. `fe80::%lo0`
+    let addr = addr.split_once('%').map_or(addr, |v| v.0);
+    if ipv6 {
+        let addr: Ipv6Addr = addr.parse().ok()?;
+        return Some((addr.into(), prefix.unwrap_or(128)));
+    }
+    let mut octets = [0u8; 4];
+    let mut count = 0;
+    for (dst, src) in octets.iter_mut().zip(addr.split('.')) {
+        *dst = src.parse().ok()?;
+        count += 1;
+    }
+    if count == 0 || addr.split('.').count() > 4 {
+        return None;
+    }
+    let prefix = match prefix {
+        Some(prefix) => prefix,
+        None if host || count == 4 => 32,
+        None => count * 8,
+    };
+    Some((Ipv4Addr::from(octets).into(), prefix))
+}
+
+/// Parses a gateway column, splitting off the zone of a link-local address.
+fn parse_gateway(value: &str) -> Option<(IpAddr, Option<&str>)> {
+    match value.split_once('%') {
+        Some((addr, scope)) => Some((addr.parse().ok()?, Some(scope))),
+        None => Some((value.parse().ok()?, None)),
+    }
+}
@@ -0,0 +1,317 @@
+/* Subset of the rust-bindgen output for wrapper.h on DragonFly BSD (x86_64) */
+
+pub const CTL_NET: u32 = 4;
+pub const SOCK_DGRAM: u32 = 2;
+pub const SOCK_RAW: u32 = 3;
+pub const AF_UNSPEC: u32 = 0;
+pub const AF_INET: u32 = 2;
+pub const AF_ROUTE: u32 = 17;
+pub const AF_LINK: u32 = 18;
+pub const AF_INET6: u32 = 28;
+pub const PF_ROUTE: u32 = 17;
+pub const NET_RT_DUMP: u32 = 1;
+pub const NET_RT_FLAGS: u32 = 2;
+pub const NET_RT_IFLIST: u32 = 3;
+pub const IFNAMSIZ: u32 = 16;
+pub const IFF_UP: u32 = 1;
+pub const RTF_UP: u32 = 1;
+pub const RTF_GATEWAY: u32 = 2;
+pub const RTF_HOST: u32 = 4;
+pub const RTF_REJECT: u32 = 8;
+pub const RTF_DYNAMIC: u32 = 16;
+pub const RTF_MODIFIED: u32 = 32;
+pub const RTF_DONE: u32 = 64;
+pub const RTF_CLONING: u32 = 256;
+pub const RTF_XRESOLVE: u32 = 512;
+pub const RTF_LLINFO: u32 = 1024;
+pub const RTF_STATIC: u32 = 2048;
+pub const RTF_BLACKHOLE: u32 = 4096;
+pub const RTF_PROTO2: u32 = 16384;
+pub const RTF_PROTO1: u32 = 32768;
+pub const RTF_PRCLONING: u32 = 65536;
+pub const RTF_WASCLONED: u32 = 131072;
+pub const RTF_PROTO3: u32 = 262144;
+pub const RTF_PINNED: u32 = 1048576;
+pub const RTF_LOCAL: u32 = 2097152;
+pub const RTF_BROADCAST: u32 = 4194304;
+pub const RTF_MULTICAST: u32 = 8388608;
+pub const RTM_VERSION: u32 = 7;
+pub const RTM_ADD: u32 = 1;
+pub const RTM_DELETE: u32 = 2;
+pub const RTM_CHANGE: u32 = 3;
+pub const RTM_GET: u32 = 4;
+pub const RTM_LOSING: u32 = 5;
+pub const RTM_REDIRECT: u32 = 6;
+pub const RTM_MISS: u32 = 7;
+pub const RTM_LOCK: u32 = 8;
+pub const RTM_RESOLVE: u32 = 11;
+pub const RTM_NEWADDR: u32 = 12;
+pub const RTM_DELADDR: u32 = 13;
+pub const RTM_IFINFO: u32 = 14;
+pub const RTM_NEWMADDR: u32 = 15;
+pub const RTM_DELMADDR: u32 = 16;
+pub const RTM_IFANNOUNCE: u32 = 17;
+pub const RTM_IEEE80211: u32 = 18;
+pub const RTV_MTU: u32 = 1;
+pub const RTV_HOPCOUNT: u32 = 2;
+pub const RTV_EXPIRE: u32 = 4;
+pub const RTA_DST: u32 = 1;
+pub const RTA_GATEWAY: u32 = 2;
+pub const RTA_NETMASK: u32 = 4;
+pub const RTA_GENMASK: u32 = 8;
+pub const RTA_IFP: u32 = 16;
+pub const RTA_IFA: u32 = 32;
+pub const RTA_AUTHOR: u32 = 64;
+pub const RTA_BRD: u32 = 128;
+pub const RTA_MPLS1: u32 = 256;
+pub const RTA_MPLS2: u32 = 512;
+pub const RTA_MPLS3: u32 = 1024;
+pub const RTAX_DST: u32 = 0;
+pub const RTAX_GATEWAY: u32 = 1;
+pub const RTAX_NETMASK: u32 = 2;
+pub const RTAX_GENMASK: u32 = 3;
+pub const RTAX_IFP: u32 = 4;
+pub const RTAX_IFA: u32 = 5;
+pub const RTAX_AUTHOR: u32 = 6;
+pub const RTAX_BRD: u32 = 7;
+pub const RTAX_MPLS1: u32 = 8;
+pub const RTAX_MPLS2: u32 = 9;
+pub const RTAX_MPLS3: u32 = 10;
+pub const RTAX_MAX: u32 = 11;
+pub type __uint8_t = ::std::os::raw::c_uchar;
+pub type __uint16_t = ::std::os::raw::c_ushort;
+pub type __uint32_t = ::std::os::raw::c_uint;
+pub type __int32_t = ::std::os::raw::c_int;
+pub type __int64_t = ::std::os::raw::c_long;
+pub type u_char = ::std::os::raw::c_uchar;
+pub type u_short = ::std::os::raw::c_ushort;
+pub type u_int = ::std::os::raw::c_uint;
+pub type u_long = ::std::os::raw::c_ulong;
+pub type pid_t = __int32_t;
+pub type time_t = __int64_t;
+pub type suseconds_t = ::std::os::raw::c_long;
+pub type sa_family_t = __uint8_t;
+pub type in_addr_t = __uint32_t;
+pub type in_port_t = __uint16_t;
+unsafe extern "C" {
+    pub fn sysctl(
+        arg1: *const ::std::os::raw::c_int,
+        arg2: u_int,
+        arg3: *mut ::std::os::raw::c_void,
+        arg4: *mut usize,
+        arg5: *const ::std::os::raw::c_void,
+        arg6: usize,
+    ) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub fn socket(
+        arg1: ::std::os::raw::c_int,
+        arg2: ::std::os::raw::c_int,
+        arg3: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct timeval {
+    pub tv_sec: time_t,
+    pub tv_usec: suseconds_t,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct sockaddr {
+    pub sa_len: u_char,
+    pub sa_family: sa_family_t,
+    pub sa_data: [::std::os::raw::c_char; 14usize],
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of sockaddr"][::std::mem::size_of::<sockaddr>() - 16usize];
+    ["Alignment of sockaddr"][::std::mem::align_of::<sockaddr>() - 1usize];
+};
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct in_addr {
+    pub s_addr: in_addr_t,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct sockaddr_in {
+    pub sin_len: u8,
+    pub sin_family: sa_family_t,
+    pub sin_port: in_port_t,
+    pub sin_addr: in_addr,
+    pub sin_zero: [::std::os::raw::c_char; 8usize],
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of sockaddr_in"][::std::mem::size_of::<sockaddr_in>() - 16usize];
+    ["Alignment of sockaddr_in"][::std::mem::align_of::<sockaddr_in>() - 4usize];
+};
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct in6_addr {
+    pub __u6_addr: in6_addr__bindgen_ty_1,
+}
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub union in6_addr__bindgen_ty_1 {
+    pub __u6_addr8: [u8; 16usize],
+    pub __u6_addr16: [u16; 8usize],
+    pub __u6_addr32: [u32; 4usize],
+}
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct sockaddr_in6 {
+    pub sin6_len: u8,
+    pub sin6_family: sa_family_t,
+    pub sin6_port: in_port_t,
+    pub sin6_flowinfo: u32,
+    pub sin6_addr: in6_addr,
+    pub sin6_scope_id: u32,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of sockaddr_in6"][::std::mem::size_of::<sockaddr_in6>() - 28usize];
+    ["Alignment of sockaddr_in6"][::std::mem::align_of::<sockaddr_in6>() - 4usize];
+};
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct sockaddr_dl {
+    pub sdl_len: u_char,
+    pub sdl_family: u_char,
+    pub sdl_index: u_short,
+    pub sdl_type: u_char,
+    pub sdl_nlen: u_char,
+    pub sdl_alen: u_char,
+    pub sdl_slen: u_char,
+    pub sdl_data: [::std::os::raw::c_char; 12usize],
+    pub sdl_rcf: u_short,
+    pub sdl_route: [u_short; 16usize],
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of sockaddr_dl"][::std::mem::size_of::<sockaddr_dl>() - 54usize];
+    ["Alignment of sockaddr_dl"][::std::mem::align_of::<sockaddr_dl>() - 2usize];
+};
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct rt_metrics {
+    pub rmx_locks: u_long,
+    pub rmx_mtu: u_long,
+    pub rmx_pksent: u_long,
+    pub rmx_expire: u_long,
+    pub rmx_sendpipe: u_long,
+    pub rmx_ssthresh: u_long,
+    pub rmx_rtt: u_long,
+    pub rmx_rttvar: u_long,
+    pub rmx_recvpipe: u_long,
+    pub rmx_hopcount: u_long,
+    pub rmx_mssopt: u_short,
+    pub rmx_pad: u_short,
+    pub rmx_msl: u_long,
+    pub rmx_iwmaxsegs: u_long,
+    pub rmx_iwcapsegs: u_long,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of rt_metrics"][::std::mem::size_of::<rt_metrics>() - 112usize];
+    ["Alignment of rt_metrics"][::std::mem::align_of::<rt_metrics>() - 8usize];
+};
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct rt_msghdr {
+    pub rtm_msglen: u_short,
+    pub rtm_version: u_char,
+    pub rtm_type: u_char,
+    pub rtm_index: u_short,
+    pub rtm_flags: ::std::os::raw::c_int,
+    pub rtm_addrs: ::std::os::raw::c_int,
+    pub rtm_pid: pid_t,
+    pub rtm_seq: ::std::os::raw::c_int,
+    pub rtm_errno: ::std::os::raw::c_int,
+    pub rtm_use: ::std::os::raw::c_int,
+    pub rtm_inits: u_long,
+    pub rtm_rmx: rt_metrics,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of rt_msghdr"][::std::mem::size_of::<rt_msghdr>() - 152usize];
+    ["Alignment of rt_msghdr"][::std::mem::align_of::<rt_msghdr>() - 8usize];
+    ["Offset of field: rt_msghdr::rtm_rmx"][::std::mem::offset_of!(rt_msghdr, rtm_rmx) - 40usize];
+};
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct if_data {
+    pub ifi_type: u_char,
+    pub ifi_physical: u_char,
+    pub ifi_addrlen: u_char,
+    pub ifi_hdrlen: u_char,
+    pub ifi_recvquota: u_char,
+    pub ifi_xmitquota: u_char,
+    pub ifi_mtu: u_long,
+    pub ifi_metric: u_long,
+    pub ifi_link_state: u_long,
+    pub ifi_baudrate: u64,
+    pub ifi_ipackets: u_long,
+    pub ifi_ierrors: u_long,
+    pub ifi_opackets: u_long,
+    pub ifi_oerrors: u_long,
+    pub ifi_collisions: u_long,
+    pub ifi_ibytes: u_long,
+    pub ifi_obytes: u_long,
+    pub ifi_imcasts: u_long,
+    pub ifi_omcasts: u_long,
+    pub ifi_iqdrops: u_long,
+    pub ifi_noproto: u_long,
+    pub ifi_hwassist: u_long,
+    pub ifi_oqdrops: u_long,
+    pub ifi_lastchange: timeval,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of if_data"][::std::mem::size_of::<if_data>() - 160usize];
+    ["Alignment of if_data"][::std::mem::align_of::<if_data>() - 8usize];
+};
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct if_msghdr {
+    pub ifm_msglen: u_short,
+    pub ifm_version: u_char,
+    pub ifm_type: u_char,
+    pub ifm_addrs: ::std::os::raw::c_int,
+    pub ifm_flags: ::std::os::raw::c_int,
+    pub ifm_index: u_short,
+    pub ifm_data: if_data,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of if_msghdr"][::std::mem::size_of::<if_msghdr>() - 176usize];
+    ["Alignment of if_msghdr"][::std::mem::align_of::<if_msghdr>() - 8usize];
+};
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct ifreq {
+    pub ifr_name: [::std::os::raw::c_char; 16usize],
+    pub ifr_ifru: ifreq__bindgen_ty_1,
+}
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub union ifreq__bindgen_ty_1 {
+    pub ifru_addr: sockaddr,
+    pub ifru_dstaddr: sockaddr,
+    pub ifru_broadaddr: sockaddr,
+    pub ifru_flags: [::std::os::raw::c_short; 2usize],
+    pub ifru_metric: ::std::os::raw::c_int,
+    pub ifru_mtu: ::std::os::raw::c_int,
+    pub ifru_phys: ::std::os::raw::c_int,
+    pub ifru_media: ::std::os::raw::c_int,
+    pub ifru_data: *mut ::std::os::raw::c_void,
+    pub ifru_cap: [::std::os::raw::c_int; 2usize],
+    pub ifru_pollcpu: ::std::os::raw::c_int,
+    pub ifru_tsolen: ::std::os::raw::c_int,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of ifreq"][::std::mem::size_of::<ifreq>() - 32usize];
+    ["Alignment of ifreq"][::std::mem::align_of::<ifreq>() - 8usize];
+};
@@ -0,0 +1,181 @@
+use crate::unix_bsd::bind::*;
+use crate::unix_bsd::try_get_msg_buf;
+use crate::{if_index_to_name, Interface};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::{io, mem};
+
+/// Lists all network interfaces.
+pub fn list_interfaces() -> io::Result<Vec<Interface>> {
+    let msgs_buf = try_get_msg_buf(NET_RT_IFLIST)?;
+    let mut list = Vec::new();
+    let mut offset = 0;
+    while offset + mem::size_of::<if_msghdr>() <= msgs_buf.len() {
+        let buf = &msgs_buf[offset..];
+        let ifm: if_msghdr = unsafe { std::ptr::read_unaligned(buf.as_ptr().cast()) };
+        let msg_len = ifm.ifm_msglen as usize;
+        if msg_len == 0 || msg_len > buf.len() {
+            break;
+        }
+        offset += msg_len;
+        // NET_RT_IFLIST also returns RTM_NEWADDR messages for each interface address
+        if ifm.ifm_version as u32 != RTM_VERSION || ifm.ifm_type as u32 != RTM_IFINFO {
+            continue;
+        }
+        #[cfg(target_os = "openbsd")]
+        let hdr_len = ifm.ifm_hdrlen as usize;
+        #[cfg(not(target_os = "openbsd"))]
+        let hdr_len = mem::size_of::<if_msghdr>();
+
+        let index = ifm.ifm_index as u32;
+        let mut name = None;
+        let mut mac = None;
+        // The link-level sockaddr is the only address carried by RTM_IFINFO
+        if ifm.ifm_addrs as u32 & RTA_IFP != 0 && hdr_len < msg_len {
+            (name, mac) = parse_sockaddr_dl(&buf[hdr_len..msg_len]);
+        }
+        let name = match name {
+            Some(name) => name,
+            None => if_index_to_name(index)?,
+        };
+        list.push(Interface {
+            index,
+            name,
+            mac,
+            mtu: ifm.ifm_data.ifi_mtu as u32,
+            flags: ifm.ifm_flags as u32,
+            is_up: ifm.ifm_flags as u32 & IFF_UP != 0,
+        });
+    }
+    Ok(list)
+}
+
+/// LinkManager is used for configuring network interfaces (up/down, MTU).
+pub struct LinkManager {
+    _private: std::marker::PhantomData<()>,
+}
+impl LinkManager {
+    /// Creates a new LinkManager.
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            _private: std::marker::PhantomData,
+        })
+    }
+    /// Lists all network interfaces.
+    pub fn list(&mut self) -> io::Result<Vec<Interface>> {
+        list_interfaces()
+    }
+    /// Sets the interface administratively up.
+    pub fn set_up(&mut self, if_index: u32) -> io::Result<()> {
+        set_flag(if_index, IFF_UP, true)
+    }
+    /// Sets the interface administratively down.
+    pub fn set_down(&mut self, if_index: u32) -> io::Result<()> {
+        set_flag(if_index, IFF_UP, false)
+    }
+    /// Sets the MTU of the interface.
+    pub fn set_mtu(&mut self, if_index: u32, mtu: u32) -> io::Result<()> {
+        let socket = ctl_socket()?;
+        let mut ifr = ifreq_for(if_index)?;
+        #[cfg(not(target_os = "openbsd"))]
+        {
+            ifr.ifr_ifru.ifru_mtu = mtu as _;
+        }
+        // OpenBSD aliases ifr_mtu to ifru_metric
+        #[cfg(target_os = "openbsd")]
+        {
+            ifr.ifr_ifru.ifru_metric = mtu as _;
+        }
+        ioctl(&socket, SIOCSIFMTU, &mut ifr)
+    }
+}
+
+// See _IOW/_IOWR in sys/ioccom.h
+const IOC_OUT: libc::c_ulong = 0x40000000;
+const IOC_IN: libc::c_ulong = 0x80000000;
+const IOCPARM_MASK: libc::c_ulong = 0x1fff;
+const fn ioc(inout: libc::c_ulong, group: u8, num: u8, len: usize) -> libc::c_ulong {
+    inout
+        | ((len as libc::c_ulong & IOCPARM_MASK) << 16)
+        | ((group as libc::c_ulong) << 8)
+        | num as libc::c_ulong
+}
+const SIOCSIFFLAGS: libc::c_ulong = ioc(IOC_IN, b'i', 16, mem::size_of::<ifreq>());
+const SIOCGIFFLAGS: libc::c_ulong = ioc(IOC_IN | IOC_OUT, b'i', 17, mem::size_of::<ifreq>());
+#[cfg(any(target_os = "freebsd", target_os = "macos", target_os = "dragonfly"))]
+const SIOCSIFMTU: libc::c_ulong = ioc(IOC_IN, b'i', 52, mem::size_of::<ifreq>());
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+const SIOCSIFMTU: libc::c_ulong = ioc(IOC_IN, b'i', 127, mem::size_of::<ifreq>());
+
+fn set_flag(if_index: u32, flag: u32, on: bool) -> io::Result<()> {
+    let socket = ctl_socket()?;
+    let mut ifr = ifreq_for(if_index)?;
+    ioctl(&socket, SIOCGIFFLAGS, &mut ifr)?;
+    // FreeBSD and DragonFly split the flags into a low and a high half
+    #[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+    unsafe {
+        let flags = ifr.ifr_ifru.ifru_flags[0] as u16 as u32;
+        let flags = if on { flags | flag } else { flags & !flag };
+        ifr.ifr_ifru.ifru_flags[0] = flags as u16 as _;
+    }
+    #[cfg(not(any(target_os = "freebsd", target_os = "dragonfly")))]
+    unsafe {
+        let flags = ifr.ifr_ifru.ifru_flags as u16 as u32;
+        let flags = if on { flags | flag } else { flags & !flag };
+        ifr.ifr_ifru.ifru_flags = flags as u16 as _;
+    }
+    ioctl(&socket, SIOCSIFFLAGS, &mut ifr)
+}
+
+fn ctl_socket() -> io::Result<OwnedFd> {
+    let fd = unsafe { socket(AF_INET as i32, SOCK_DGRAM as i32, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+fn ifreq_for(if_index: u32) -> io::Result<ifreq> {
+    let name = if_index_to_name(if_index)?;
+    let mut ifr: ifreq = unsafe { mem::zeroed() };
+    if name.len() >= ifr.ifr_name.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "interface name too long",
+        ));
+    }
+    for (dst, src) in ifr.ifr_name.iter_mut().zip(name.bytes()) {
+        *dst = src as _;
+    }
+    Ok(ifr)
+}
+
+fn ioctl(socket: &OwnedFd, request: libc::c_ulong, ifr: &mut ifreq) -> io::Result<()> {
+    if unsafe { libc::ioctl(socket.as_raw_fd(), request as _, ifr as *mut ifreq) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Extracts the interface name and the Ethernet address from a raw `sockaddr_dl`.
+fn parse_sockaddr_dl(sa: &[u8]) -> (Option<String>, Option<[u8; 6]>) {
+    // sdl_len, sdl_family, sdl_index(2), sdl_type, sdl_nlen, sdl_alen, sdl_slen, sdl_data
+    const SDL_DATA_OFFSET: usize = 8;
+    if sa.len() < SDL_DATA_OFFSET || sa[1] as u32 != AF_LINK {
+        return (None, None);
+    }
+    let sa = &sa[..(sa[0] as usize).clamp(SDL_DATA_OFFSET, sa.len())];
+    let nlen = sa[5] as usize;
+    let alen = sa[6] as usize;
+    let data = &sa[SDL_DATA_OFFSET..];
+    let name = data
+        .get(..nlen)
+        .filter(|v| !v.is_empty())
+        .map(|v| String::from_utf8_lossy(v).into_owned());
+    let mac = if alen == 6 {
+        data.get(nlen..nlen + alen)
+            .and_then(|v| <[u8; 6]>::try_from(v).ok())
+    } else {
+        None
+    };
+    (name, mac)
+}
@@ -3,25 +3,33 @@
 // https://github.com/openbsd/src/blob/master/sbin/route/route.c
 // https://github.com/NetBSD/src/blob/trunk/sbin/route/route.c
 
-use crate::{Route, RouteChange};
-use std::collections::VecDeque;
+use crate::{
+    BufPool, EventOrigin, EventQueue, ListenerConfig, RawAttribute, Reported, Route, RouteChange,
+};
 use std::io::{Read, Write};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
-use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
 use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
 use std::{io, mem};
 #[cfg(any(feature = "async", feature = "async_io"))]
 mod async_route;
 #[cfg(any(feature = "async", feature = "async_io"))]
 pub use async_route::*;
 mod bind;
-use crate::if_index_to_name;
+#[cfg(feature = "command")]
+mod command;
+mod interface;
 use bind::*;
+pub use interface::*;
 
 /// RouteListener for receiving route change events.
 pub struct RouteListener {
-    list: VecDeque<RouteChange>,
+    pub(crate) list: EventQueue,
     route_socket: UnixStream,
+    buf: Vec<u8>,
     #[cfg(feature = "shutdown")]
     pub(crate) shutdown_handle: crate::RouteListenerShutdown,
 }
@@ -29,36 +37,52 @@ pub struct RouteListener {
 impl RouteListener {
     /// Creates a new RouteListener.
     pub fn new() -> io::Result<Self> {
-        let route_socket = create_route_socket()?;
+        Self::with_config(ListenerConfig::default())
+    }
+    /// Creates a new RouteListener with the given queue configuration.
+    pub fn with_config(config: ListenerConfig) -> io::Result<Self> {
+        Self::with_socket(create_route_socket(listener_family(&config)?)?, config)
+    }
+    /// (Unix only) Creates a RouteListener that receives the route change events on
+    /// `socket`, a `PF_ROUTE` socket opened elsewhere, e.g. by a privileged parent of a
+    /// sandboxed process.
+    ///
+    /// The address family the socket was opened with decides which events it receives; the
+    /// families of `config` only have to be valid.
+    pub fn from_socket(socket: OwnedFd, config: ListenerConfig) -> io::Result<Self> {
+        config.check_families()?;
+        Self::with_socket(UnixStream::from(socket), config)
+    }
+    fn with_socket(route_socket: UnixStream, config: ListenerConfig) -> io::Result<Self> {
+        crate::set_recv_buffer(&route_socket, &config)?;
         #[cfg(feature = "shutdown")]
         route_socket.set_nonblocking(true)?;
         Ok(RouteListener {
-            list: Default::default(),
+            list: EventQueue::new(&config)?,
+            buf: vec![0; recv_buf_len(&route_socket)],
             route_socket,
             #[cfg(feature = "shutdown")]
-            shutdown_handle: crate::RouteListenerShutdown::new()?,
+            shutdown_handle: crate::RouteListenerShutdown::new(),
         })
     }
 
-    /// Listens for a route change event and returns a RouteChange.
+    /// Listens for a route change event and returns it together with its sender.
     #[cfg(not(feature = "shutdown"))]
-    pub fn listen(&mut self) -> io::Result<RouteChange> {
-        if let Some(route) = self.list.pop_front() {
-            return Ok(route);
+    pub fn listen_with_origin(&mut self) -> io::Result<(RouteChange, EventOrigin)> {
+        if let Some(event) = self.list.pop_with_origin()? {
+            return Ok(event);
         }
-        let mut buf = [0u8; 4096];
-        let route_socket = &mut self.route_socket;
         loop {
-            let len = route_socket.read(&mut buf)?;
+            let len = self.route_socket.read(&mut self.buf)?;
 
             deserialize_res_change(
-                |route| {
-                    self.list.push_back(route);
+                |route, origin| {
+                    self.list.push(route, origin);
                 },
-                &buf[..len],
+                &self.buf[..len],
             )?;
-            if let Some(route) = self.list.pop_front() {
-                return Ok(route);
+            if let Some(event) = self.list.pop_with_origin()? {
+                return Ok(event);
             }
         }
     }
@@ -69,45 +93,202 @@ impl AsRawFd for RouteListener {
         self.route_socket.as_raw_fd()
     }
 }
+impl AsFd for RouteListener {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.route_socket.as_fd()
+    }
+}
+impl IntoRawFd for RouteListener {
+    /// Returns the routing socket. Events buffered by the listener are lost.
+    fn into_raw_fd(self) -> RawFd {
+        self.route_socket.into_raw_fd()
+    }
+}
+impl FromRawFd for RouteListener {
+    /// Creates a RouteListener on the `PF_ROUTE` socket `fd`, e.g. one returned by
+    /// [`into_raw_fd`](IntoRawFd::into_raw_fd) before an `exec`, with the default queue
+    /// configuration.
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        let route_socket = UnixStream::from_raw_fd(fd);
+        #[cfg(feature = "shutdown")]
+        let _ = route_socket.set_nonblocking(true);
+        Self {
+            list: EventQueue::new_or_empty(&ListenerConfig::default()),
+            buf: vec![0; recv_buf_len(&route_socket)],
+            route_socket,
+            #[cfg(feature = "shutdown")]
+            shutdown_handle: crate::RouteListenerShutdown::new(),
+        }
+    }
+}
 
 impl RouteListener {
-    /// Listens for a route change event and returns a RouteChange.
+    /// Listens for a route change event and returns it together with its sender.
     #[cfg(feature = "shutdown")]
-    pub fn listen(&mut self) -> io::Result<RouteChange> {
-        if let Some(route) = self.list.pop_front() {
-            return Ok(route);
+    pub fn listen_with_origin(&mut self) -> io::Result<(RouteChange, EventOrigin)> {
+        if let Some(event) = self.list.pop_with_origin()? {
+            return Ok(event);
         }
-        let mut buf = [0u8; 4096];
         loop {
             self.wait()?;
-            let len = match self.route_socket.read(&mut buf) {
+            let len = match self.route_socket.read(&mut self.buf) {
                 Ok(list) => list,
                 Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => continue,
                 Err(e) => return Err(e),
             };
             deserialize_res_change(
-                |route| {
-                    self.list.push_back(route);
+                |route, origin| {
+                    self.list.push(route, origin);
                 },
-                &buf[..len],
+                &self.buf[..len],
             )?;
-            if let Some(route) = self.list.pop_front() {
-                return Ok(route);
+            if let Some(event) = self.list.pop_with_origin()? {
+                return Ok(event);
+            }
+        }
+    }
+}
+
+impl RouteListener {
+    pub(crate) fn take_snapshot(&mut self) -> Vec<crate::Route> {
+        self.list.take_snapshot()
+    }
+    /// Reads one message and buffers the route changes it carries.
+    pub(crate) fn recv_once(&mut self) -> io::Result<()> {
+        let len = match self.route_socket.read(&mut self.buf) {
+            Ok(len) => len,
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        deserialize_res_change(
+            |route, origin| {
+                self.list.push(route, origin);
+            },
+            &self.buf[..len],
+        )?;
+        Ok(())
+    }
+}
+
+impl RouteListener {
+    /// Waits for route change events and passes them to `f` as views of the receive buffer,
+    /// without building a [`Route`] for each. Returns the number of events passed, at least
+    /// one.
+    ///
+    /// The event queue is bypassed: events buffered for [`listen`](Self::listen) are not
+    /// passed, and the snapshot, deduplication and filters of the [`ListenerConfig`] do not
+    /// apply.
+    pub fn listen_ref<F: FnMut(crate::RouteRef<'_>)>(&mut self, mut f: F) -> io::Result<usize> {
+        loop {
+            #[cfg(feature = "shutdown")]
+            let len = {
+                self.wait()?;
+                match self.route_socket.read(&mut self.buf) {
+                    Ok(len) => len,
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                    Err(e) => return Err(e),
+                }
+            };
+            #[cfg(not(feature = "shutdown"))]
+            let len = self.route_socket.read(&mut self.buf)?;
+            let count = deserialize_route_refs(&mut f, &self.buf[..len])?;
+            if count > 0 {
+                return Ok(count);
             }
         }
     }
 }
 
 /// RouteManager is used for managing routes (adding, deleting, and listing).
+///
+/// Clones are cheap and share the manager; it can be used from several threads at once.
+#[derive(Clone)]
 pub struct RouteManager {
-    _private: std::marker::PhantomData<()>,
+    defaults: crate::RouteManagerBuilder,
+    // Injected by from_socket and used by one request at a time; otherwise each request
+    // opens its own socket
+    socket: Option<Arc<Mutex<UnixStream>>>,
+    // Reused by the sysctl dumps of list
+    bufs: Arc<BufPool>,
 }
 
 impl RouteManager {
     /// Creates a new RouteManager.
     pub fn new() -> io::Result<Self> {
-        Ok(Self {
-            _private: std::marker::PhantomData,
+        Self::with_defaults(Default::default())
+    }
+    pub(crate) fn with_defaults(defaults: crate::RouteManagerBuilder) -> io::Result<Self> {
+        Ok(Self::with_parts(defaults, None))
+    }
+    /// (Unix only) Creates a manager that sends its requests through `socket`, a `PF_ROUTE`
+    /// socket, instead of opening sockets of its own, e.g. in a sandboxed process that
+    /// received the socket from a privileged parent.
+    ///
+    /// Unlike Linux, the BSDs check the privileges of the sender of each change, so adding,
+    /// deleting and updating routes may still fail with `EPERM` in an unprivileged process;
+    /// listing routes does not use the socket.
+    pub fn from_socket(socket: OwnedFd) -> io::Result<Self> {
+        Ok(Self::with_parts(
+            Default::default(),
+            Some(UnixStream::from(socket)),
+        ))
+    }
+    fn with_parts(defaults: crate::RouteManagerBuilder, socket: Option<UnixStream>) -> Self {
+        Self {
+            defaults,
+            socket: socket.map(|v| Arc::new(Mutex::new(v))),
+            bufs: Arc::new(BufPool::new(0)),
+        }
+    }
+    /// Runs `f` on the injected socket, or on a new one.
+    ///
+    /// A reply that does not arrive within the timeout of the manager fails the request with
+    /// `TimedOut`.
+    fn with_socket<T>(&self, f: impl FnOnce(&UnixStream) -> io::Result<T>) -> io::Result<T> {
+        let run = |socket: &UnixStream| {
+            let Some(timeout) = self.defaults.timeout else {
+                return f(socket);
+            };
+            socket.set_read_timeout(Some(timeout))?;
+            f(socket).map_err(|e| match e.kind() {
+                io::ErrorKind::WouldBlock => io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("no routing socket reply within {timeout:?}"),
+                ),
+                _ => e,
+            })
+        };
+        match &self.socket {
+            Some(socket) => {
+                let socket = socket.lock().unwrap();
+                drain(&socket);
+                run(&socket)
+            }
+            None => run(&create_route_socket(AF_UNSPEC as i32)?),
+        }
+    }
+    pub(crate) fn defaults(&self) -> &crate::RouteManagerBuilder {
+        &self.defaults
+    }
+    /// Builds the routing socket message of a dry run, see
+    /// [`RouteRequest`](crate::RouteRequest).
+    pub(crate) fn request(
+        &self,
+        kind: crate::RequestKind,
+        route: &Route,
+    ) -> io::Result<crate::RouteRequest> {
+        let route = &*self.defaults.apply(route);
+        let rtm_type = match kind {
+            crate::RequestKind::Add => RTM_ADD,
+            crate::RequestKind::Delete => RTM_DELETE,
+            crate::RequestKind::Update => RTM_CHANGE,
+        };
+        let rtmsg = add_or_del_route_req(route, rtm_type as u8)?;
+        let bytes = rtmsg.slice().to_vec();
+        let attrs = &bytes[mem::size_of::<rt_msghdr>()..];
+        Ok(crate::RouteRequest {
+            decoded: format!("{:?} attrs: {attrs:02x?}", rtmsg.hdr),
+            bytes,
         })
     }
     /// Returns a new instance of RouteListener.
@@ -115,20 +296,120 @@ impl RouteManager {
         RouteListener::new()
     }
     /// Lists all current routes.
-    pub fn list(&mut self) -> io::Result<Vec<Route>> {
-        list_routes()
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), err(level = "debug"))
+    )]
+    pub fn list(&self) -> io::Result<Vec<Route>> {
+        let routes = crate::retry(self.defaults.retry, || list_routes(&mut self.bufs.take()))?;
+        debug_event!(count = routes.len(), "listed routes");
+        Ok(routes)
+    }
+    /// Returns the installed route to exactly `destination/prefix`, or `None` if there is none.
+    pub fn get_route(&self, destination: IpAddr, prefix: u8) -> io::Result<Option<Route>> {
+        self.with_socket(|socket| get_route(socket, destination, prefix))
+    }
+    /// Returns the source address the kernel would choose for packets to `dest`, i.e. the
+    /// interface address of the route it uses (`route get`), or `None` if `dest` is
+    /// unreachable.
+    pub fn find_source(&self, dest: &IpAddr) -> io::Result<Option<IpAddr>> {
+        let route = self.with_socket(|socket| get_host_route(socket, dest.to_canonical()))?;
+        Ok(route.and_then(|v| v.pref_source))
+    }
+    /// Returns the MTU of the path to `dest`, or `None` if `dest` is unreachable.
+    ///
+    /// This is the MTU of the route the kernel uses for `dest` (`rmx_mtu`), which includes a
+    /// path MTU learned from ICMP, else the MTU of the output interface.
+    pub fn path_mtu(&self, dest: &IpAddr) -> io::Result<Option<u32>> {
+        let route = self.with_socket(|socket| get_host_route(socket, dest.to_canonical()))?;
+        let Some(route) = route else {
+            return Ok(None);
+        };
+        if route.mtu.is_some() {
+            return Ok(route.mtu);
+        }
+        let Some(if_index) = route.if_index else {
+            return Ok(None);
+        };
+        Ok(crate::list_interfaces()?
+            .into_iter()
+            .find(|v| v.index == if_index)
+            .map(|v| v.mtu))
     }
     /// Adds a new route.
-    pub fn add(&mut self, route: &Route) -> io::Result<()> {
-        add_route(route)
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub fn add(&self, route: &Route) -> io::Result<()> {
+        let route = &*self.defaults.apply(route);
+        crate::retry(self.defaults.retry, || {
+            self.with_socket(|socket| add_or_del_route(socket, route, RTM_ADD as u8))
+        })
+        .map_err(crate::context("add", route))
     }
     /// Deletes an existing route.
-    pub fn delete(&mut self, route: &Route) -> io::Result<()> {
-        delete_route(route)
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub fn delete(&self, route: &Route) -> io::Result<()> {
+        let route = &*self.defaults.apply(route);
+        crate::retry(self.defaults.retry, || {
+            self.with_socket(|socket| add_or_del_route(socket, route, RTM_DELETE as u8))
+        })
+        .map_err(crate::context("delete", route))
+    }
+    /// Modifies an existing route in place, without a window in which it is missing.
+    ///
+    /// The route is identified by destination, table and metric on Linux, by destination,
+    /// interface and gateway on Windows, and by destination on BSD; the remaining attributes
+    /// (e.g. the gateway on Linux and BSD, the metric on Windows) are replaced. Fails if no
+    /// such route exists.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub fn update(&self, route: &Route) -> io::Result<()> {
+        let route = &*self.defaults.apply(route);
+        crate::retry(self.defaults.retry, || {
+            self.with_socket(|socket| add_or_del_route(socket, route, RTM_CHANGE as u8))
+        })
+        .map_err(crate::context("update", route))
     }
 }
 
-fn try_get_msg_buf() -> io::Result<Vec<u8>> {
+impl FromRawFd for RouteManager {
+    /// Creates a manager that sends its requests through the `PF_ROUTE` socket `fd`, see
+    /// [`RouteManager::from_socket`].
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        Self::with_parts(Default::default(), Some(UnixStream::from_raw_fd(fd)))
+    }
+}
+
+pub(crate) fn try_get_msg_buf(net_rt: u32) -> io::Result<Vec<u8>> {
+    let mut msgs_buf = Vec::new();
+    read_msg_buf(net_rt, &mut msgs_buf)?;
+    Ok(msgs_buf)
+}
+
+/// Reads the sysctl table `net_rt` into `msgs_buf`, reusing its allocation.
+fn read_msg_buf(net_rt: u32, msgs_buf: &mut Vec<u8>) -> io::Result<()> {
     const MAX_RETRYS: usize = 3;
 
     for _ in 0..MAX_RETRYS {
@@ -139,7 +420,7 @@ fn try_get_msg_buf() -> io::Result<Vec<u8>> {
         mib[1] = AF_ROUTE;
         mib[2] = 0;
         mib[3] = 0; // family: ipv4 & ipv6
-        mib[4] = NET_RT_DUMP;
+        mib[4] = net_rt;
         // mib[5] flags: 0
 
         // see: https://github.com/golang/net/blob/ec05fdcd71141c885f3fb84c41d1c692f094ccbe/route/route.go#L126
@@ -157,7 +438,8 @@ fn try_get_msg_buf() -> io::Result<Vec<u8>> {
             return Err(io::Error::last_os_error());
         }
 
-        let mut msgs_buf: Vec<u8> = vec![0; len];
+        msgs_buf.clear();
+        msgs_buf.resize(len, 0);
 
         if unsafe {
             sysctl(
@@ -173,37 +455,117 @@ fn try_get_msg_buf() -> io::Result<Vec<u8>> {
             // will retry return error if
             continue;
         } else {
-            return Ok(msgs_buf);
+            // The table may have shrunk in between
+            msgs_buf.truncate(len);
+            return Ok(());
         }
     }
 
     Err(io::Error::other("Failed to get routing table"))
 }
 
-fn list_routes() -> io::Result<Vec<Route>> {
-    let msgs_buf = try_get_msg_buf()?;
+fn list_routes(msgs_buf: &mut Vec<u8>) -> io::Result<Vec<Route>> {
+    read_msg_buf(NET_RT_DUMP, msgs_buf)?;
 
     let mut routes = vec![];
     deserialize_res(
-        |rtm_type, route| {
-            if rtm_type == RTM_GET {
+        |rt_hdr, route| {
+            if rt_hdr.rtm_type as u32 == RTM_GET {
                 routes.push(route);
             }
         },
-        &msgs_buf,
+        msgs_buf,
     )?;
     Ok(routes)
 }
 
-fn add_route(route: &Route) -> io::Result<()> {
-    add_or_del_route(route, RTM_ADD as u8)
+/// Sequence numbers of requests, to pick their replies out of the socket.
+static SEQ: AtomicI32 = AtomicI32::new(1);
+
+fn get_route(socket: &UnixStream, destination: IpAddr, prefix: u8) -> io::Result<Option<Route>> {
+    let wanted = Route::new(crate::network_address(destination, prefix), prefix);
+    let rtm_addrs = RTA_DST | RTA_NETMASK;
+    let mut rtmsg = route_to_m_rtmsg(RTM_GET as u8, rtm_addrs, &wanted)?;
+    let mut rtm_flags = RTF_UP;
+    if (destination.is_ipv4() && prefix == 32) || (destination.is_ipv6() && prefix == 128) {
+        rtm_flags |= RTF_HOST;
+    }
+    rtmsg.hdr.rtm_addrs = rtm_addrs as i32;
+    rtmsg.hdr.rtm_flags = rtm_flags as i32;
+    // Without an exact match the kernel may answer with a covering route
+    Ok(send_get(socket, rtmsg)?.filter(|v| v.same_destination(&wanted)))
+}
+
+/// Asks the kernel for the route it uses to reach the host `dest`, together with the
+/// interface and its address (`route get`).
+fn get_host_route(socket: &UnixStream, dest: IpAddr) -> io::Result<Option<Route>> {
+    let max_prefix = if dest.is_ipv4() { 32 } else { 128 };
+    let host = Route::new(dest, max_prefix);
+    let mut rtmsg = route_to_m_rtmsg(RTM_GET as u8, RTA_DST, &host)?;
+    // An empty RTA_IFP makes the kernel report the interface and its address (RTA_IFA)
+    let attr_offset = rtmsg.hdr.rtm_msglen as usize - std::mem::size_of::<rt_msghdr>();
+    let attr_offset = put_ifa_addr(attr_offset, &mut rtmsg, 0)?;
+    rtmsg.hdr.rtm_msglen = (std::mem::size_of::<rt_msghdr>() + attr_offset) as u16;
+    rtmsg.hdr.rtm_addrs = (RTA_DST | RTA_IFP) as i32;
+    rtmsg.hdr.rtm_flags = (RTF_UP | RTF_HOST) as i32;
+    match send_get(socket, rtmsg) {
+        Err(e) if crate::os_error(&e) == Some(libc::ENETUNREACH) => Ok(None),
+        rs => rs,
+    }
+}
+
+/// Sends a `RTM_GET` request and returns the route of the reply, or `None` if there is none.
+fn send_get(socket: &UnixStream, mut rtmsg: m_rtmsg) -> io::Result<Option<Route>> {
+    rtmsg.hdr.rtm_type = RTM_GET as u8;
+    rtmsg.hdr.rtm_version = RTM_VERSION as u8;
+    let mut found = None;
+    match send_request(socket, rtmsg, |_, route| found = Some(route)) {
+        Err(e) if crate::is_route_not_found(&e) => Ok(None),
+        rs => rs.map(|_| found),
+    }
 }
-fn delete_route(route: &Route) -> io::Result<()> {
-    add_or_del_route(route, RTM_DELETE as u8)
+
+/// Sends `rtmsg` with a new sequence number and passes the route of the reply to `reply_fn`.
+fn send_request<F: FnMut(&rt_msghdr, Route)>(
+    mut socket: &UnixStream,
+    mut rtmsg: m_rtmsg,
+    reply_fn: F,
+) -> io::Result<()> {
+    let seq = SEQ.fetch_add(1, Ordering::Relaxed);
+    rtmsg.hdr.rtm_seq = seq;
+    socket.write_all(rtmsg.slice())?;
+    let pid = std::process::id() as libc::pid_t;
+    let mut buf = vec![0u8; recv_buf_len(socket)];
+    loop {
+        // The socket also receives every other routing message; skip to our reply
+        let len = socket.read(&mut buf)?;
+        let Some(rt_hdr) = read_rt_msghdr(&buf[..len]) else {
+            continue;
+        };
+        if rt_hdr.rtm_pid != pid || rt_hdr.rtm_seq != seq {
+            continue;
+        }
+        return deserialize_res(reply_fn, &buf[..len]);
+    }
+}
+
+/// Discards messages queued on a reused socket, e.g. the copy of a request that failed.
+fn drain(socket: &UnixStream) {
+    let mut buf = [0u8; 2048];
+    while unsafe {
+        libc::recv(
+            socket.as_raw_fd(),
+            buf.as_mut_ptr().cast(),
+            buf.len(),
+            libc::MSG_DONTWAIT,
+        )
+    } > 0
+    {}
 }
 
 fn add_or_del_route_req(route: &Route, rtm_type: u8) -> io::Result<m_rtmsg> {
-    let mut rtm_flags = RTF_STATIC | RTF_UP;
+    let route = &*route.input_form();
+    let mut rtm_flags = RTF_STATIC | RTF_UP | route.raw_flags;
 
     if route.gateway.is_some() {
         rtm_flags |= RTF_GATEWAY;
@@ -216,74 +578,94 @@ fn add_or_del_route_req(route: &Route, rtm_type: u8) -> io::Result<m_rtmsg> {
     }
 
     let mut rtm_addrs = RTA_DST | RTA_NETMASK;
-    if rtm_type == RTM_ADD as u8 || route.gateway.is_some() {
+    if rtm_type != RTM_DELETE as u8 || route.gateway.is_some() {
         rtm_addrs |= RTA_GATEWAY;
     }
     #[cfg(target_os = "netbsd")]
     if route.if_index.is_some() || route.if_name.is_some() {
         rtm_addrs |= RTA_IFP;
     }
-    #[cfg(any(target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
     if route.pref_source.is_some() {
         rtm_addrs |= RTA_IFA;
     }
-    let mut rtmsg: m_rtmsg = route_to_m_rtmsg(rtm_type, route)?;
+    for attr in &route.raw_attrs {
+        let kind = u32::from(attr.kind());
+        if kind >= RTAX_MAX || rtm_addrs & (1 << kind) != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("raw attribute {kind} is not a free RTAX_* slot of the route message"),
+            ));
+        }
+        rtm_addrs |= 1 << kind;
+    }
+    let mut rtmsg: m_rtmsg = route_to_m_rtmsg(rtm_type, rtm_addrs, route)?;
 
     rtmsg.hdr.rtm_addrs = rtm_addrs as i32;
     rtmsg.hdr.rtm_seq = 1;
     rtmsg.hdr.rtm_flags = rtm_flags as i32;
     rtmsg.hdr.rtm_type = rtm_type;
     rtmsg.hdr.rtm_version = RTM_VERSION as u8;
+    debug_event!(
+        rtm_type,
+        rtm_addrs,
+        rtm_flags,
+        msglen = rtmsg.hdr.rtm_msglen,
+        "built route message"
+    );
     Ok(rtmsg)
 }
 
-fn add_or_del_route(route: &Route, rtm_type: u8) -> io::Result<()> {
+fn add_or_del_route(socket: &UnixStream, route: &Route, rtm_type: u8) -> io::Result<()> {
     let rtmsg = add_or_del_route_req(route, rtm_type)?;
-    let fd = unsafe { socket(PF_ROUTE as i32, SOCK_RAW as i32, AF_UNSPEC as i32) };
-    if fd < 0 {
-        return Err(io::Error::last_os_error());
-    }
-
-    let mut route_fd = unsafe { UnixStream::from_raw_fd(fd) };
-
-    route_fd.write_all(rtmsg.slice())?;
-
-    let mut buf = [0u8; std::mem::size_of::<m_rtmsg>()];
-
-    let len = route_fd.read(&mut buf)?;
-    deserialize_res(|_, _| {}, &buf[..len])?;
-
-    Ok(())
+    send_request(socket, rtmsg, |_, _| {})
 }
 
-fn route_to_m_rtmsg(_rtm_type: u8, value: &Route) -> io::Result<m_rtmsg> {
+fn route_to_m_rtmsg(rtm_type: u8, rtm_addrs: u32, value: &Route) -> io::Result<m_rtmsg> {
     value.check()?;
     let mut rtmsg = m_rtmsg {
         hdr: rt_msghdr::default(),
         attrs: [0u8; 512],
     };
     let if_index = value.get_index();
-    let mut attr_offset = put_ip_addr(0, &mut rtmsg, value.destination)?;
-
-    if let Some(gateway) = value.gateway {
-        attr_offset = put_ip_addr(attr_offset, &mut rtmsg, gateway)?;
-    }
-
-    if _rtm_type == RTM_ADD as u8 && value.gateway.is_none() {
-        if let Some(if_index) = if_index {
-            attr_offset = put_ifa_addr(attr_offset, &mut rtmsg, if_index)?;
+    let mut attr_offset = 0;
+    // The sockaddrs must be laid out in RTAX order, one for each bit set in rtm_addrs
+    for idx in 0..RTAX_MAX {
+        if rtm_addrs & (1 << idx) == 0 {
+            continue;
+        }
+        if let Some(attr) = value.raw_attrs.iter().find(|v| v.kind() as u32 == idx) {
+            attr_offset = put_raw_addr(attr_offset, &mut rtmsg, attr.value())?;
+            continue;
+        }
+        match idx {
+            RTAX_DST => {
+                attr_offset = put_ip_addr(attr_offset, &mut rtmsg, value.destination)?;
+            }
+            RTAX_GATEWAY => {
+                if let Some(gateway) = value.gateway {
+                    attr_offset =
+                        put_gateway_addr(attr_offset, &mut rtmsg, gateway, value.gateway_scope_id)?;
+                } else if rtm_type != RTM_DELETE as u8 {
+                    if let Some(if_index) = if_index {
+                        attr_offset = put_ifa_addr(attr_offset, &mut rtmsg, if_index)?;
+                    }
+                }
+            }
+            RTAX_NETMASK => {
+                attr_offset = put_ip_addr(attr_offset, &mut rtmsg, value.mask())?;
+            }
+            RTAX_IFP => {
+                if let Some(if_index) = if_index {
+                    attr_offset = put_ifa_addr(attr_offset, &mut rtmsg, if_index)?;
+                }
+            }
+            RTAX_IFA => {
+                if let Some(source_addr) = value.pref_source {
+                    attr_offset = put_ip_addr(attr_offset, &mut rtmsg, source_addr)?;
+                }
+            }
+            _ => {}
         }
-    }
-
-    attr_offset = put_ip_addr(attr_offset, &mut rtmsg, value.mask())?;
-
-    #[cfg(any(target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
-    if let Some(source_addr) = value.pref_source {
-        attr_offset = put_ip_addr(attr_offset, &mut rtmsg, source_addr)?;
-    }
-
-    if let Some(if_index) = if_index {
-        attr_offset = put_ifa_addr(attr_offset, &mut rtmsg, if_index)?;
     }
 
     let msg_len = std::mem::size_of::<rt_msghdr>() + attr_offset;
@@ -292,6 +674,7 @@ fn route_to_m_rtmsg(_rtm_type: u8, value: &Route) -> io::Result<m_rtmsg> {
     #[cfg(target_os = "openbsd")]
     {
         rtmsg.hdr.rtm_hdrlen = std::mem::size_of::<rt_msghdr>() as u16;
+        rtmsg.hdr.rtm_priority = value.priority;
     }
     rtmsg.hdr.rtm_msglen = msg_len as u16;
     Ok(rtmsg)
@@ -314,6 +697,20 @@ fn put_ifa_addr(mut attr_offset: usize, rtmsg: &mut m_rtmsg, if_index: u32) -> i
     Ok(attr_offset)
 }
 
+fn put_raw_addr(mut attr_offset: usize, rtmsg: &mut m_rtmsg, sa_bytes: &[u8]) -> io::Result<usize> {
+    let sa_len = sa_bytes.len();
+    if attr_offset + sa_size(sa_len) > rtmsg.attrs.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "raw attributes exceed the route message size",
+        ));
+    }
+    rtmsg.attrs[attr_offset..attr_offset + sa_len].copy_from_slice(sa_bytes);
+
+    attr_offset += sa_size(sa_len);
+    Ok(attr_offset)
+}
+
 fn put_ip_addr(mut attr_offset: usize, rtmsg: &mut m_rtmsg, addr: IpAddr) -> io::Result<usize> {
     match addr {
         IpAddr::V4(addr) => {
@@ -340,13 +737,37 @@ fn put_ip_addr(mut attr_offset: usize, rtmsg: &mut m_rtmsg, addr: IpAddr) -> io:
     Ok(attr_offset)
 }
 
+fn put_gateway_addr(
+    attr_offset: usize,
+    rtmsg: &mut m_rtmsg,
+    addr: IpAddr,
+    scope_id: Option<u32>,
+) -> io::Result<usize> {
+    let (IpAddr::V6(addr), Some(scope_id)) = (addr, scope_id) else {
+        return put_ip_addr(attr_offset, rtmsg, addr);
+    };
+    let sa_len = std::mem::size_of::<sockaddr_in6>();
+    let mut sa_in: sockaddr_in6 = addr.into();
+    sa_in.sin6_scope_id = scope_id;
+
+    let sa_ptr = &sa_in as *const sockaddr_in6 as *const u8;
+    let sa_bytes = unsafe { std::slice::from_raw_parts(sa_ptr, sa_len) };
+    rtmsg.attrs[attr_offset..attr_offset + sa_len].copy_from_slice(sa_bytes);
+    Ok(attr_offset + sa_size(sa_len))
+}
+
 #[cfg(target_os = "macos")]
 fn sa_size(len: usize) -> usize {
     len
 }
 
 // NetBSD uses the same socket address alignment as FreeBSD and OpenBSD
-#[cfg(any(target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+#[cfg(any(
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+))]
 fn sa_size(sa_len: usize) -> usize {
     // See https://github.com/freebsd/freebsd-src/blob/7e51bc6cdd5c317109e25b0b64230d00d68dceb3/contrib/bsnmp/lib/support.h#L89
     // NetBSD follows the same alignment rules as FreeBSD and OpenBSD
@@ -356,35 +777,121 @@ fn sa_size(sa_len: usize) -> usize {
     1 + ((sa_len - 1) | (std::mem::size_of::<libc::c_long>() - 1))
 }
 
-fn deserialize_res_change<F: FnMut(RouteChange)>(mut add_fn: F, msgs_buf: &[u8]) -> io::Result<()> {
+fn deserialize_res_change<F: FnMut(RouteChange, EventOrigin)>(
+    mut add_fn: F,
+    msgs_buf: &[u8],
+) -> io::Result<()> {
     deserialize_res(
-        |rtm_type, route| {
-            let route = match rtm_type {
+        |rt_hdr, route| {
+            let origin = EventOrigin {
+                pid: Some(rt_hdr.rtm_pid as u32),
+                seq: Some(rt_hdr.rtm_seq as u32),
+                system_route: rt_hdr.rtm_flags as u32 & RTF_STATIC == 0,
+            };
+            let route = match rt_hdr.rtm_type as u32 {
                 RTM_ADD => RouteChange::Add(route),
                 RTM_DELETE => RouteChange::Delete(route),
-                RTM_CHANGE => RouteChange::Change(route),
+                RTM_CHANGE => RouteChange::Change {
+                    old: None,
+                    new: route,
+                },
                 _ => return,
             };
-            add_fn(route);
+            add_fn(route, origin);
         },
         msgs_buf,
     )
 }
 
-fn deserialize_res<F: FnMut(u32, Route)>(mut add_fn: F, msgs_buf: &[u8]) -> io::Result<()> {
+/// Passes the route change messages in `msgs_buf` to `ref_fn` without building routes and
+/// returns their number.
+fn deserialize_route_refs<F: FnMut(crate::RouteRef<'_>)>(
+    mut ref_fn: F,
+    msgs_buf: &[u8],
+) -> io::Result<usize> {
+    let mut count = 0;
+    for_each_route_msg(
+        |rt_hdr, msg| {
+            let kind = match rt_hdr.rtm_type as u32 {
+                RTM_ADD => crate::RouteChangeKind::Add,
+                RTM_DELETE => crate::RouteChangeKind::Delete,
+                RTM_CHANGE => crate::RouteChangeKind::Change,
+                _ => return Ok(()),
+            };
+            let Some(addrs) = route_addrs(rt_hdr, &msg[mem::size_of::<rt_msghdr>()..]) else {
+                return Ok(());
+            };
+            ref_fn(crate::RouteRef {
+                kind,
+                destination: addrs.destination,
+                prefix: addrs.prefix,
+                gateway: addrs.gateway,
+                if_index: Some(rt_hdr.rtm_index as u32),
+                msg,
+            });
+            count += 1;
+            Ok(())
+        },
+        msgs_buf,
+    )?;
+    Ok(count)
+}
+
+/// Parses a single route message, see [`crate::RouteRef::to_owned`].
+pub(crate) fn parse_route_msg(msg: &[u8]) -> io::Result<Route> {
+    let not_route = || io::Error::new(io::ErrorKind::InvalidData, "not a route message");
+    let rt_hdr = read_rt_msghdr(msg).ok_or_else(not_route)?;
+    message_to_route(&rt_hdr, &msg[mem::size_of::<rt_msghdr>()..]).ok_or_else(not_route)
+}
+
+/// Length of the header shared by all routing messages: `msglen`, `version` and `type`.
+const MSG_PREFIX_LEN: usize = 4;
+
+fn deserialize_res<F: FnMut(&rt_msghdr, Route)>(mut add_fn: F, msgs_buf: &[u8]) -> io::Result<()> {
+    for_each_route_msg(
+        |rt_hdr, msg| {
+            let rt_msg = &msg[std::mem::size_of::<rt_msghdr>()..];
+            if let Some(route) = message_to_route(rt_hdr, rt_msg) {
+                add_fn(rt_hdr, route);
+            }
+            Ok(())
+        },
+        msgs_buf,
+    )
+}
+
+/// Passes the route messages in `msgs_buf` that are not filtered out to `msg_fn`, whole.
+fn for_each_route_msg<F: FnMut(&rt_msghdr, &[u8]) -> io::Result<()>>(
+    mut msg_fn: F,
+    msgs_buf: &[u8],
+) -> io::Result<()> {
     let mut offset = 0;
-    while offset + std::mem::size_of::<rt_msghdr>() <= msgs_buf.len() {
+    while offset + MSG_PREFIX_LEN <= msgs_buf.len() {
         let buf = &msgs_buf[offset..];
-
-        let rt_hdr = unsafe { &*buf.as_ptr().cast::<rt_msghdr>() };
-        let msg_len = rt_hdr.rtm_msglen as usize;
+        let msg_len = u16::from_ne_bytes([buf[0], buf[1]]) as usize;
         if msg_len == 0 {
             break;
         }
+        if msg_len < MSG_PREFIX_LEN || msg_len > buf.len() {
+            return Err(malformed(format!(
+                "route message of {msg_len} bytes, {} available",
+                buf.len()
+            )));
+        }
+        let buf = &buf[..msg_len];
         offset += msg_len;
-        if rt_hdr.rtm_version as u32 != RTM_VERSION {
+        if buf[2] as u32 != RTM_VERSION {
+            continue;
+        }
+        if is_link_event(buf[3] as u32) {
+            crate::InterfaceCache::invalidate();
             continue;
         }
+        // Address and interface messages have shorter headers of their own
+        let Some(rt_hdr) = read_rt_msghdr(buf) else {
+            continue;
+        };
+        let rt_hdr = &rt_hdr;
         #[cfg(target_os = "openbsd")]
         if (rt_hdr.rtm_flags as u32 & (RTF_GATEWAY | RTF_STATIC | RTF_LLINFO)) == 0 {
             continue;
@@ -401,6 +908,7 @@ fn deserialize_res<F: FnMut(u32, Route)>(mut add_fn: F, msgs_buf: &[u8]) -> io::
         }
 
         if rt_hdr.rtm_errno != 0 {
+            debug_event!(errno = rt_hdr.rtm_errno, "route message error reply");
             return Err(io::Error::from_raw_os_error(rt_hdr.rtm_errno));
         }
 
@@ -409,17 +917,92 @@ fn deserialize_res<F: FnMut(u32, Route)>(mut add_fn: F, msgs_buf: &[u8]) -> io::
             continue;
         }
 
-        let rt_msg = &buf[std::mem::size_of::<rt_msghdr>()..msg_len];
-
-        if let Some(route) = message_to_route(rt_hdr, rt_msg) {
-            add_fn(rt_hdr.rtm_type as u32, route);
-        }
+        msg_fn(rt_hdr, buf)?;
     }
     Ok(())
 }
 
+/// Reads the header of a route message, or `None` if `buf` is too short for one.
+fn read_rt_msghdr(buf: &[u8]) -> Option<rt_msghdr> {
+    if buf.len() < mem::size_of::<rt_msghdr>() {
+        return None;
+    }
+    Some(unsafe { std::ptr::read_unaligned(buf.as_ptr().cast()) })
+}
+
+/// The error for a routing message that does not fit the buffer it was read into.
+fn malformed(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn is_link_event(rtm_type: u32) -> bool {
+    #[cfg(target_os = "macos")]
+    return rtm_type == RTM_IFINFO;
+    #[cfg(not(target_os = "macos"))]
+    return rtm_type == RTM_IFINFO || rtm_type == RTM_IFANNOUNCE;
+}
+
+/// The addresses of a route message, parsed without allocating.
+struct RouteAddrs<'a> {
+    destination: IpAddr,
+    prefix: u8,
+    gateway: Option<IpAddr>,
+    gateway_scope_id: Option<u32>,
+    // Each sockaddr is kept as its `sa_len` bytes
+    sockaddrs: [Option<&'a [u8]>; RTAX_MAX as usize],
+}
+
 fn message_to_route(hdr: &rt_msghdr, msg: &[u8]) -> Option<Route> {
+    let addrs = route_addrs(hdr, msg)?;
+    let route_addresses = addrs.sockaddrs;
+    let mut pref_source = None;
+    if hdr.rtm_addrs & (1 << RTAX_IFA) != 0 {
+        if let Some(ifa_sa) = route_addresses[RTAX_IFA as usize] {
+            pref_source = sa_to_ip(ifa_sa);
+        }
+    }
+    // Keep the sockaddrs that are not modelled by Route
+    let mut raw_attrs = Vec::new();
+    for (idx, sa) in route_addresses.iter().enumerate() {
+        let Some(sa) = sa else {
+            continue;
+        };
+        match idx as u32 {
+            RTAX_DST | RTAX_GATEWAY | RTAX_NETMASK | RTAX_IFP => continue,
+            RTAX_IFA => continue,
+            _ => {}
+        }
+        raw_attrs.push(RawAttribute::new(idx as u16, sa.to_vec()));
+    }
+
+    Some(Route {
+        destination: addrs.destination,
+        prefix: addrs.prefix,
+        gateway: addrs.gateway,
+        gateway_scope_id: addrs.gateway_scope_id,
+        pref_source,
+        if_name: crate::route_if_name(hdr.rtm_index as u32),
+        if_index: Some(hdr.rtm_index as u32),
+        raw_attrs,
+        raw_flags: 0,
+        mtu: rt_metric(hdr.rtm_rmx.rmx_mtu).and_then(|v| u32::try_from(v).ok()),
+        // The kernel reports the expiry in wall-clock seconds, 0 meaning never
+        expire: rt_metric(hdr.rtm_rmx.rmx_expire).map(|v| UNIX_EPOCH + Duration::from_secs(v)),
+        #[cfg(target_os = "openbsd")]
+        priority: hdr.rtm_priority & RTP_MASK as u8,
+        reported: Reported {
+            // RTF_DONE only marks a message as processed
+            flags: Some(hdr.rtm_flags as u32 & !RTF_DONE),
+            #[cfg(target_os = "openbsd")]
+            fib: Some(hdr.rtm_tableid as u32),
+        },
+    })
+}
+
+/// Reads the sockaddrs of a route message, or `None` if it has no usable destination.
+fn route_addrs<'a>(hdr: &rt_msghdr, msg: &'a [u8]) -> Option<RouteAddrs<'a>> {
     let mut gateway = None;
+    let mut gateway_scope_id = None;
 
     // check if message has no destination
     if hdr.rtm_addrs & (1 << RTAX_DST) == 0 {
@@ -431,7 +1014,7 @@ fn message_to_route(hdr: &rt_msghdr, msg: &[u8]) -> Option<Route> {
 
     // See https://opensource.apple.com/source/network_cmds/network_cmds-606.40.2/netstat.tproj/route.c.auto.html,
     // function `get_rtaddrs()`
-    let mut route_addresses = [None; RTAX_MAX as usize];
+    let mut route_addresses: [Option<&[u8]>; RTAX_MAX as usize] = [None; RTAX_MAX as usize];
     let mut cur_pos = 0;
     for (idx, item) in route_addresses
         .iter_mut()
@@ -439,35 +1022,36 @@ fn message_to_route(hdr: &rt_msghdr, msg: &[u8]) -> Option<Route> {
         .take(RTAX_MAX as usize)
     {
         if hdr.rtm_addrs & (1 << idx) != 0 {
-            let buf = &msg[cur_pos..];
-            if buf.len() < std::mem::size_of::<sockaddr>() {
+            // A zero-length netmask may be left out at the end of the message
+            let Some(sa_len) = msg.get(cur_pos).map(|v| *v as usize) else {
                 continue;
+            };
+            if cur_pos + sa_len > msg.len() {
+                debug_event!(idx, sa_len, "sockaddr exceeds the route message, skipped");
+                return None;
             }
-            assert!(buf.len() >= std::mem::size_of::<sockaddr>());
-            let sa: &sockaddr = unsafe { &*(buf.as_ptr() as *const sockaddr) };
-            assert!(buf.len() >= sa.sa_len as usize);
-            *item = Some(sa);
+            *item = Some(&msg[cur_pos..cur_pos + sa_len]);
 
-            // NetBSD uses the same alignment as FreeBSD and OpenBSD
-            #[cfg(any(target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+            // NetBSD and DragonFly use the same alignment as FreeBSD and OpenBSD
+            #[cfg(not(target_os = "macos"))]
             {
-                cur_pos += sa_size(sa.sa_len as usize);
+                cur_pos += sa_size(sa_len);
             }
             #[cfg(target_os = "macos")]
             {
                 // see ROUNDUP() macro in the route.c file linked above.
                 // The len needs to be a multiple of 4bytes
-                let aligned_len = if sa.sa_len == 0 {
+                let aligned_len = if sa_len == 0 {
                     4
                 } else {
-                    ((sa.sa_len - 1) | 0x3) + 1
+                    ((sa_len - 1) | 0x3) + 1
                 };
-                cur_pos += aligned_len as usize;
+                cur_pos += aligned_len;
             }
         }
     }
 
-    let destination = sa_to_ip(route_addresses[RTAX_DST as usize]?)?;
+    let destination = route_addresses[RTAX_DST as usize].and_then(sa_to_ip)?;
     let mut prefix = match destination {
         IpAddr::V4(_) => 32,
         IpAddr::V6(_) => 128,
@@ -495,7 +1079,18 @@ fn message_to_route(hdr: &rt_msghdr, msg: &[u8]) -> Option<Route> {
                 let segs = v6gw.segments();
                 gateway = Some(IpAddr::V6(Ipv6Addr::new(
                     segs[0], 0, segs[2], segs[3], segs[4], segs[5], segs[6], segs[7],
-                )))
+                )));
+                // Prefer sin6_scope_id, which newer kernels fill in instead
+                let offset = mem::offset_of!(sockaddr_in6, sin6_scope_id);
+                let scope_id = gw_sa
+                    .get(offset..offset + 4)
+                    .and_then(|v| <[u8; 4]>::try_from(v).ok())
+                    .map(u32::from_ne_bytes)
+                    .filter(|v| *v != 0)
+                    .unwrap_or(segs[1] as u32);
+                if scope_id != 0 {
+                    gateway_scope_id = Some(scope_id);
+                }
             }
         }
     }
@@ -506,42 +1101,57 @@ fn message_to_route(hdr: &rt_msghdr, msg: &[u8]) -> Option<Route> {
             None => prefix = 0,
             // Yes, apparently a 0 prefixlen is encoded as having an sa_len of 0
             // (at least in some cases).
-            Some(sa) if sa.sa_len == 0 => prefix = 0,
-            Some(sa) => match destination {
-                IpAddr::V4(_) => {
-                    let mask_sa: &sockaddr_in = unsafe { mem::transmute(sa) };
-                    prefix = u32::from_be(mask_sa.sin_addr.s_addr).leading_ones() as u8;
-                }
-                IpAddr::V6(_) => {
-                    let mask_sa: &sockaddr_in6 = unsafe { mem::transmute(sa) };
-                    // sin6_addr.__u6_addr is a union that represents the 16 v6 bytes either as
-                    // 16 u8's or 16 u16's or 4 u32's. So we need the unsafe here because of the union
-                    prefix = u128::from_be_bytes(unsafe { mask_sa.sin6_addr.__u6_addr.__u6_addr8 })
-                        .leading_ones() as u8;
-                }
-            },
-        }
-    }
-    #[cfg(any(target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
-    let mut pref_source = None;
-    #[cfg(any(target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
-    if hdr.rtm_addrs & (1 << RTAX_IFA) != 0 {
-        if let Some(ifa_sa) = route_addresses[RTAX_IFA as usize] {
-            pref_source = sa_to_ip(ifa_sa);
+            Some([]) => prefix = 0,
+            Some(sa_bytes) => {
+                // Netmasks may be truncated after their last non-zero byte, the bytes past
+                // sa_len belong to the next sockaddr
+                let mask = match destination {
+                    IpAddr::V4(_) => {
+                        let mut octets = [0u8; 4];
+                        let offset = mem::offset_of!(sockaddr_in, sin_addr);
+                        for (dst, src) in octets.iter_mut().zip(sa_bytes.iter().skip(offset)) {
+                            *dst = *src;
+                        }
+                        IpAddr::from(octets)
+                    }
+                    IpAddr::V6(_) => {
+                        let mut octets = [0u8; 16];
+                        let offset = mem::offset_of!(sockaddr_in6, sin6_addr);
+                        for (dst, src) in octets.iter_mut().zip(sa_bytes.iter().skip(offset)) {
+                            *dst = *src;
+                        }
+                        IpAddr::from(octets)
+                    }
+                };
+                // `route add -netmask` accepts masks that no prefix length describes
+                prefix = match crate::mask_to_prefix(mask) {
+                    Ok(prefix) => prefix,
+                    Err(_) => {
+                        debug_event!(
+                            destination = %destination,
+                            mask = %mask,
+                            "route with a non-contiguous netmask skipped"
+                        );
+                        return None;
+                    }
+                };
+            }
         }
     }
-
-    Some(Route {
+    Some(RouteAddrs {
         destination,
         prefix,
         gateway,
-        #[cfg(any(target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
-        pref_source,
-        if_name: if_index_to_name(hdr.rtm_index as u32).ok(),
-        if_index: Some(hdr.rtm_index as u32),
+        gateway_scope_id,
+        sockaddrs: route_addresses,
     })
 }
 
+/// Reads an `rt_metrics` value, whose width differs between the BSDs; 0 means unset.
+fn rt_metric<T: TryInto<u64>>(value: T) -> Option<u64> {
+    value.try_into().ok().filter(|v| *v != 0)
+}
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 #[allow(non_camel_case_types)]
@@ -582,21 +1192,20 @@ impl Default for rt_msghdr {
     }
 }
 
-fn sa_to_ip(sa: &sockaddr) -> Option<IpAddr> {
-    match sa.sa_family as u32 {
+/// Reads the address of the sockaddr `sa`, or `None` if it is not an IP address or too short
+/// to hold one.
+fn sa_to_ip(sa: &[u8]) -> Option<IpAddr> {
+    match *sa.get(mem::offset_of!(sockaddr, sa_family))? as u32 {
         AF_INET => {
-            assert!(sa.sa_len as usize >= std::mem::size_of::<sockaddr_in>());
-            let inet: &sockaddr_in = unsafe { std::mem::transmute(sa) };
-            let octets: [u8; 4] = inet.sin_addr.s_addr.to_ne_bytes();
+            let offset = mem::offset_of!(sockaddr_in, sin_addr);
+            let octets: [u8; 4] = sa.get(offset..offset + 4)?.try_into().ok()?;
             Some(IpAddr::from(octets))
         }
         AF_INET6 => {
-            assert!(sa.sa_len as usize >= std::mem::size_of::<sockaddr_in6>());
-            let inet6: &sockaddr_in6 = unsafe { mem::transmute(sa) };
-            let octets: [u8; 16] = unsafe { inet6.sin6_addr.__u6_addr.__u6_addr8 };
+            let offset = mem::offset_of!(sockaddr_in6, sin6_addr);
+            let octets: [u8; 16] = sa.get(offset..offset + 16)?.try_into().ok()?;
             Some(IpAddr::from(octets))
         }
-        AF_LINK => None,
         _ => None,
     }
 }
@@ -634,8 +1243,88 @@ impl From<Ipv6Addr> for sockaddr_in6 {
     }
 }
 
-fn create_route_socket() -> io::Result<UnixStream> {
-    let fd = unsafe { socket(PF_ROUTE as i32, SOCK_RAW as i32, AF_UNSPEC as i32) };
+/// Routing socket protocol selecting the address families a listener receives.
+fn listener_family(config: &ListenerConfig) -> io::Result<i32> {
+    config.check_families()?;
+    Ok(match (config.ipv4, config.ipv6) {
+        (true, false) => AF_INET as i32,
+        (false, true) => AF_INET6 as i32,
+        _ => AF_UNSPEC as i32,
+    })
+}
+
+/// Normalized flags from the `rtm_flags` of the message `route` was read from.
+pub(crate) fn reported_flags(route: &Route) -> crate::RouteFlags {
+    use crate::RouteFlags;
+    let Some(bits) = route.reported.flags else {
+        return RouteFlags::empty();
+    };
+    #[cfg(any(target_os = "macos", target_os = "dragonfly"))]
+    let cloned = RTF_WASCLONED;
+    #[cfg(target_os = "openbsd")]
+    let cloned = RTF_CLONED;
+    // FreeBSD and NetBSD no longer clone routes
+    #[cfg(any(target_os = "freebsd", target_os = "netbsd"))]
+    let cloned = 0;
+    let mut flags = RouteFlags::empty();
+    for (rtf, flag) in [
+        (RTF_UP, RouteFlags::UP),
+        (RTF_STATIC, RouteFlags::STATIC),
+        (RTF_DYNAMIC, RouteFlags::DYNAMIC),
+        (RTF_REJECT, RouteFlags::REJECT),
+        (RTF_BLACKHOLE, RouteFlags::BLACKHOLE),
+        (cloned, RouteFlags::CLONED),
+    ] {
+        flags.set(flag, rtf != 0 && bits & rtf != 0);
+    }
+    flags
+}
+
+/// Whether the route only applies to lookups bound to its interface (`RTF_IFSCOPE`).
+#[cfg(target_os = "macos")]
+pub(crate) fn is_interface_scoped(route: &Route) -> bool {
+    route.reported.flags.is_some_and(|v| v & RTF_IFSCOPE != 0)
+}
+
+/// The flags reserved for routing protocols, which an owner tag may consist of.
+#[cfg(target_os = "netbsd")]
+pub(crate) const OWNER_FLAGS: u32 = RTF_PROTO1 | RTF_PROTO2;
+#[cfg(not(target_os = "netbsd"))]
+pub(crate) const OWNER_FLAGS: u32 = RTF_PROTO1 | RTF_PROTO2 | RTF_PROTO3;
+
+/// Whether an event was caused by a request sent from this process.
+pub(crate) fn is_own_origin(origin: &EventOrigin) -> bool {
+    origin.pid == Some(std::process::id())
+}
+
+/// Largest routing message: its length is a `u16`.
+const MAX_MSG_LEN: usize = u16::MAX as usize;
+
+/// Returns the size of a buffer that holds any message `socket` can receive.
+///
+/// Each read returns a single message, whose excess is discarded if the buffer is too
+/// short. A message larger than the receive buffer of the socket is never queued, so its
+/// size (`SO_RCVBUF`) suffices.
+pub(crate) fn recv_buf_len<S: AsRawFd>(socket: &S) -> usize {
+    let mut size: libc::c_int = 0;
+    let mut len = mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let rs = unsafe {
+        libc::getsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_RCVBUF,
+            (&mut size as *mut libc::c_int).cast(),
+            &mut len,
+        )
+    };
+    if rs < 0 {
+        return MAX_MSG_LEN;
+    }
+    (size.max(0) as usize).clamp(4096, MAX_MSG_LEN)
+}
+
+fn create_route_socket(family: i32) -> io::Result<UnixStream> {
+    let fd = unsafe { socket(PF_ROUTE as i32, SOCK_RAW as i32, family) };
     if fd < 0 {
         return Err(io::Error::last_os_error());
     }
@@ -0,0 +1,76 @@
+use crate::{EventOrigin, ListenerConfig, Route, RouteChange};
+use std::convert::Infallible;
+use std::io;
+
+/// AsyncRouteListener for asynchronously receiving route change events.
+pub struct AsyncRouteListener {
+    never: Infallible,
+}
+impl AsyncRouteListener {
+    /// Creates a new AsyncRouteListener.
+    pub fn new() -> io::Result<Self> {
+        Self::with_config(ListenerConfig::default())
+    }
+    /// Creates a new AsyncRouteListener with the given queue configuration.
+    pub fn with_config(_config: ListenerConfig) -> io::Result<Self> {
+        Err(super::unsupported_target())
+    }
+    pub(crate) fn take_snapshot(&mut self) -> Vec<Route> {
+        match self.never {}
+    }
+    /// Asynchronously listens for a route change event and returns a RouteChange.
+    pub async fn listen(&mut self) -> io::Result<RouteChange> {
+        match self.never {}
+    }
+    /// Asynchronously listens for a route change event and returns it together with its
+    /// sender.
+    pub async fn listen_with_origin(&mut self) -> io::Result<(RouteChange, EventOrigin)> {
+        match self.never {}
+    }
+}
+/// AsyncRouteManager for asynchronously managing routes (adding, deleting, and listing).
+#[derive(Clone)]
+pub struct AsyncRouteManager {
+    never: Infallible,
+}
+impl AsyncRouteManager {
+    /// Creates a new AsyncRouteManager.
+    pub fn new() -> io::Result<Self> {
+        Err(super::unsupported_target())
+    }
+    /// (Unix only) Makes operations that do not complete within `timeout` fail with
+    /// `TimedOut`.
+    #[cfg(unix)]
+    pub fn with_timeout(self, _timeout: std::time::Duration) -> Self {
+        match self.never {}
+    }
+    /// Sets whether routes are converted with [`Route::to_canonical`] before they are
+    /// installed or deleted.
+    pub fn with_normalize_mapped(self, _normalize: bool) -> Self {
+        match self.never {}
+    }
+    /// Retries operations that fail with a transient error under `policy`.
+    pub fn with_retry_policy(self, _policy: crate::RetryPolicy) -> Self {
+        match self.never {}
+    }
+    /// Retrieves a new instance of AsyncRouteListener.
+    pub fn listener() -> io::Result<AsyncRouteListener> {
+        AsyncRouteListener::new()
+    }
+    /// Asynchronously lists all current routes.
+    pub async fn list(&self) -> io::Result<Vec<Route>> {
+        match self.never {}
+    }
+    /// Asynchronously adds a new route.
+    pub async fn add(&self, _route: &Route) -> io::Result<()> {
+        match self.never {}
+    }
+    /// Asynchronously deletes an existing route.
+    pub async fn delete(&self, _route: &Route) -> io::Result<()> {
+        match self.never {}
+    }
+    /// Asynchronously modifies an existing route in place.
+    pub async fn update(&self, _route: &Route) -> io::Result<()> {
+        match self.never {}
+    }
+}
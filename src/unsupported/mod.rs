@@ -0,0 +1,198 @@
+//! Stub backend for targets without a route API binding, e.g. `wasm32`.
+//!
+//! The types mirror those of the supported platforms, so code using them compiles everywhere,
+//! but their constructors fail with `Unsupported`.
+#[cfg(any(feature = "async", feature = "async_io"))]
+mod async_route;
+use crate::{EventOrigin, ListenerConfig, Route, RouteChange};
+#[cfg(any(feature = "async", feature = "async_io"))]
+pub use async_route::*;
+use std::convert::Infallible;
+use std::io;
+use std::net::IpAddr;
+use std::time::Duration;
+
+pub(crate) fn unsupported_target() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "route management is not supported on this target",
+    )
+}
+
+/// RouteListener for receiving route change events.
+pub struct RouteListener {
+    never: Infallible,
+}
+impl RouteListener {
+    /// Creates a new RouteListener.
+    pub fn new() -> io::Result<Self> {
+        Self::with_config(ListenerConfig::default())
+    }
+    /// Creates a new RouteListener with the given queue configuration.
+    pub fn with_config(_config: ListenerConfig) -> io::Result<Self> {
+        Err(unsupported_target())
+    }
+    /// (Unix only) Creates a RouteListener on a routing socket opened elsewhere.
+    #[cfg(unix)]
+    pub fn from_socket(_socket: std::os::fd::OwnedFd, _config: ListenerConfig) -> io::Result<Self> {
+        Err(unsupported_target())
+    }
+    pub(crate) fn take_snapshot(&mut self) -> Vec<Route> {
+        match self.never {}
+    }
+    /// Listens for a route change event and returns a RouteChange.
+    pub fn listen(&mut self) -> io::Result<RouteChange> {
+        match self.never {}
+    }
+    /// Listens for a route change event and returns it together with its sender.
+    pub fn listen_with_origin(&mut self) -> io::Result<(RouteChange, EventOrigin)> {
+        match self.never {}
+    }
+    /// Listens for a route change event, waiting at most `timeout`.
+    pub fn listen_timeout(&mut self, _timeout: Duration) -> io::Result<RouteChange> {
+        match self.never {}
+    }
+    /// Returns a pending route change event without blocking, or `None` if there is none.
+    pub fn try_listen(&mut self) -> io::Result<Option<RouteChange>> {
+        match self.never {}
+    }
+    /// Drains all route change events that are pending, without blocking.
+    pub fn read_events(&mut self) -> io::Result<Vec<RouteChange>> {
+        match self.never {}
+    }
+    /// Returns all pending route change events without blocking, possibly none.
+    pub fn try_drain(&mut self) -> io::Result<Vec<RouteChange>> {
+        match self.never {}
+    }
+    /// Waits for a route change event and returns it together with the pending events, at
+    /// most `max` (but at least one) in total.
+    pub fn listen_batch(&mut self, _max: usize) -> io::Result<Vec<RouteChange>> {
+        match self.never {}
+    }
+    /// Waits for a route change event and appends it to `events` together with the pending
+    /// events. Returns the number of events appended.
+    pub fn listen_into(&mut self, _events: &mut Vec<RouteChange>) -> io::Result<usize> {
+        match self.never {}
+    }
+    /// Retrieves a shutdown handle for the RouteListener.
+    #[cfg(feature = "shutdown")]
+    pub fn shutdown_handle(&self) -> io::Result<RouteListenerShutdown> {
+        match self.never {}
+    }
+}
+/// Shutdown handle for the RouteListener, used to stop listening.
+#[derive(Clone)]
+#[cfg(feature = "shutdown")]
+pub struct RouteListenerShutdown {
+    never: Infallible,
+}
+#[cfg(feature = "shutdown")]
+impl RouteListenerShutdown {
+    /// Shuts down the RouteListener.
+    pub fn shutdown(&self) -> io::Result<()> {
+        match self.never {}
+    }
+}
+/// RouteManager is used for managing routes (adding, deleting, and listing).
+#[derive(Clone)]
+pub struct RouteManager {
+    never: Infallible,
+}
+impl RouteManager {
+    /// Creates a new RouteManager.
+    pub fn new() -> io::Result<Self> {
+        Err(unsupported_target())
+    }
+    pub(crate) fn with_defaults(_defaults: crate::RouteManagerBuilder) -> io::Result<Self> {
+        Err(unsupported_target())
+    }
+    /// (Unix only) Creates a manager that sends its requests through a routing socket
+    /// opened elsewhere.
+    #[cfg(unix)]
+    pub fn from_socket(_socket: std::os::fd::OwnedFd) -> io::Result<Self> {
+        Err(unsupported_target())
+    }
+    pub(crate) fn defaults(&self) -> &crate::RouteManagerBuilder {
+        match self.never {}
+    }
+    pub(crate) fn request(
+        &self,
+        _kind: crate::RequestKind,
+        _route: &Route,
+    ) -> io::Result<crate::RouteRequest> {
+        match self.never {}
+    }
+    /// Returns a new instance of RouteListener.
+    pub fn listener() -> io::Result<RouteListener> {
+        RouteListener::new()
+    }
+    /// Lists all current routes.
+    pub fn list(&self) -> io::Result<Vec<Route>> {
+        match self.never {}
+    }
+    /// Returns the installed route to exactly `destination/prefix`, or `None` if there is none.
+    pub fn get_route(&self, _destination: IpAddr, _prefix: u8) -> io::Result<Option<Route>> {
+        match self.never {}
+    }
+    /// Returns the source address the system would choose for packets to `dest`.
+    pub fn find_source(&self, _dest: &IpAddr) -> io::Result<Option<IpAddr>> {
+        match self.never {}
+    }
+    /// Returns the MTU of the path to `dest`.
+    pub fn path_mtu(&self, _dest: &IpAddr) -> io::Result<Option<u32>> {
+        match self.never {}
+    }
+    /// Returns the number of routes per family and interface.
+    pub fn stats(&self) -> io::Result<crate::RouteStats> {
+        match self.never {}
+    }
+    /// Adds a new route.
+    pub fn add(&self, _route: &Route) -> io::Result<()> {
+        match self.never {}
+    }
+    /// Deletes an existing route.
+    pub fn delete(&self, _route: &Route) -> io::Result<()> {
+        match self.never {}
+    }
+    /// Modifies an existing route in place.
+    pub fn update(&self, _route: &Route) -> io::Result<()> {
+        match self.never {}
+    }
+}
+
+#[cfg(feature = "command")]
+impl crate::CommandRouteManager {
+    /// Lists all current routes.
+    pub fn list(&mut self) -> io::Result<Vec<Route>> {
+        Err(unsupported_target())
+    }
+    pub(crate) fn add_command(_route: &Route) -> io::Result<(&'static str, Vec<String>)> {
+        Err(unsupported_target())
+    }
+    pub(crate) fn delete_command(_route: &Route) -> io::Result<(&'static str, Vec<String>)> {
+        Err(unsupported_target())
+    }
+    pub(crate) fn update_command(_route: &Route) -> io::Result<(&'static str, Vec<String>)> {
+        Err(unsupported_target())
+    }
+}
+
+/// Normalized flags reported for `route`; there are none without a backend.
+pub(crate) fn reported_flags(_route: &Route) -> crate::RouteFlags {
+    crate::RouteFlags::empty()
+}
+
+/// Whether an event was caused by a request sent from this process.
+pub(crate) fn is_own_origin(_origin: &EventOrigin) -> bool {
+    false
+}
+pub(crate) fn if_name_to_index(_name: &str) -> io::Result<u32> {
+    Err(unsupported_target())
+}
+pub(crate) fn if_index_to_name(_index: u32) -> io::Result<String> {
+    Err(unsupported_target())
+}
+/// Lists the addresses configured on all interfaces.
+pub(crate) fn interface_addresses() -> io::Result<Vec<crate::InterfaceAddress>> {
+    Err(unsupported_target())
+}
@@ -1,4 +1,6 @@
-use crate::{Route, RouteChange, RouteListener, RouteManager};
+use crate::{
+    EventOrigin, ListenerConfig, RetryPolicy, Route, RouteChange, RouteListener, RouteManager,
+};
 use std::io;
 
 /// AsyncRouteListener for asynchronously receiving route change events.
@@ -8,48 +10,134 @@ pub struct AsyncRouteListener {
 impl AsyncRouteListener {
     /// Creates a new AsyncRouteListener.
     pub fn new() -> io::Result<Self> {
+        Self::with_config(ListenerConfig::default())
+    }
+    /// Creates a new AsyncRouteListener with the given queue configuration.
+    pub fn with_config(config: ListenerConfig) -> io::Result<Self> {
         Ok(Self {
-            route_listener: RouteListener::new()?,
+            route_listener: RouteListener::with_config(config)?,
         })
     }
+    pub(crate) fn take_snapshot(&mut self) -> Vec<Route> {
+        self.route_listener.take_snapshot()
+    }
     /// Asynchronously listens for a route change event and returns a RouteChange.
+    ///
+    /// This method is cancel safe: if the future is dropped (e.g. in `select!`), no event is
+    /// lost and the next call picks up where this one left off.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self), ret(Display), err(level = "debug"))
+    )]
     pub async fn listen(&mut self) -> io::Result<RouteChange> {
+        if let Some(change) = self.route_listener.initial.pop_front() {
+            return Ok(change);
+        }
+        self.route_listener.check_overflow()?;
         self.route_listener
             .receiver
             .recv_async()
             .await
             .map_err(|_| io::Error::new(io::ErrorKind::Interrupted, "shutdown"))
     }
+    /// Asynchronously listens for a route change event and returns it together with its
+    /// sender. Windows does not report the sender, so the origin is always empty.
+    pub async fn listen_with_origin(&mut self) -> io::Result<(RouteChange, EventOrigin)> {
+        let change = self.listen().await?;
+        Ok((change, EventOrigin::default()))
+    }
 }
 /// AsyncRouteManager for asynchronously managing routes (adding, deleting, and listing).
+///
+/// Clones are cheap and share the manager; it can be used from several tasks at once.
+#[derive(Clone)]
 pub struct AsyncRouteManager {
-    _private: std::marker::PhantomData<()>,
+    normalize_mapped: bool,
+    retry: Option<RetryPolicy>,
 }
 impl AsyncRouteManager {
     /// Creates a new AsyncRouteManager.
     pub fn new() -> io::Result<Self> {
         Ok(Self {
-            _private: std::marker::PhantomData,
+            normalize_mapped: false,
+            retry: None,
         })
     }
+    /// Sets whether routes are converted with [`Route::to_canonical`] before they are
+    /// installed or deleted, see
+    /// [`RouteManagerBuilder::with_normalize_mapped`](crate::RouteManagerBuilder::with_normalize_mapped).
+    pub fn with_normalize_mapped(mut self, normalize: bool) -> Self {
+        self.normalize_mapped = normalize;
+        self
+    }
+    /// Retries operations that fail with a transient error under `policy`, see
+    /// [`RetryPolicy`]. The waits between attempts block, like the operations.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+    /// The synchronous manager the operations run on.
+    fn manager(&self) -> io::Result<RouteManager> {
+        let mut builder = RouteManager::builder().with_normalize_mapped(self.normalize_mapped);
+        if let Some(policy) = self.retry {
+            builder = builder.with_retry_policy(policy);
+        }
+        builder.build()
+    }
     /// Retrieves a new instance of AsyncRouteListener.
     pub fn listener() -> io::Result<AsyncRouteListener> {
         AsyncRouteListener::new()
     }
     /// Asynchronously lists all current routes.
     /// **Note: On Windows, this is not truly asynchronous.**
-    pub async fn list(&mut self) -> io::Result<Vec<Route>> {
-        RouteManager::new()?.list()
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), err(level = "debug"))
+    )]
+    pub async fn list(&self) -> io::Result<Vec<Route>> {
+        self.manager()?.list()
     }
     /// Asynchronously adds a new route.
     /// **Note: On Windows, this is not truly asynchronous.**
-    pub async fn add(&mut self, route: &Route) -> io::Result<()> {
-        RouteManager::new()?.add(route)
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub async fn add(&self, route: &Route) -> io::Result<()> {
+        self.manager()?.add(route)
     }
 
     /// Asynchronously deletes an existing route.
     /// **Note: On Windows, this is not truly asynchronous.**
-    pub async fn delete(&mut self, route: &Route) -> io::Result<()> {
-        RouteManager::new()?.delete(route)
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub async fn delete(&self, route: &Route) -> io::Result<()> {
+        self.manager()?.delete(route)
+    }
+    /// Asynchronously modifies an existing route in place. See `RouteManager::update`.
+    /// **Note: On Windows, this is not truly asynchronous.**
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub async fn update(&self, route: &Route) -> io::Result<()> {
+        self.manager()?.update(route)
     }
 }
@@ -0,0 +1,90 @@
+use crate::{CommandRouteManager, Route};
+use std::io;
+use std::net::IpAddr;
+
+impl CommandRouteManager {
+    /// Lists all current routes, parsed from `netsh interface ipv4|ipv6 show route`.
+    pub fn list(&mut self) -> io::Result<Vec<Route>> {
+        let mut routes = Vec::new();
+        for family in ["ipv4", "ipv6"] {
+            let args = ["interface", family, "show", "route"].map(String::from);
+            let output = self.run("netsh", &args)?;
+            routes.extend(output.lines().filter_map(parse_route));
+        }
+        debug_event!(count = routes.len(), "listed routes");
+        Ok(routes)
+    }
+    /// The `netsh` command of [`add`](Self::add).
+    pub(crate) fn add_command(route: &Route) -> io::Result<(&'static str, Vec<String>)> {
+        Ok(("netsh", route_args("add", route)?))
+    }
+    /// The `netsh` command of [`delete`](Self::delete).
+    pub(crate) fn delete_command(route: &Route) -> io::Result<(&'static str, Vec<String>)> {
+        Ok(("netsh", route_args("delete", route)?))
+    }
+    /// The `netsh` command of [`update`](Self::update).
+    pub(crate) fn update_command(route: &Route) -> io::Result<(&'static str, Vec<String>)> {
+        Ok(("netsh", route_args("set", route)?))
+    }
+}
+
+/// Builds the arguments of `netsh interface ipv4|ipv6 <cmd> route`; the changes are not
+/// persisted across reboots, like those of the native API.
+fn route_args(cmd: &str, route: &Route) -> io::Result<Vec<String>> {
+    let route = &*route.input_form();
+    route.check()?;
+    let family = if route.destination.is_ipv4() {
+        "ipv4"
+    } else {
+        "ipv6"
+    };
+    // netsh identifies the interface by index or alias only
+    let if_index = route
+        .get_index()
+        .or(route.gateway_scope_id)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "netsh requires the interface of the route",
+            )
+        })?;
+    let mut args: Vec<String> = vec![
+        "interface".into(),
+        family.into(),
+        cmd.into(),
+        "route".into(),
+    ];
+    args.push(format!("prefix={}/{}", route.destination, route.prefix));
+    args.push(format!("interface={if_index}"));
+    if let Some(gateway) = route.gateway {
+        args.push(format!("nexthop={gateway}"));
+    }
+    if let Some(metric) = route.metric {
+        if cmd != "delete" {
+            args.push(format!("metric={metric}"));
+        }
+    }
+    args.push("store=active".into());
+    Ok(args)
+}
+
+/// Parses a row of `netsh interface ipv4|ipv6 show route`:
+/// `Publish Type Met Prefix Idx Gateway/Interface Name`.
+fn parse_route(line: &str) -> Option<Route> {
+    let mut tokens = line.split_whitespace();
+    let _publish = tokens.next()?;
+    let _kind = tokens.next()?;
+    let metric: u32 = tokens.next()?.parse().ok()?;
+    let (destination, prefix) = tokens.next()?.split_once('/')?;
+    let destination: IpAddr = destination.parse().ok()?;
+    let if_index: u32 = tokens.next()?.parse().ok()?;
+    let mut route = Route::new(destination, prefix.parse().ok()?)
+        .with_if_index(if_index)
+        .with_metric(metric);
+    route.if_name = crate::route_if_name(if_index);
+    // Directly connected routes show the interface name instead of a gateway
+    if let Some(gateway) = tokens.next().and_then(|v| v.parse().ok()) {
+        route.gateway = Some(gateway);
+    }
+    Some(route)
+}
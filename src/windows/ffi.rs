@@ -1,4 +1,4 @@
-use crate::Route;
+use crate::{Route, RowDetails};
 use std::net::IpAddr;
 use std::{io, mem};
 use windows_sys::Win32::NetworkManagement::IpHelper::{
@@ -6,7 +6,9 @@ use windows_sys::Win32::NetworkManagement::IpHelper::{
     ConvertInterfaceLuidToIndex, InitializeIpForwardEntry, MIB_IPFORWARD_ROW2,
 };
 use windows_sys::Win32::NetworkManagement::Ndis::NET_LUID_LH;
-use windows_sys::Win32::Networking::WinSock::{AF_INET, AF_INET6, IN6_ADDR, IN_ADDR};
+use windows_sys::Win32::Networking::WinSock::{
+    AF_INET, AF_INET6, IN6_ADDR, IN_ADDR, SOCKADDR_INET,
+};
 
 pub(crate) fn encode_utf16(string: &str) -> Vec<u16> {
     use std::iter::once;
@@ -68,7 +70,13 @@ pub(crate) unsafe fn row_to_route(row: *const MIB_IPFORWARD_ROW2) -> Option<Rout
         AF_INET6 => IpAddr::from(mem::transmute::<IN6_ADDR, [u8; 16]>(
             (*row).DestinationPrefix.Prefix.Ipv6.sin6_addr,
         )),
-        _ => panic!("Unexpected family {dst_family}"),
+        _ => {
+            debug_event!(
+                family = dst_family,
+                "route row of unexpected family skipped"
+            );
+            return None;
+        }
     };
 
     let dst_len = (*row).DestinationPrefix.PrefixLength;
@@ -91,14 +99,71 @@ pub(crate) unsafe fn row_to_route(row: *const MIB_IPFORWARD_ROW2) -> Option<Rout
             (*row).InterfaceLuid,
         ))
         .with_metric((*row).Metric);
-    route.if_name = if_index_to_name((*row).InterfaceIndex).ok();
+    route.reported.row = Some(RowDetails {
+        protocol: (*row).Protocol,
+        origin: (*row).Origin,
+        loopback: (*row).Loopback,
+        autoconfigure_address: (*row).AutoconfigureAddress,
+        publish: (*row).Publish,
+        immortal: (*row).Immortal,
+        valid_lifetime: (*row).ValidLifetime,
+        preferred_lifetime: (*row).PreferredLifetime,
+    });
+    route.if_name = crate::route_if_name((*row).InterfaceIndex);
     route.gateway = gateway;
+    if nexthop_family == AF_INET6 {
+        let scope_id = (*row).NextHop.Ipv6.Anonymous.sin6_scope_id;
+        if scope_id != 0 {
+            route.gateway_scope_id = Some(scope_id);
+        }
+    }
     Some(route)
 }
 
+/// Formats the fields of `row`, for dry runs.
+pub(crate) fn row_debug(row: &MIB_IPFORWARD_ROW2) -> String {
+    unsafe {
+        format!(
+            "MIB_IPFORWARD_ROW2 {{ InterfaceLuid: {:#x}, InterfaceIndex: {}, \
+             DestinationPrefix: {}/{}, NextHop: {}, SitePrefixLength: {}, ValidLifetime: {}, \
+             PreferredLifetime: {}, Metric: {}, Protocol: {}, Loopback: {}, \
+             AutoconfigureAddress: {}, Publish: {}, Immortal: {}, Age: {}, Origin: {} }}",
+            mem::transmute::<NET_LUID_LH, u64>(row.InterfaceLuid),
+            row.InterfaceIndex,
+            sockaddr_inet_debug(&row.DestinationPrefix.Prefix),
+            row.DestinationPrefix.PrefixLength,
+            sockaddr_inet_debug(&row.NextHop),
+            row.SitePrefixLength,
+            row.ValidLifetime,
+            row.PreferredLifetime,
+            row.Metric,
+            row.Protocol,
+            row.Loopback,
+            row.AutoconfigureAddress,
+            row.Publish,
+            row.Immortal,
+            row.Age,
+            row.Origin,
+        )
+    }
+}
+
+unsafe fn sockaddr_inet_debug(addr: &SOCKADDR_INET) -> String {
+    match addr.si_family {
+        AF_INET => IpAddr::from(mem::transmute::<IN_ADDR, [u8; 4]>(addr.Ipv4.sin_addr)).to_string(),
+        AF_INET6 => format!(
+            "{}%{}",
+            IpAddr::from(mem::transmute::<IN6_ADDR, [u8; 16]>(addr.Ipv6.sin6_addr)),
+            addr.Ipv6.Anonymous.sin6_scope_id
+        ),
+        family => format!("<family {family}>"),
+    }
+}
+
 impl TryFrom<&Route> for MIB_IPFORWARD_ROW2 {
     type Error = io::Error;
     fn try_from(route: &Route) -> Result<Self, Self::Error> {
+        let route = &*route.input_form();
         route.check()?;
         let mut row: MIB_IPFORWARD_ROW2 = unsafe { std::mem::zeroed() };
         unsafe { InitializeIpForwardEntry(&mut row) };
@@ -121,6 +186,9 @@ impl TryFrom<&Route> for MIB_IPFORWARD_ROW2 {
                     row.NextHop.si_family = AF_INET6;
                     row.NextHop.Ipv6.sin6_addr =
                         mem::transmute::<[u8; 16], IN6_ADDR>(addr.octets());
+                    if let Some(scope_id) = route.gateway_scope_id {
+                        row.NextHop.Ipv6.Anonymous.sin6_scope_id = scope_id;
+                    }
                 },
             }
         } else {
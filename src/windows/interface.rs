@@ -0,0 +1,191 @@
+use crate::windows::ffi::decode_utf16;
+use crate::{Interface, InterfaceAddress};
+use std::io;
+use std::net::IpAddr;
+use windows_sys::Win32::Foundation::{ERROR_NOT_FOUND, ERROR_SUCCESS};
+use windows_sys::Win32::NetworkManagement::IpHelper::{
+    FreeMibTable, GetIfEntry, GetIfTable2, GetIpInterfaceEntry, GetUnicastIpAddressTable,
+    InitializeIpInterfaceEntry, SetIfEntry, SetIpInterfaceEntry, MIB_IFROW,
+    MIB_IF_ADMIN_STATUS_DOWN, MIB_IF_ADMIN_STATUS_UP, MIB_IF_ROW2, MIB_IF_TABLE2,
+    MIB_IPINTERFACE_ROW, MIB_UNICASTIPADDRESS_ROW, MIB_UNICASTIPADDRESS_TABLE,
+};
+use windows_sys::Win32::NetworkManagement::Ndis::{NET_IF_ADMIN_STATUS_UP, NET_LUID_LH};
+use windows_sys::Win32::Networking::WinSock::{
+    ADDRESS_FAMILY, AF_INET, AF_INET6, AF_UNSPEC, IN6_ADDR, IN_ADDR,
+};
+
+/// Lists all network interfaces.
+pub fn list_interfaces() -> io::Result<Vec<Interface>> {
+    let mut ptable: *mut MIB_IF_TABLE2 = std::ptr::null_mut();
+
+    let ret = unsafe { GetIfTable2(&mut ptable) };
+    if ret != ERROR_SUCCESS {
+        return Err(io::Error::from_raw_os_error(ret as i32));
+    }
+
+    let prows = unsafe {
+        std::ptr::slice_from_raw_parts(
+            &(*ptable).Table as *const _ as *const MIB_IF_ROW2,
+            (*ptable).NumEntries as usize,
+        )
+    };
+    let res = unsafe { &*prows }
+        .iter()
+        .map(row_to_interface)
+        .collect::<Vec<_>>();
+    unsafe { FreeMibTable(ptable as *mut _ as *mut _) };
+    Ok(res)
+}
+
+/// Lists the addresses configured on all interfaces.
+pub(crate) fn interface_addresses() -> io::Result<Vec<InterfaceAddress>> {
+    let mut ptable: *mut MIB_UNICASTIPADDRESS_TABLE = std::ptr::null_mut();
+
+    let ret = unsafe { GetUnicastIpAddressTable(AF_UNSPEC, &mut ptable) };
+    if ret != ERROR_SUCCESS {
+        return Err(io::Error::from_raw_os_error(ret as i32));
+    }
+
+    let prows = unsafe {
+        std::ptr::slice_from_raw_parts(
+            &(*ptable).Table as *const _ as *const MIB_UNICASTIPADDRESS_ROW,
+            (*ptable).NumEntries as usize,
+        )
+    };
+    let res = unsafe { &*prows }
+        .iter()
+        .filter_map(|row| {
+            let addr = unsafe {
+                match row.Address.si_family {
+                    AF_INET => IpAddr::from(std::mem::transmute::<IN_ADDR, [u8; 4]>(
+                        row.Address.Ipv4.sin_addr,
+                    )),
+                    AF_INET6 => IpAddr::from(std::mem::transmute::<IN6_ADDR, [u8; 16]>(
+                        row.Address.Ipv6.sin6_addr,
+                    )),
+                    _ => return None,
+                }
+            };
+            Some(InterfaceAddress {
+                index: row.InterfaceIndex,
+                addr,
+                prefix: row.OnLinkPrefixLength,
+            })
+        })
+        .collect::<Vec<_>>();
+    unsafe { FreeMibTable(ptable as *mut _ as *mut _) };
+    Ok(res)
+}
+
+/// LinkManager is used for configuring network interfaces (up/down, MTU).
+pub struct LinkManager {
+    _private: std::marker::PhantomData<()>,
+}
+impl LinkManager {
+    /// Creates a new LinkManager.
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            _private: std::marker::PhantomData,
+        })
+    }
+    /// Lists all network interfaces.
+    pub fn list(&mut self) -> io::Result<Vec<Interface>> {
+        list_interfaces()
+    }
+    /// Sets the interface administratively up.
+    pub fn set_up(&mut self, if_index: u32) -> io::Result<()> {
+        set_admin_status(if_index, MIB_IF_ADMIN_STATUS_UP)
+    }
+    /// Sets the interface administratively down.
+    pub fn set_down(&mut self, if_index: u32) -> io::Result<()> {
+        set_admin_status(if_index, MIB_IF_ADMIN_STATUS_DOWN)
+    }
+    /// Sets the MTU of the interface for both IPv4 and IPv6.
+    pub fn set_mtu(&mut self, if_index: u32, mtu: u32) -> io::Result<()> {
+        let v4 = set_ip_mtu(if_index, AF_INET, mtu);
+        let v6 = set_ip_mtu(if_index, AF_INET6, mtu);
+        // An interface may have only one of the two protocols bound
+        match (v4, v6) {
+            (Err(e), Err(_)) => Err(e),
+            (Err(e), Ok(_)) | (Ok(_), Err(e))
+                if crate::os_error(&e) != Some(ERROR_NOT_FOUND as i32) =>
+            {
+                Err(e)
+            }
+            _ => Ok(()),
+        }
+    }
+    /// Returns the interface metric of the interface for IPv4, or IPv6 if `ipv6` is set.
+    ///
+    /// Windows adds it to the route metric when selecting routes; see
+    /// [`Route::effective_metric`](crate::Route::effective_metric).
+    pub fn interface_metric(&mut self, if_index: u32, ipv6: bool) -> io::Result<u32> {
+        let family = if ipv6 { AF_INET6 } else { AF_INET };
+        Ok(ip_interface_row(if_index, None, family)?.Metric)
+    }
+}
+
+fn set_admin_status(if_index: u32, status: u32) -> io::Result<()> {
+    let mut row: MIB_IFROW = unsafe { std::mem::zeroed() };
+    row.dwIndex = if_index;
+    let ret = unsafe { GetIfEntry(&mut row) };
+    if ret != ERROR_SUCCESS {
+        return Err(io::Error::from_raw_os_error(ret as i32));
+    }
+    row.dwAdminStatus = status;
+    let ret = unsafe { SetIfEntry(&row) };
+    if ret != ERROR_SUCCESS {
+        return Err(io::Error::from_raw_os_error(ret as i32));
+    }
+    Ok(())
+}
+
+/// Reads the per-protocol settings of an interface, identified by index or, if set, by LUID.
+pub(crate) fn ip_interface_row(
+    if_index: u32,
+    luid: Option<u64>,
+    family: ADDRESS_FAMILY,
+) -> io::Result<MIB_IPINTERFACE_ROW> {
+    let mut row: MIB_IPINTERFACE_ROW = unsafe { std::mem::zeroed() };
+    unsafe { InitializeIpInterfaceEntry(&mut row) };
+    row.Family = family;
+    row.InterfaceIndex = if_index;
+    if let Some(luid) = luid {
+        row.InterfaceLuid = NET_LUID_LH { Value: luid };
+    }
+    let ret = unsafe { GetIpInterfaceEntry(&mut row) };
+    if ret != ERROR_SUCCESS {
+        return Err(io::Error::from_raw_os_error(ret as i32));
+    }
+    Ok(row)
+}
+
+fn set_ip_mtu(if_index: u32, family: ADDRESS_FAMILY, mtu: u32) -> io::Result<()> {
+    let mut row = ip_interface_row(if_index, None, family)?;
+    row.NlMtu = mtu;
+    // SetIpInterfaceEntry rejects IPv4 rows that carry a site prefix length
+    if family == AF_INET {
+        row.SitePrefixLength = 0;
+    }
+    let ret = unsafe { SetIpInterfaceEntry(&mut row) };
+    if ret != ERROR_SUCCESS {
+        return Err(io::Error::from_raw_os_error(ret as i32));
+    }
+    Ok(())
+}
+
+fn row_to_interface(row: &MIB_IF_ROW2) -> Interface {
+    let mac = if row.PhysicalAddressLength == 6 {
+        <[u8; 6]>::try_from(&row.PhysicalAddress[..6]).ok()
+    } else {
+        None
+    };
+    Interface {
+        index: row.InterfaceIndex,
+        name: decode_utf16(&row.Alias),
+        mac,
+        mtu: row.Mtu,
+        is_up: row.AdminStatus == NET_IF_ADMIN_STATUS_UP,
+        luid: unsafe { std::mem::transmute::<NET_LUID_LH, u64>(row.InterfaceLuid) },
+    }
+}
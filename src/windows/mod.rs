@@ -1,25 +1,35 @@
 // See https://github.com/johnyburd/net-route/blob/main/src/platform_impl/windows.rs
 
 use crate::common::Route;
-use crate::RouteChange;
-use flume::{Receiver, Sender};
+use crate::{EventOrigin, ListenerConfig, OverflowPolicy, RouteCache, RouteChange};
+use flume::{Receiver, RecvTimeoutError, Sender, TryRecvError, TrySendError};
+use std::collections::VecDeque;
 use std::io;
 use std::net::IpAddr;
 use std::os::windows::raw::HANDLE;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+use std::time::Duration;
+use windows_sys::Win32::Foundation::{ERROR_NETWORK_UNREACHABLE, ERROR_NOT_FOUND, ERROR_SUCCESS};
 use windows_sys::Win32::NetworkManagement::IpHelper::{
     CancelMibChangeNotify2, CreateIpForwardEntry2, DeleteIpForwardEntry2, FreeMibTable,
-    GetBestRoute2, GetIpForwardTable2, MibAddInstance, MibDeleteInstance, MibParameterNotification,
-    NotifyRouteChange2, MIB_IPFORWARD_ROW2, MIB_IPFORWARD_TABLE2, MIB_NOTIFICATION_TYPE,
+    GetBestRoute2, GetCurrentThreadCompartmentId, GetIpForwardTable2, MibAddInstance,
+    MibDeleteInstance, MibParameterNotification, NotifyRouteChange2, SetIpForwardEntry2,
+    MIB_IPFORWARD_ROW2, MIB_IPFORWARD_TABLE2, MIB_NOTIFICATION_TYPE,
+};
+use windows_sys::Win32::Networking::WinSock::{
+    NlroManual, AF_INET, AF_INET6, AF_UNSPEC, IN6_ADDR, IN_ADDR, MIB_IPPROTO_ICMP, SOCKADDR_INET,
 };
-use windows_sys::Win32::Networking::WinSock::{AF_INET, AF_INET6, AF_UNSPEC, SOCKADDR_INET};
 #[cfg(any(feature = "async", feature = "async_io"))]
 pub(crate) mod async_route;
+#[cfg(feature = "command")]
+mod command;
 pub(crate) mod ffi;
+mod interface;
 #[cfg(any(feature = "async", feature = "async_io"))]
 pub use async_route::*;
 pub(crate) use ffi::*;
+pub use interface::*;
 
 #[repr(transparent)]
 struct NotifyHandle(HANDLE);
@@ -29,18 +39,55 @@ unsafe impl Sync for NotifyHandle {}
 pub struct RouteListener {
     handle: Arc<Mutex<Option<RouteHandle>>>,
     receiver: Receiver<RouteChange>,
+    overflowed: Arc<AtomicBool>,
+    // Replay of the table at creation, delivered before the channel
+    initial: VecDeque<RouteChange>,
+    snapshot: Vec<Route>,
+}
+/// State handed to the notification callback.
+struct Notifier {
+    sender: Sender<RouteChange>,
+    receiver: Receiver<RouteChange>,
+    policy: OverflowPolicy,
+    overflowed: Arc<AtomicBool>,
+    ignore_system_routes: bool,
+    cache: Mutex<Option<RouteCache>>,
 }
 impl RouteListener {
     /// Creates a new RouteListener.
     pub fn new() -> io::Result<Self> {
+        Self::with_config(ListenerConfig::default())
+    }
+    /// Creates a new RouteListener with the given queue configuration.
+    pub fn with_config(config: ListenerConfig) -> io::Result<Self> {
+        config.check_families()?;
+        if config.suppress_own_changes {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "route change notifications do not report their sender on Windows",
+            ));
+        }
+        let family = match (config.ipv4, config.ipv6) {
+            (true, false) => AF_INET,
+            (false, true) => AF_INET6,
+            _ => AF_UNSPEC,
+        };
         let mut handle: HANDLE = std::ptr::null_mut();
-        let (sender, receiver) = flume::bounded::<RouteChange>(128);
-        let mut sender = Box::new(sender);
+        let (sender, receiver) = flume::bounded::<RouteChange>(config.queue_capacity);
+        let overflowed = Arc::new(AtomicBool::new(false));
+        let mut notifier = Box::new(Notifier {
+            sender,
+            receiver: receiver.clone(),
+            policy: config.overflow_policy,
+            overflowed: overflowed.clone(),
+            ignore_system_routes: config.ignore_system_routes,
+            cache: Mutex::new(None),
+        });
         let ret = unsafe {
             NotifyRouteChange2(
-                AF_UNSPEC,
+                family,
                 Some(callback),
-                (sender.as_mut() as *mut _) as *mut _,
+                (notifier.as_mut() as *mut _) as *mut _,
                 false,
                 &mut handle,
             )
@@ -48,17 +95,132 @@ impl RouteListener {
         if ret != ERROR_SUCCESS {
             return Err(io::Error::from_raw_os_error(ret as i32));
         }
-        Ok(RouteListener {
-            handle: Arc::new(Mutex::new(Some((NotifyHandle(handle), sender)))),
+        let mut listener = RouteListener {
+            handle: Arc::new(Mutex::new(Some((NotifyHandle(handle), notifier)))),
             receiver,
-        })
+            overflowed,
+            initial: VecDeque::new(),
+            snapshot: Vec::new(),
+        };
+        if !config.needs_routes() {
+            return Ok(listener);
+        }
+        if let Some((_, notifier)) = &*listener.handle.lock().unwrap() {
+            // The callback waits on the cache lock, so no change is applied before the dump
+            let mut cache = notifier.cache.lock().unwrap();
+            let routes = RouteManager::new()?.list()?;
+            *cache = config.route_cache(&routes);
+            listener.initial = config.replay(routes.clone()).collect();
+            if config.snapshot {
+                listener.snapshot = routes;
+            }
+        }
+        Ok(listener)
+    }
+    pub(crate) fn take_snapshot(&mut self) -> Vec<Route> {
+        std::mem::take(&mut self.snapshot)
+    }
+    /// Reports an overflow recorded under `OverflowPolicy::Error`, once.
+    pub(crate) fn check_overflow(&self) -> io::Result<()> {
+        if self.overflowed.swap(false, Ordering::Relaxed) {
+            return Err(crate::overflow_error());
+        }
+        Ok(())
     }
     /// Listens for a route change event and returns a RouteChange.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self), ret(Display), err(level = "debug"))
+    )]
     pub fn listen(&mut self) -> io::Result<RouteChange> {
+        if let Some(change) = self.initial.pop_front() {
+            return Ok(change);
+        }
+        self.check_overflow()?;
         self.receiver
             .recv()
             .map_err(|_| io::Error::other("shutdown"))
     }
+    /// Listens for a route change event and returns it together with its sender.
+    ///
+    /// Windows does not report the sender, so the origin is always empty.
+    pub fn listen_with_origin(&mut self) -> io::Result<(RouteChange, EventOrigin)> {
+        self.listen().map(|change| (change, EventOrigin::default()))
+    }
+    /// Listens for a route change event, waiting at most `timeout`.
+    ///
+    /// Fails with `TimedOut` if no event arrives in time.
+    pub fn listen_timeout(&mut self, timeout: Duration) -> io::Result<RouteChange> {
+        if let Some(change) = self.initial.pop_front() {
+            return Ok(change);
+        }
+        self.check_overflow()?;
+        self.receiver.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => io::Error::from(io::ErrorKind::TimedOut),
+            RecvTimeoutError::Disconnected => io::Error::other("shutdown"),
+        })
+    }
+    /// Returns a pending route change event without blocking, or `None` if there is none.
+    pub fn try_listen(&mut self) -> io::Result<Option<RouteChange>> {
+        if let Some(change) = self.initial.pop_front() {
+            return Ok(Some(change));
+        }
+        self.check_overflow()?;
+        match self.receiver.try_recv() {
+            Ok(route) => Ok(Some(route)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(io::Error::other("shutdown")),
+        }
+    }
+    /// Drains all route change events that are pending, without blocking.
+    pub fn read_events(&mut self) -> io::Result<Vec<RouteChange>> {
+        self.try_drain()
+    }
+    /// Returns all pending route change events without blocking, possibly none.
+    pub fn try_drain(&mut self) -> io::Result<Vec<RouteChange>> {
+        let mut events = Vec::new();
+        self.drain_into(&mut events, 0, usize::MAX)?;
+        Ok(events)
+    }
+    /// Waits for a route change event and returns it together with the pending events, at
+    /// most `max` (but at least one) in total.
+    ///
+    /// Events beyond `max` stay queued for the next call.
+    pub fn listen_batch(&mut self, max: usize) -> io::Result<Vec<RouteChange>> {
+        let mut events = vec![self.listen()?];
+        self.drain_into(&mut events, 0, max)?;
+        Ok(events)
+    }
+    /// Waits for a route change event and appends it to `events` together with the pending
+    /// events. Returns the number of events appended.
+    ///
+    /// Reusing `events` (after clearing it) avoids allocating in a processing loop.
+    pub fn listen_into(&mut self, events: &mut Vec<RouteChange>) -> io::Result<usize> {
+        let start = events.len();
+        events.push(self.listen()?);
+        self.drain_into(events, start, usize::MAX)?;
+        Ok(events.len() - start)
+    }
+    /// Appends pending events to `events` until it holds `max` events after `start`.
+    ///
+    /// An overflow after some events were collected is reported by the next call instead.
+    fn drain_into(
+        &mut self,
+        events: &mut Vec<RouteChange>,
+        start: usize,
+        max: usize,
+    ) -> io::Result<()> {
+        while events.len() - start < max {
+            if events.len() > start && self.overflowed.load(Ordering::Relaxed) {
+                break;
+            }
+            match self.try_listen()? {
+                Some(event) => events.push(event),
+                None => break,
+            }
+        }
+        Ok(())
+    }
 
     /// Retrieves a shutdown handle for the RouteListener.
     #[cfg(feature = "shutdown")]
@@ -69,11 +231,11 @@ impl RouteListener {
     }
 }
 fn shutdown(handle: &Mutex<Option<RouteHandle>>) {
-    if let Some((handle, sender)) = handle.lock().unwrap().take() {
+    if let Some((handle, notifier)) = handle.lock().unwrap().take() {
         unsafe {
             CancelMibChangeNotify2(handle.0);
         }
-        drop(sender)
+        drop(notifier)
     }
 }
 
@@ -83,7 +245,7 @@ fn shutdown(handle: &Mutex<Option<RouteHandle>>) {
 pub struct RouteListenerShutdown {
     handle: Arc<Mutex<Option<RouteHandle>>>,
 }
-type RouteHandle = (NotifyHandle, Box<Sender<RouteChange>>);
+type RouteHandle = (NotifyHandle, Box<Notifier>);
 #[cfg(feature = "shutdown")]
 impl RouteListenerShutdown {
     /// Shuts down the RouteListener.
@@ -98,114 +260,339 @@ impl Drop for RouteListener {
     }
 }
 /// RouteManager is used for managing routes (adding, deleting, and listing).
+///
+/// Clones are cheap and share the manager; it can be used from several threads at once.
+#[derive(Clone)]
 pub struct RouteManager {
-    _private: std::marker::PhantomData<()>,
+    defaults: crate::RouteManagerBuilder,
 }
 impl RouteManager {
     /// Creates a new RouteManager.
     pub fn new() -> io::Result<Self> {
-        Ok(Self {
-            _private: std::marker::PhantomData,
+        Self::with_defaults(Default::default())
+    }
+    pub(crate) fn with_defaults(defaults: crate::RouteManagerBuilder) -> io::Result<Self> {
+        Ok(Self { defaults })
+    }
+    pub(crate) fn defaults(&self) -> &crate::RouteManagerBuilder {
+        &self.defaults
+    }
+    /// Builds the row of a dry run, see [`RouteRequest`](crate::RouteRequest).
+    pub(crate) fn request(
+        &self,
+        kind: crate::RequestKind,
+        route: &Route,
+    ) -> io::Result<crate::RouteRequest> {
+        let route = &*self.defaults.apply(route);
+        let row = match kind {
+            crate::RequestKind::Add => self.add_row(route)?,
+            crate::RequestKind::Delete | crate::RequestKind::Update => route.try_into()?,
+        };
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                &row as *const MIB_IPFORWARD_ROW2 as *const u8,
+                std::mem::size_of::<MIB_IPFORWARD_ROW2>(),
+            )
+        };
+        Ok(crate::RouteRequest {
+            bytes: bytes.to_vec(),
+            decoded: ffi::row_debug(&row),
         })
     }
+    /// The row `add` creates, tagged with the owner of the manager.
+    fn add_row(&self, route: &Route) -> io::Result<MIB_IPFORWARD_ROW2> {
+        let mut row: MIB_IPFORWARD_ROW2 = route.try_into()?;
+        if let Some(owner) = self.defaults.owner {
+            row.Protocol = owner as i32;
+        }
+        Ok(row)
+    }
     /// Returns a new instance of RouteListener.
     pub fn listener() -> io::Result<RouteListener> {
         RouteListener::new()
     }
     /// Lists all current routes.
-    pub fn list(&mut self) -> io::Result<Vec<Route>> {
-        let mut ptable: *mut MIB_IPFORWARD_TABLE2 = std::ptr::null_mut();
-
-        let ret = unsafe { GetIpForwardTable2(AF_UNSPEC, &mut ptable as *mut _ as *mut _) };
-        if ret != ERROR_SUCCESS {
-            return Err(io::Error::from_raw_os_error(ret as i32));
-        }
-
-        let prows = unsafe {
-            std::ptr::slice_from_raw_parts(
-                &(*ptable).Table as *const _ as *const MIB_IPFORWARD_ROW2,
-                (*ptable).NumEntries as usize,
-            )
-        };
-
-        let entries = unsafe { (*ptable).NumEntries };
-        let res = (0..entries)
-            .map(|idx| unsafe { (*prows)[idx as usize] })
-            .filter_map(|row| unsafe { row_to_route(&row) })
-            .collect::<Vec<_>>();
-        unsafe { FreeMibTable(ptable as *mut _ as *mut _) };
-        Ok(res)
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), err(level = "debug"))
+    )]
+    pub fn list(&self) -> io::Result<Vec<Route>> {
+        crate::retry(self.defaults.retry, list_routes)
     }
     /// Route Lookup by Destination Address
-    pub fn find_route(&mut self, dest_ip: &IpAddr) -> io::Result<Option<Route>> {
-        unsafe {
-            let mut row: MIB_IPFORWARD_ROW2 = std::mem::zeroed();
-            let mut dest: SOCKADDR_INET = std::mem::zeroed();
-            let mut best_source_address: SOCKADDR_INET = std::mem::zeroed();
-
-            match dest_ip {
-                IpAddr::V4(ipv4) => {
-                    dest.si_family = AF_INET;
-                    dest.Ipv4.sin_family = AF_INET;
-                    dest.Ipv4.sin_addr.S_un.S_addr = u32::from(*ipv4).to_be();
-                }
-                IpAddr::V6(ipv6) => {
-                    dest.si_family = AF_INET6;
-                    dest.Ipv6.sin6_family = AF_INET6;
-                    dest.Ipv6.sin6_addr.u.Byte = ipv6.octets();
-                }
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(dest = %dest_ip),
+            err(level = "debug")
+        )
+    )]
+    pub fn find_route(&self, dest_ip: &IpAddr) -> io::Result<Option<Route>> {
+        let (row, _) = best_route(dest_ip)?;
+        Ok(unsafe { row_to_route(&row) })
+    }
+    /// Returns the source address the system would choose for packets to `dest`
+    /// (`BestSourceAddress` of `GetBestRoute2`), or `None` if `dest` is unreachable.
+    pub fn find_source(&self, dest: &IpAddr) -> io::Result<Option<IpAddr>> {
+        let (_, source) = match best_route(dest) {
+            Ok(rs) => rs,
+            Err(e) if is_unreachable(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let source = unsafe {
+            match source.si_family {
+                AF_INET => IpAddr::from(std::mem::transmute::<IN_ADDR, [u8; 4]>(
+                    source.Ipv4.sin_addr,
+                )),
+                AF_INET6 => IpAddr::from(std::mem::transmute::<IN6_ADDR, [u8; 16]>(
+                    source.Ipv6.sin6_addr,
+                )),
+                _ => return Ok(None),
             }
+        };
+        Ok(Some(source))
+    }
+    /// Returns the MTU of the path to `dest`, or `None` if `dest` is unreachable: the IP MTU
+    /// (`NlMtu`) of the interface of the best route to it.
+    pub fn path_mtu(&self, dest: &IpAddr) -> io::Result<Option<u32>> {
+        let (row, _) = match best_route(dest) {
+            Ok(rs) => rs,
+            Err(e) if is_unreachable(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let family = if dest.is_ipv4() { AF_INET } else { AF_INET6 };
+        let luid = unsafe { row.InterfaceLuid.Value };
+        let row = interface::ip_interface_row(row.InterfaceIndex, Some(luid), family)?;
+        Ok(Some(row.NlMtu))
+    }
+    /// Returns the installed route to exactly `destination/prefix`, or `None` if there is none.
+    /// Among several routes the one with the lowest metric is returned.
+    ///
+    /// **Note: On Windows, this filters the full route table.**
+    pub fn get_route(&self, destination: IpAddr, prefix: u8) -> io::Result<Option<Route>> {
+        let wanted = Route::new(destination, prefix);
+        let rs = self
+            .list()?
+            .into_iter()
+            .filter(|v| v.same_destination(&wanted))
+            .min_by_key(|v| v.metric.unwrap_or(0));
+        Ok(rs)
+    }
+    /// Adds a new route.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub fn add(&self, route: &Route) -> io::Result<()> {
+        let route = &*self.defaults.apply(route);
+        self.add_row(route)
+            .and_then(|row| {
+                crate::retry(self.defaults.retry, || {
+                    let err = unsafe { CreateIpForwardEntry2(&row) };
+                    if err != ERROR_SUCCESS {
+                        return Err(io::Error::from_raw_os_error(err as i32));
+                    }
+                    Ok(())
+                })
+            })
+            .map_err(crate::context("add", route))
+    }
+    /// Deletes an existing route.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub fn delete(&self, route: &Route) -> io::Result<()> {
+        let route = &*self.defaults.apply(route);
+        MIB_IPFORWARD_ROW2::try_from(route)
+            .and_then(|row| {
+                crate::retry(self.defaults.retry, || {
+                    let err = unsafe { DeleteIpForwardEntry2(&row) };
+                    if err != ERROR_SUCCESS {
+                        return Err(io::Error::from_raw_os_error(err as i32));
+                    }
+                    Ok(())
+                })
+            })
+            .map_err(crate::context("delete", route))
+    }
+    /// Modifies an existing route in place, without a window in which it is missing.
+    ///
+    /// The route is identified by destination, table and metric on Linux, by destination,
+    /// interface and gateway on Windows, and by destination on BSD; the remaining attributes
+    /// (e.g. the gateway on Linux and BSD, the metric on Windows) are replaced. Fails if no
+    /// such route exists.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(route = %route),
+            err(level = "debug")
+        )
+    )]
+    pub fn update(&self, route: &Route) -> io::Result<()> {
+        let route = &*self.defaults.apply(route);
+        MIB_IPFORWARD_ROW2::try_from(route)
+            .and_then(|row| {
+                crate::retry(self.defaults.retry, || {
+                    let err = unsafe { SetIpForwardEntry2(&row) };
+                    if err != ERROR_SUCCESS {
+                        return Err(io::Error::from_raw_os_error(err as i32));
+                    }
+                    Ok(())
+                })
+            })
+            .map_err(crate::context("update", route))
+    }
+}
 
-            let err = GetBestRoute2(
-                std::ptr::null_mut(),
-                0,
-                std::ptr::null(),
-                &dest,
-                0,
-                &mut row,
-                &mut best_source_address,
-            );
-            if err != ERROR_SUCCESS {
-                return Err(io::Error::from_raw_os_error(err as i32));
+fn is_unreachable(e: &io::Error) -> bool {
+    matches!(
+        crate::os_error(e).map(|v| v as u32),
+        Some(ERROR_NOT_FOUND | ERROR_NETWORK_UNREACHABLE)
+    )
+}
+
+/// Calls `GetBestRoute2` for `dest_ip`, returning the route and the source address.
+fn best_route(dest_ip: &IpAddr) -> io::Result<(MIB_IPFORWARD_ROW2, SOCKADDR_INET)> {
+    unsafe {
+        let mut row: MIB_IPFORWARD_ROW2 = std::mem::zeroed();
+        let mut dest: SOCKADDR_INET = std::mem::zeroed();
+        let mut best_source_address: SOCKADDR_INET = std::mem::zeroed();
+
+        match dest_ip {
+            IpAddr::V4(ipv4) => {
+                dest.si_family = AF_INET;
+                dest.Ipv4.sin_family = AF_INET;
+                dest.Ipv4.sin_addr.S_un.S_addr = u32::from(*ipv4).to_be();
+            }
+            IpAddr::V6(ipv6) => {
+                dest.si_family = AF_INET6;
+                dest.Ipv6.sin6_family = AF_INET6;
+                dest.Ipv6.sin6_addr.u.Byte = ipv6.octets();
             }
-            Ok(row_to_route(&row))
         }
-    }
-    /// Adds a new route.
-    pub fn add(&mut self, route: &Route) -> io::Result<()> {
-        let row: MIB_IPFORWARD_ROW2 = route.try_into()?;
 
-        let err = unsafe { CreateIpForwardEntry2(&row) };
+        let err = GetBestRoute2(
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null(),
+            &dest,
+            0,
+            &mut row,
+            &mut best_source_address,
+        );
         if err != ERROR_SUCCESS {
             return Err(io::Error::from_raw_os_error(err as i32));
         }
-        Ok(())
+        Ok((row, best_source_address))
     }
-    /// Deletes an existing route.
-    pub fn delete(&mut self, route: &Route) -> io::Result<()> {
-        let row: MIB_IPFORWARD_ROW2 = route.try_into()?;
-        let err = unsafe { DeleteIpForwardEntry2(&row) };
-        if err != ERROR_SUCCESS {
-            return Err(io::Error::from_raw_os_error(err as i32));
-        }
-        Ok(())
+}
+
+fn list_routes() -> io::Result<Vec<Route>> {
+    let mut ptable: *mut MIB_IPFORWARD_TABLE2 = std::ptr::null_mut();
+
+    let ret = unsafe { GetIpForwardTable2(AF_UNSPEC, &mut ptable as *mut _ as *mut _) };
+    if ret != ERROR_SUCCESS {
+        return Err(io::Error::from_raw_os_error(ret as i32));
     }
+
+    let prows = unsafe {
+        std::ptr::slice_from_raw_parts(
+            &(*ptable).Table as *const _ as *const MIB_IPFORWARD_ROW2,
+            (*ptable).NumEntries as usize,
+        )
+    };
+
+    let entries = unsafe { (*ptable).NumEntries };
+    // The table holds the routes of the calling thread's compartment
+    let compartment = unsafe { GetCurrentThreadCompartmentId() };
+    let res = (0..entries)
+        .map(|idx| unsafe { (*prows)[idx as usize] })
+        .filter_map(|row| unsafe { row_to_route(&row) })
+        .map(|mut route| {
+            route.reported.compartment = Some(compartment);
+            route
+        })
+        .collect::<Vec<_>>();
+    unsafe { FreeMibTable(ptable as *mut _ as *mut _) };
+    debug_event!(count = res.len(), "listed routes");
+    Ok(res)
 }
 
+/// Normalized flags from the origin and protocol of the row `route` was read from.
+pub(crate) fn reported_flags(route: &Route) -> crate::RouteFlags {
+    let mut flags = crate::RouteFlags::empty();
+    let Some(row) = &route.reported.row else {
+        return flags;
+    };
+    flags.insert(crate::RouteFlags::UP);
+    flags.set(crate::RouteFlags::STATIC, row.origin == NlroManual);
+    flags.set(crate::RouteFlags::DYNAMIC, row.protocol == MIB_IPPROTO_ICMP);
+    flags
+}
+
+impl Notifier {
+    fn notify(&self, event: RouteChange) {
+        match self.policy {
+            OverflowPolicy::Block => _ = self.sender.send(event),
+            OverflowPolicy::DropOldest => {
+                let mut event = event;
+                while let Err(TrySendError::Full(rejected)) = self.sender.try_send(event) {
+                    _ = self.receiver.try_recv();
+                    event = rejected;
+                }
+            }
+            OverflowPolicy::DropNewest => _ = self.sender.try_send(event),
+            OverflowPolicy::Error => {
+                if let Err(TrySendError::Full(_)) = self.sender.try_send(event) {
+                    self.overflowed.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+}
 unsafe extern "system" fn callback(
     callercontext: *const core::ffi::c_void,
     row: *const MIB_IPFORWARD_ROW2,
     notificationtype: MIB_NOTIFICATION_TYPE,
 ) {
-    let tx = &*(callercontext as *const Sender<RouteChange>);
+    let notifier = &*(callercontext as *const Notifier);
 
     if let Some(route) = ffi::row_to_route(row) {
         let event = match notificationtype {
-            n if n == MibParameterNotification => RouteChange::Change(route),
+            n if n == MibParameterNotification => RouteChange::Change {
+                old: None,
+                new: route,
+            },
             n if n == MibAddInstance => RouteChange::Add(route),
             n if n == MibDeleteInstance => RouteChange::Delete(route),
             _ => return,
         };
-        _ = tx.send(event)
+        // Filtered events still update the cache
+        let event = match &mut *notifier.cache.lock().unwrap() {
+            Some(cache) => cache.track(event),
+            None => Some(event),
+        };
+        let Some(event) = event else {
+            return;
+        };
+        if notifier.ignore_system_routes && (*row).Origin != NlroManual {
+            return;
+        }
+        notifier.notify(event)
     }
 }